@@ -0,0 +1,30 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use memory_module::prelude::*;
+
+fn bench_sqlite_save(c: &mut Criterion) {
+    let path = std::env::temp_dir().join(format!("mm_bench_{}.db", uuid::Uuid::new_v4()));
+    let backend = SqliteBackend::new(format!("sqlite://{}?mode=rwc", path.display()));
+    let mut store = MemoryStore::default();
+    for i in 0..1000 {
+        store.add_memory(Memory::new(vec![i as f32 * 0.001; 64], 0.0, 25.0, 1.0));
+    }
+
+    c.bench_function("sqlite_save_1000", |b| {
+        b.iter(|| store.save(&backend).expect("save"))
+    });
+
+    // Baseline: a new backend per save pays for a runtime and a pool every
+    // time, as every save did before the pool was shared.
+    c.bench_function("sqlite_save_1000_fresh_backend", |b| {
+        b.iter(|| {
+            let backend = SqliteBackend::new(format!("sqlite://{}?mode=rwc", path.display()));
+            store.save(&backend).expect("save")
+        })
+    });
+
+    drop(backend);
+    let _ = std::fs::remove_file(&path);
+}
+
+criterion_group!(sqlite_benches, bench_sqlite_save);
+criterion_main!(sqlite_benches);
@@ -1,5 +1,8 @@
 use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use memory_module::prelude::*;
+use memory_module::test_util::{random_memory, random_store};
+
+const DIM: usize = 3;
 
 fn bench_memory_store_insert(c: &mut Criterion) {
     let profile = AgentProfile::default();
@@ -8,9 +11,8 @@ fn bench_memory_store_insert(c: &mut Criterion) {
         b.iter_batched(
             || MemoryStore::new(profile.clone(), state.clone()),
             |mut store| {
-                for _ in 0..1000 {
-                    let mem = Memory::new(vec![0.1, 0.2, 0.3], 0.0, 0.0, 1.0);
-                    store.add_memory(mem);
+                for i in 0..1000 {
+                    store.add_memory(random_memory(DIM, i));
                 }
             },
             BatchSize::SmallInput,
@@ -18,27 +20,65 @@ fn bench_memory_store_insert(c: &mut Criterion) {
     });
 }
 
-fn bench_memory_store_query(c: &mut Criterion) {
+fn bench_memory_store_add_memories(c: &mut Criterion) {
     let profile = AgentProfile::default();
     let state = AgentState::default();
-    c.bench_function("memory_store_query", |b| {
+    c.bench_function("memory_store_add_memories", |b| {
         b.iter_batched(
             || {
-                let mut store = MemoryStore::new(profile.clone(), state.clone());
-                for _ in 0..1000 {
-                    let mem = Memory::new(vec![0.1, 0.2, 0.3], 0.0, 0.0, 1.0);
-                    store.add_memory(mem);
-                }
-                store
+                let store = MemoryStore::new(profile.clone(), state.clone());
+                let memories: Vec<Memory> = (0..1000).map(|i| random_memory(DIM, i)).collect();
+                (store, memories)
+            },
+            |(mut store, memories)| {
+                store.add_memories(memories);
             },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn bench_memory_store_query(c: &mut Criterion) {
+    let query = random_memory(DIM, 0).semantic_vector;
+    c.bench_function("memory_store_query", |b| {
+        b.iter_batched(
+            || random_store(1000, DIM, 42),
             |mut store| {
-                let _ = store.find_relevant(&[0.1, 0.2, 0.3], 10).unwrap();
+                let _ = store.find_relevant(&query, 10).unwrap();
             },
             BatchSize::SmallInput,
         )
     });
 }
 
+fn bench_memory_store_query_ids(c: &mut Criterion) {
+    let mut store = random_store(1000, 384, 42);
+    let query = random_memory(384, 0).semantic_vector;
+
+    let mut group = c.benchmark_group("memory_store_query_384d");
+    group.bench_function("find_relevant", |b| {
+        b.iter(|| store.find_relevant(&query, 10).unwrap())
+    });
+    group.bench_function("find_relevant_ids", |b| {
+        b.iter(|| store.find_relevant_ids(&query, 10).unwrap())
+    });
+    group.finish();
+}
+
+/// Compares serial and parallel retention passes over a 50k-memory store.
+///
+/// The threshold of `0.0` prunes nothing, so every iteration does the same
+/// work.
+#[cfg(feature = "parallel")]
+fn bench_memory_store_maintain_parallel(c: &mut Criterion) {
+    let mut store = random_store(50_000, DIM, 42);
+
+    let mut group = c.benchmark_group("memory_store_maintain_50k");
+    group.bench_function("maintain", |b| b.iter(|| store.maintain(0.0)));
+    group.bench_function("maintain_parallel", |b| b.iter(|| store.maintain_parallel(0.0)));
+    group.finish();
+}
+
 #[cfg(feature = "concurrent")]
 fn bench_concurrent_store_insert(c: &mut Criterion) {
     let profile = AgentProfile::default();
@@ -47,9 +87,8 @@ fn bench_concurrent_store_insert(c: &mut Criterion) {
         b.iter_batched(
             || ConcurrentMemoryStore::new(profile.clone(), state.clone()),
             |store| {
-                for _ in 0..1000 {
-                    let mem = Memory::new(vec![0.1, 0.2, 0.3], 0.0, 0.0, 1.0);
-                    store.add_memory(mem);
+                for i in 0..1000 {
+                    store.add_memory(random_memory(DIM, i));
                 }
             },
             BatchSize::SmallInput,
@@ -59,20 +98,20 @@ fn bench_concurrent_store_insert(c: &mut Criterion) {
 
 #[cfg(feature = "concurrent")]
 fn bench_concurrent_store_query(c: &mut Criterion) {
+    let query = random_memory(DIM, 0).semantic_vector;
     let profile = AgentProfile::default();
     let state = AgentState::default();
     c.bench_function("concurrent_store_query", |b| {
         b.iter_batched(
             || {
                 let store = ConcurrentMemoryStore::new(profile.clone(), state.clone());
-                for _ in 0..1000 {
-                    let mem = Memory::new(vec![0.1, 0.2, 0.3], 0.0, 0.0, 1.0);
-                    store.add_memory(mem);
+                for i in 0..1000 {
+                    store.add_memory(random_memory(DIM, i));
                 }
                 store
             },
             |store| {
-                let _ = store.find_relevant(&[0.1, 0.2, 0.3], 10).unwrap();
+                let _ = store.find_relevant(&query, 10).unwrap();
             },
             BatchSize::SmallInput,
         )
@@ -87,9 +126,8 @@ fn bench_sharded_store_insert(c: &mut Criterion) {
         b.iter_batched(
             || ShardedMemoryStore::new(profile.clone(), state.clone(), 4),
             |store| {
-                for _ in 0..1000 {
-                    let mem = Memory::new(vec![0.1, 0.2, 0.3], 0.0, 0.0, 1.0);
-                    store.add_memory(mem);
+                for i in 0..1000 {
+                    store.add_memory(random_memory(DIM, i));
                 }
             },
             BatchSize::SmallInput,
@@ -99,31 +137,60 @@ fn bench_sharded_store_insert(c: &mut Criterion) {
 
 #[cfg(feature = "concurrent")]
 fn bench_sharded_store_query(c: &mut Criterion) {
+    let query = random_memory(DIM, 0).semantic_vector;
     let profile = AgentProfile::default();
     let state = AgentState::default();
     c.bench_function("sharded_store_query", |b| {
         b.iter_batched(
             || {
                 let store = ShardedMemoryStore::new(profile.clone(), state.clone(), 4);
-                for _ in 0..1000 {
-                    let mem = Memory::new(vec![0.1, 0.2, 0.3], 0.0, 0.0, 1.0);
-                    store.add_memory(mem);
+                for i in 0..1000 {
+                    store.add_memory(random_memory(DIM, i));
                 }
                 store
             },
             |store| {
-                let _ = store.find_relevant(&[0.1, 0.2, 0.3], 10).unwrap();
+                let _ = store.find_relevant(&query, 10).unwrap();
             },
             BatchSize::SmallInput,
         )
     });
 }
 
-criterion_group!(basic_benches, bench_memory_store_insert, bench_memory_store_query);
+/// Compares scoring shards on one thread against the global rayon pool.
+#[cfg(all(feature = "concurrent", feature = "parallel"))]
+fn bench_sharded_store_query_parallel(c: &mut Criterion) {
+    let dim = 128;
+    let store = ShardedMemoryStore::new(AgentProfile::default(), AgentState::default(), 8);
+    for i in 0..20_000 {
+        store.add_memory(random_memory(dim, i));
+    }
+    let query = random_memory(dim, 0).semantic_vector;
+    let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+
+    let mut group = c.benchmark_group("sharded_store_query_20k");
+    group.bench_function("sequential", |b| {
+        b.iter(|| single.install(|| store.find_relevant(&query, 10).unwrap()))
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| store.find_relevant(&query, 10).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(basic_benches, bench_memory_store_insert, bench_memory_store_add_memories, bench_memory_store_query, bench_memory_store_query_ids);
 #[cfg(feature = "concurrent")]
 criterion_group!(concurrent_benches, bench_concurrent_store_insert, bench_concurrent_store_query, bench_sharded_store_insert, bench_sharded_store_query);
+#[cfg(feature = "parallel")]
+criterion_group!(parallel_benches, bench_memory_store_maintain_parallel);
+#[cfg(all(feature = "concurrent", feature = "parallel"))]
+criterion_group!(sharded_parallel_benches, bench_sharded_store_query_parallel);
 
-#[cfg(feature = "concurrent")]
+#[cfg(all(feature = "concurrent", feature = "parallel"))]
+criterion_main!(basic_benches, concurrent_benches, parallel_benches, sharded_parallel_benches);
+#[cfg(all(feature = "concurrent", not(feature = "parallel")))]
 criterion_main!(basic_benches, concurrent_benches);
-#[cfg(not(feature = "concurrent"))]
+#[cfg(all(not(feature = "concurrent"), feature = "parallel"))]
+criterion_main!(basic_benches, parallel_benches);
+#[cfg(not(any(feature = "concurrent", feature = "parallel")))]
 criterion_main!(basic_benches);
@@ -43,10 +43,8 @@ fn main() -> Result<()> {
         );
         
         // Add some metadata
-        memory.metadata = serde_json::json!({
-            "description": description,
-            "source": "example"
-        });
+        memory.set_meta("description", description);
+        memory.set_meta("source", "example");
         
         // Make the traumatic event older to test retention
         if description == "traumatic_event" {
@@ -77,20 +75,13 @@ fn main() -> Result<()> {
     let relevant = store.find_relevant(&query, 3)?;
     
     println!("\nTop relevant memories:");
-    for (score, memory) in relevant {
-        let description = memory.metadata.get("description")
-            .and_then(|v| v.as_str())
+    for result in relevant {
+        let description = result.memory.get_meta_str("description")
             .unwrap_or("<no description>");
-            
-        let retention = memory.calculate_retention(
-            Utc::now(),
-            store.agent_state(),
-            store.agent_profile(),
-        );
         
         println!(
-            "- {} (score: {:.3}, retention: {:.3})",
-            description, score, retention
+            "- {} (score: {:.3}, similarity: {:.3}, retention: {:.3})",
+            description, result.score, result.similarity, result.retention
         );
     }
     
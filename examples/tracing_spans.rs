@@ -0,0 +1,27 @@
+//! Logs the `tracing` spans emitted by queries and maintenance
+
+use memory_module::prelude::*;
+use memory_module::error::Result;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+fn main() -> Result<()> {
+    // Print every span when it closes, with its fields and timing
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
+
+    let mut store = MemoryStore::default();
+    for i in 0..100 {
+        let angle = i as f32 * 0.1;
+        store.add_memory(Memory::new(vec![angle.cos(), angle.sin()], 0.0, 25.0, 1.0));
+    }
+
+    let results = store.find_relevant(&[1.0, 0.0], 5)?;
+    println!("Retrieved {} memories", results.len());
+
+    let pruned = store.maintain(0.001);
+    println!("Pruned {} memories", pruned);
+
+    Ok(())
+}
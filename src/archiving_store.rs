@@ -0,0 +1,207 @@
+//! Two-tier storage that keeps weak memories out of the query path.
+//!
+//! [`ArchivingMemoryStore`] holds an active [`MemoryStore`] that queries
+//! scan, and a cold archive of memories whose retention has faded.
+
+use crate::error::{MemoryError, Result};
+use crate::model::{AgentState, Memory};
+use crate::store::{MemoryStore, RetrievalResult};
+use uuid::Uuid;
+
+/// A [`MemoryStore`] that demotes faded memories to a cold archive.
+///
+/// Queries only scan the active tier, so long-lived agents do not pay for
+/// memories they are unlikely to recall. [`demote`](ArchivingMemoryStore::demote)
+/// moves every active memory whose retention is below the demote threshold
+/// into the archive, and [`promote`](ArchivingMemoryStore::promote) brings
+/// one back by ID. The archive is an ordinary [`MemoryStore`] sharing the
+/// active tier's profile and agent state, so it can be inspected or saved
+/// like any other store.
+///
+/// # Example
+///
+/// ```
+/// use memory_module::prelude::*;
+///
+/// let mut store = ArchivingMemoryStore::new(MemoryStore::default(), 1e-6).unwrap();
+/// let mut faded = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+/// faded.memory_strength = 0.0;
+/// let faded_id = store.add_memory(faded);
+///
+/// assert_eq!(store.demote(), 1);
+/// assert!(store.active().get_memory(&faded_id).is_none());
+/// assert!(store.get_memory(&faded_id).is_some());
+/// assert!(store.active().get_memory(&faded_id).is_some());
+/// ```
+pub struct ArchivingMemoryStore {
+    active: MemoryStore,
+    archive: MemoryStore,
+    demote_threshold: f32,
+}
+
+impl ArchivingMemoryStore {
+    /// Wraps `active` with an empty archive.
+    ///
+    /// The archive copies the active store's profile, agent state and
+    /// settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `demote_threshold` is not
+    /// within `0.0..=1.0`.
+    pub fn new(active: MemoryStore, demote_threshold: f32) -> Result<Self> {
+        let mut store = Self {
+            archive: active.empty_like(),
+            active,
+            demote_threshold: 0.0,
+        };
+        store.set_demote_threshold(demote_threshold)?;
+        Ok(store)
+    }
+
+    /// Sets the retention below which [`demote`](ArchivingMemoryStore::demote)
+    /// archives a memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `threshold` is not within
+    /// `0.0..=1.0`.
+    pub fn set_demote_threshold(&mut self, threshold: f32) -> Result<()> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(MemoryError::invalid_param("demote_threshold", threshold));
+        }
+        self.demote_threshold = threshold;
+        Ok(())
+    }
+
+    /// Gets the retention below which memories are archived
+    pub fn demote_threshold(&self) -> f32 {
+        self.demote_threshold
+    }
+
+    /// Gets the active tier, which queries scan
+    pub fn active(&self) -> &MemoryStore {
+        &self.active
+    }
+
+    /// Gets the active tier mutably, e.g. to change its settings
+    pub fn active_mut(&mut self) -> &mut MemoryStore {
+        &mut self.active
+    }
+
+    /// Gets the cold archive
+    pub fn archive(&self) -> &MemoryStore {
+        &self.archive
+    }
+
+    /// Adds a new memory to the active tier.
+    pub fn add_memory(&mut self, memory: Memory) -> Uuid {
+        self.active.add_memory(memory)
+    }
+
+    /// Moves every active memory whose retention is below the demote
+    /// threshold into the archive, returning how many were moved.
+    ///
+    /// Demotion goes through [`MemoryStore::maintain_collect`], so observers
+    /// of the active tier see each demoted memory as pruned.
+    pub fn demote(&mut self) -> usize {
+        let demoted = self.active.maintain_collect(self.demote_threshold);
+        let count = demoted.len();
+        self.archive.add_memories(demoted);
+        count
+    }
+
+    /// Moves an archived memory back into the active tier.
+    ///
+    /// The memory keeps its retention, so unless it is strengthened it is
+    /// archived again by the next [`demote`](ArchivingMemoryStore::demote).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::NotFound`] if the memory is not archived.
+    pub fn promote(&mut self, id: &Uuid) -> Result<()> {
+        let memory = self
+            .archive
+            .take_memory(id)
+            .ok_or_else(|| MemoryError::not_found(id))?;
+        self.active.add_memory(memory);
+        Ok(())
+    }
+
+    /// Gets a memory by ID, promoting it first if it is archived.
+    pub fn get_memory(&mut self, id: &Uuid) -> Option<&Memory> {
+        if self.active.get_memory(id).is_none() {
+            self.promote(id).ok()?;
+        }
+        self.active.get_memory(id)
+    }
+
+    /// Finds relevant memories in the active tier; see
+    /// [`MemoryStore::find_relevant`]. Archived memories are never returned,
+    /// so an empty active tier returns no results.
+    pub fn find_relevant(&mut self, query_vector: &[f32], limit: usize) -> Result<Vec<RetrievalResult>> {
+        self.active.find_relevant(query_vector, limit)
+    }
+
+    /// Updates the agent's state in both tiers.
+    pub fn update_agent_state(&mut self, state: AgentState) {
+        self.archive.update_agent_state(state.clone());
+        self.active.update_agent_state(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn faded(vector: Vec<f32>) -> Memory {
+        let mut memory = Memory::new(vector, 0.0, 25.0, 1.0);
+        memory.memory_strength = 0.0;
+        memory
+    }
+
+    #[test]
+    fn test_demote_moves_faded_memories_to_archive() {
+        let mut store = ArchivingMemoryStore::new(MemoryStore::default(), 1e-6).unwrap();
+        let strong = store.add_memory(Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0));
+        let weak = store.add_memory(faded(vec![1.0, 0.0]));
+
+        assert_eq!(store.demote(), 1);
+        assert!(store.active().get_memory(&strong).is_some());
+        assert!(store.active().get_memory(&weak).is_none());
+        assert!(store.archive().get_memory(&weak).is_some());
+        assert_eq!(store.demote(), 0);
+        assert!(ArchivingMemoryStore::new(MemoryStore::default(), 1.5).is_err());
+    }
+
+    #[test]
+    fn test_archived_memories_are_not_queried() {
+        let mut store = ArchivingMemoryStore::new(MemoryStore::default(), 1e-6).unwrap();
+        let strong = store.add_memory(Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0));
+        let weak = store.add_memory(faded(vec![1.0, 0.0]));
+        store.demote();
+
+        let results = store.find_relevant(&[1.0, 0.0], 5).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].memory.id, strong);
+        assert_eq!(store.archive().get_memory(&weak).unwrap().retrieval_count, 0);
+    }
+
+    #[test]
+    fn test_promote_restores_archived_memory() {
+        let mut store = ArchivingMemoryStore::new(MemoryStore::default(), 1e-6).unwrap();
+        let weak = store.add_memory(faded(vec![1.0, 0.0]));
+        store.demote();
+
+        store.promote(&weak).unwrap();
+        assert!(store.archive().get_memory(&weak).is_none());
+        let results = store.find_relevant(&[1.0, 0.0], 5).unwrap();
+        assert_eq!(results[0].memory.id, weak);
+        assert!(store.promote(&weak).unwrap_err().is_not_found());
+
+        store.demote();
+        assert_eq!(store.get_memory(&weak).map(|m| m.id), Some(weak));
+        assert!(store.active().get_memory(&weak).is_some());
+        assert!(store.get_memory(&Uuid::new_v4()).is_none());
+    }
+}
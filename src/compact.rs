@@ -0,0 +1,155 @@
+//! Half-precision storage for memory vectors.
+//!
+//! [`CompactMemory`] keeps a memory's semantic vector as `f16`, halving the
+//! space the vector takes at the cost of about three significant digits of
+//! precision. Vectors are widened to `f32` on the fly when compared.
+
+use crate::model::Memory;
+use crate::similarity::SimilarityMetric;
+use half::f16;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Converts an `f32` vector to half precision, rounding each component to
+/// the nearest `f16`.
+///
+/// Components beyond the `f16` range (about ±65504) become infinite.
+pub fn to_f16(vector: &[f32]) -> Vec<f16> {
+    vector.iter().map(|&v| f16::from_f32(v)).collect()
+}
+
+/// Widens a half-precision vector back to `f32`.
+pub fn to_f32(vector: &[f16]) -> Vec<f32> {
+    vector.iter().map(|v| v.to_f32()).collect()
+}
+
+/// A [`Memory`] whose semantic vector is stored in half precision.
+///
+/// The vector is held as `f16` and the wrapped memory's own
+/// `semantic_vector` is left empty; [`into_memory`](CompactMemory::into_memory)
+/// restores a full-precision memory. Similarity is computed in `f32` without
+/// materializing the widened vector, so a query ranks compact memories
+/// almost exactly as it would the originals.
+///
+/// # Example
+///
+/// ```
+/// use memory_module::prelude::*;
+///
+/// let memory = Memory::new(vec![0.6, 0.8], 0.0, 25.0, 1.0);
+/// let compact = CompactMemory::from(memory.clone());
+///
+/// let exact = SimilarityMetric::Cosine.similarity(&[1.0, 0.0], &memory.semantic_vector);
+/// let approx = compact.similarity(&[1.0, 0.0], SimilarityMetric::Cosine);
+/// assert!((exact - approx).abs() < 1e-3);
+/// assert_eq!(compact.into_memory().id, memory.id);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactMemory {
+    vector: Vec<f16>,
+    memory: Memory,
+}
+
+impl CompactMemory {
+    /// Gets the memory's ID
+    pub fn id(&self) -> Uuid {
+        self.memory.id
+    }
+
+    /// Gets the half-precision semantic vector
+    pub fn vector(&self) -> &[f16] {
+        &self.vector
+    }
+
+    /// Gets the semantic vector widened to `f32`
+    pub fn vector_f32(&self) -> Vec<f32> {
+        to_f32(&self.vector)
+    }
+
+    /// Gets the memory's other fields; its `semantic_vector` is empty while
+    /// the memory is compact.
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Computes the similarity between `query` and this memory's vector
+    /// under `metric`.
+    ///
+    /// Mismatched lengths are handled as by [`SimilarityMetric::similarity`].
+    pub fn similarity(&self, query: &[f32], metric: SimilarityMetric) -> f32 {
+        if query.is_empty() || self.vector.is_empty() || query.len() != self.vector.len() {
+            return metric.similarity(query, &[]);
+        }
+        let (mut dot, mut query_sq, mut vector_sq) = (0.0f32, 0.0f32, 0.0f32);
+        for (&q, v) in query.iter().zip(&self.vector) {
+            let v = v.to_f32();
+            dot += q * v;
+            query_sq += q * q;
+            vector_sq += v * v;
+        }
+        metric.similarity_from_dot(dot, query_sq.sqrt(), vector_sq.sqrt())
+    }
+
+    /// Converts back to a full-precision [`Memory`].
+    pub fn into_memory(self) -> Memory {
+        let mut memory = self.memory;
+        memory.semantic_vector = to_f32(&self.vector);
+        memory
+    }
+}
+
+impl From<Memory> for CompactMemory {
+    fn from(mut memory: Memory) -> Self {
+        let vector = to_f16(&std::mem::take(&mut memory.semantic_vector));
+        Self { vector, memory }
+    }
+}
+
+impl From<CompactMemory> for Memory {
+    fn from(compact: CompactMemory) -> Self {
+        compact.into_memory()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_similarity_matches_f32_within_tolerance() {
+        let query: Vec<f32> = (0..64).map(|i| ((i * 7 % 13) as f32 - 6.0) / 6.0).collect();
+        for seed in 0..20 {
+            let vector: Vec<f32> = (0..64).map(|i| (((i + seed) * 11 % 17) as f32 - 8.0) / 8.0).collect();
+            let memory = Memory::new(vector, 0.0, 25.0, 1.0);
+            let compact = CompactMemory::from(memory.clone());
+            for metric in [SimilarityMetric::Cosine, SimilarityMetric::DotProduct, SimilarityMetric::NegativeL2] {
+                let exact = metric.similarity(&query, &memory.semantic_vector);
+                let approx = compact.similarity(&query, metric);
+                assert!((exact - approx).abs() <= 1e-3 * exact.abs().max(1.0), "{:?}: {} vs {}", metric, exact, approx);
+            }
+        }
+        let compact = CompactMemory::from(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+        assert_eq!(compact.similarity(&[1.0, 0.0, 0.0], SimilarityMetric::Cosine), 0.0);
+        assert_eq!(compact.similarity(&[1.0], SimilarityMetric::NegativeL2), f32::MIN);
+    }
+
+    #[test]
+    fn test_compact_memory_round_trips() {
+        let memory = Memory::new(vec![0.25, -0.5, 0.125], 0.3, 25.0, 0.8).with_metadata("kind", "event");
+        let compact = CompactMemory::from(memory.clone());
+        assert!(compact.memory().semantic_vector.is_empty());
+        assert_eq!(compact.id(), memory.id);
+        // These components are exactly representable in f16.
+        assert_eq!(compact.vector_f32(), memory.semantic_vector);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&compact).unwrap();
+            let decoded: CompactMemory = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded, compact);
+        }
+        assert_eq!(Memory::from(compact), memory);
+    }
+}
@@ -1,3 +1,9 @@
+//! A memory store that can be shared between threads.
+//!
+//! [`ConcurrentMemoryStore`] keeps its memories in a `DashMap`, so memories
+//! can be added, retrieved and pruned through `&self` from many threads at
+//! once without an outer lock.
+
 #![cfg(feature = "concurrent")]
 
 use crate::error::{MemoryError, Result};
@@ -5,8 +11,12 @@ use crate::model::{AgentProfile, AgentState, Memory};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use chrono::Utc;
-use crate::simd_utils;
+use crate::observer::StoreObserver;
+use crate::simd;
+use crate::store::{rank_order, RetrievalResult};
 use dashmap::DashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Instant;
 use uuid::Uuid;
 
 /// Thread-safe memory store using `DashMap` for concurrent access.
@@ -14,7 +24,9 @@ use uuid::Uuid;
 pub struct ConcurrentMemoryStore {
     memories: DashMap<Uuid, Memory>,
     agent_profile: AgentProfile,
-    agent_state: AgentState,
+    agent_state: RwLock<AgentState>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    observers: RwLock<Vec<Arc<dyn StoreObserver>>>,
 }
 
 impl ConcurrentMemoryStore {
@@ -23,14 +35,40 @@ impl ConcurrentMemoryStore {
         Self {
             memories: DashMap::new(),
             agent_profile,
-            agent_state,
+            agent_state: RwLock::new(agent_state),
+            observers: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Registers an observer that is notified of adds, retrievals, removals
+    /// and prunes.
+    ///
+    /// Observers may be invoked from several threads at once, so any state
+    /// they keep must use interior mutability. Registering an observer from
+    /// within an observer callback deadlocks.
+    pub fn add_observer(&self, observer: Arc<dyn StoreObserver>) {
+        self.observers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(observer);
+    }
+
+    /// Invokes `f` on every registered observer.
+    fn notify(&self, f: impl Fn(&dyn StoreObserver)) {
+        let observers = self.observers.read().unwrap_or_else(PoisonError::into_inner);
+        for observer in observers.iter() {
+            f(observer.as_ref());
         }
     }
 
     /// Adds a new memory to the store.
     pub fn add_memory(&self, memory: Memory) -> Uuid {
         let id = memory.id;
+        // Observers run after the insert, so they can read the new memory
+        // back from the store.
+        let added = memory.clone();
         self.memories.insert(id, memory);
+        self.notify(|observer| observer.on_add(&added));
         id
     }
 
@@ -43,17 +81,30 @@ impl ConcurrentMemoryStore {
     pub fn remove_memory(&self, id: &Uuid) -> Result<()> {
         self.memories
             .remove(id)
-            .map(|_| ())
-            .ok_or_else(|| MemoryError::not_found(id))
+            .ok_or_else(|| MemoryError::not_found(id))?;
+        self.notify(|observer| observer.on_remove(id));
+        Ok(())
     }
 
     /// Finds memories matching a query vector, ordered by relevance.
+    ///
+    /// Scoring does not lock the whole store, so other threads may add or
+    /// remove memories while a query runs. A memory that is removed after it
+    /// was ranked but before its retrieval is recorded is omitted from the
+    /// results rather than returned stale; the query may then return fewer
+    /// than `limit` results even when the store holds more memories. An
+    /// empty store returns no results rather than an error.
     pub fn find_relevant(
         &self,
         query_vector: &[f32],
         limit: usize,
-    ) -> Result<Vec<(f32, Memory)>> {
+    ) -> Result<Vec<RetrievalResult>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let started = Instant::now();
         let now = Utc::now();
+        let agent_state = self.agent_state();
 
         // First pass: score all memories
         let mut scored: Vec<_> = self
@@ -62,29 +113,41 @@ impl ConcurrentMemoryStore {
             .map(|entry| {
                 let id = *entry.key();
                 let mem = entry.value();
-                let similarity = cosine_similarity(query_vector, &mem.semantic_vector);
-                let retention = mem.calculate_retention(now, &self.agent_state, &self.agent_profile);
-                (id, similarity * retention)
+                let similarity = simd::cosine_similarity(query_vector, &mem.semantic_vector);
+                let retention = mem.calculate_retention(now, &agent_state, &self.agent_profile);
+                (id, similarity * retention, similarity, retention, mem.timestamp)
             })
             .collect();
 
-        // Sort by score in descending order
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.sort_by(|a, b| rank_order((a.1, a.4, &a.0), (b.1, b.4, &b.0)));
 
-        let top_n: Vec<_> = scored.into_iter().take(limit).collect();
-
-        // Update retrieval history for top memories
-        for (id, _) in &top_n {
-            if let Some(mut mem) = self.memories.get_mut(id) {
-                mem.record_retrieval(self.agent_profile.rho);
-            }
-        }
-
-        let result = top_n
+        // Record the retrieval and clone under the same entry lock, so a
+        // returned memory always reflects its updated retrieval history.
+        let result: Vec<_> = scored
             .into_iter()
-            .filter_map(|(id, score)| self.memories.get(&id).map(|mem| (score, mem.clone())))
+            .take(limit)
+            .filter_map(|(id, score, similarity, retention, _)| {
+                let mut mem = self.memories.get_mut(&id)?;
+                mem.record_retrieval(self.agent_profile.rho);
+                Some(RetrievalResult {
+                    score,
+                    similarity,
+                    retention,
+                    last_retrieved: mem.last_retrieved,
+                    retrieval_count: mem.retrieval_count,
+                    memory: mem.clone(),
+                })
+            })
             .collect();
 
+        let elapsed = started.elapsed();
+        self.notify(|observer| {
+            for result in &result {
+                observer.on_retrieve(&result.memory.id, result.score);
+            }
+            observer.on_query(result.len(), elapsed);
+        });
+
         Ok(result)
     }
 
@@ -93,7 +156,7 @@ impl ConcurrentMemoryStore {
         &self,
         query_vectors: &[Vec<f32>],
         limit: usize,
-    ) -> Result<Vec<Vec<(f32, Memory)>>> {
+    ) -> Result<Vec<Vec<RetrievalResult>>> {
         query_vectors
             .iter()
             .map(|q| self.find_relevant(q, limit))
@@ -102,19 +165,47 @@ impl ConcurrentMemoryStore {
 
     /// Performs maintenance operations like pruning old memories.
     pub fn maintain(&self, retention_threshold: f32) -> usize {
+        self.maintain_collect(retention_threshold).len()
+    }
+
+    /// Like [`maintain`](ConcurrentMemoryStore::maintain), but returns the
+    /// pruned memories.
+    ///
+    /// Retention is checked again when each memory is removed, so a memory
+    /// replaced by another thread during the scan is only pruned if the
+    /// replacement is also below the threshold.
+    pub fn maintain_collect(&self, retention_threshold: f32) -> Vec<Memory> {
         assert!((0.0..=1.0).contains(&retention_threshold));
         let now = Utc::now();
-        let before = self.memories.len();
-        self.memories.retain(|_id, mem| {
-            let retention = mem.calculate_retention(now, &self.agent_state, &self.agent_profile);
-            retention >= retention_threshold
+        let agent_state = self.agent_state();
+        let expired = |mem: &Memory| {
+            mem.calculate_retention(now, &agent_state, &self.agent_profile) < retention_threshold
+        };
+        let candidates: Vec<Uuid> = self
+            .memories
+            .iter()
+            .filter(|entry| expired(entry.value()))
+            .map(|entry| *entry.key())
+            .collect();
+        let pruned: Vec<Memory> = candidates
+            .iter()
+            .filter_map(|id| self.memories.remove_if(id, |_, mem| expired(mem)))
+            .map(|(_, mem)| mem)
+            .collect();
+        self.notify(|observer| {
+            for mem in &pruned {
+                observer.on_prune(&mem.id);
+            }
         });
-        before - self.memories.len()
+        pruned
     }
 
     /// Updates the agent's state.
+    ///
+    /// The state is swapped atomically behind a lock, so this may be called
+    /// from any thread while other threads are querying the store.
     pub fn update_agent_state(&self, state: AgentState) {
-        self.agent_state = state;
+        *self.agent_state.write().unwrap_or_else(PoisonError::into_inner) = state;
     }
 
     /// Gets the current agent profile.
@@ -122,13 +213,11 @@ impl ConcurrentMemoryStore {
         &self.agent_profile
     }
 
-    /// Gets the current agent state.
-    pub fn agent_state(&self) -> &AgentState {
-        &self.agent_state
+    /// Returns a snapshot of the current agent state.
+    pub fn agent_state(&self) -> AgentState {
+        self.agent_state
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
     }
 }
-
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    simd_utils::cosine_similarity(a, b)
-}
-
@@ -1,5 +1,5 @@
 #[cfg(feature = "faiss")]
-use faiss::{index::flat::FlatIndex, index::id_map::IdMap, metric::MetricType, IndexImpl};
+use faiss::{index::flat::FlatIndex, index::id_map::IdMap, metric::MetricType, selector::IdSelector, Index, IndexImpl};
 #[cfg(feature = "faiss")]
 use std::collections::HashMap;
 #[cfg(feature = "faiss")]
@@ -12,6 +12,7 @@ pub struct FaissIndex {
     dim: usize,
     next_id: u64,
     map: HashMap<u64, Uuid>,
+    ids: HashMap<Uuid, u64>,
 }
 
 #[cfg(feature = "faiss")]
@@ -20,34 +21,114 @@ impl FaissIndex {
     pub fn new(dim: usize) -> faiss::error::Result<Self> {
         let quantizer = FlatIndex::new(dim as u32, MetricType::L2)?;
         let index = IdMap::new(quantizer)?;
-        Ok(Self { index, dim, next_id: 0, map: HashMap::new() })
+        Ok(Self { index, dim, next_id: 0, map: HashMap::new(), ids: HashMap::new() })
     }
 
-    /// Add a vector with the associated memory `Uuid`.
+    /// Add a vector with the associated memory `Uuid`, replacing any vector
+    /// it already has.
     pub fn add_vector(&mut self, id: Uuid, vector: &[f32]) -> faiss::error::Result<()> {
         assert_eq!(vector.len(), self.dim, "Vector dimension mismatch");
+        self.remove_vector(id)?;
         let faiss_id = self.next_id;
         self.next_id += 1;
         self.map.insert(faiss_id, id);
+        self.ids.insert(id, faiss_id);
         self.index.add_with_ids(vector, &[faiss_id])?;
         Ok(())
     }
 
+    /// Remove the vector associated with a memory `Uuid`, if present.
+    pub fn remove_vector(&mut self, id: Uuid) -> faiss::error::Result<()> {
+        if let Some(&faiss_id) = self.ids.get(&id) {
+            let selector = IdSelector::batch(&[faiss_id])?;
+            self.index.remove_ids(&selector)?;
+            self.ids.remove(&id);
+            self.map.remove(&faiss_id);
+        }
+        Ok(())
+    }
+
+    /// Add several vectors in a single FAISS call, replacing any vectors the
+    /// ids already have.
+    pub fn add_vectors(&mut self, entries: &[(Uuid, &[f32])]) -> faiss::error::Result<()> {
+        let mut flat = Vec::with_capacity(entries.len() * self.dim);
+        let mut ids = Vec::with_capacity(entries.len());
+        for (id, vector) in entries {
+            assert_eq!(vector.len(), self.dim, "Vector dimension mismatch");
+            self.remove_vector(*id)?;
+            let faiss_id = self.next_id;
+            self.next_id += 1;
+            self.map.insert(faiss_id, *id);
+            self.ids.insert(*id, faiss_id);
+            flat.extend_from_slice(vector);
+            ids.push(faiss_id);
+        }
+        self.index.add_with_ids(&flat, &ids)?;
+        Ok(())
+    }
+
+    /// Number of vectors in the index.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Whether the index holds no vectors.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
     /// Search for nearest neighbours of the query vector.
+    ///
+    /// `k` is capped at the number of indexed vectors, so asking for more
+    /// neighbours than exist returns every vector rather than padding. A
+    /// query of the wrong dimension returns no results.
     pub fn search(&self, query: &[f32], k: usize) -> faiss::error::Result<Vec<(f32, Uuid)>> {
-        if query.len() != self.dim {
+        let k = k.min(self.len());
+        if query.len() != self.dim || k == 0 {
             return Ok(Vec::new());
         }
         let (distances, ids) = self.index.search(query, k)?;
         let results = distances
             .into_iter()
             .zip(ids.into_iter())
-            .filter_map(|(d, fid)| self.map.get(&fid).map(|uid| (d, *uid)))
+            // FAISS pads missing neighbours with the id -1.
+            .filter_map(|(d, fid)| fid.get().and_then(|fid| self.map.get(&fid)).map(|uid| (d, *uid)))
             .collect();
         Ok(results)
     }
 }
 
+#[cfg(feature = "faiss")]
+impl crate::vector_index::VectorIndex for FaissIndex {
+    fn add_vector(&mut self, id: Uuid, vector: &[f32]) -> crate::error::Result<()> {
+        if vector.len() != self.dim {
+            return Err(crate::error::MemoryError::invalid_param("vector dimension", vector.len()));
+        }
+        Ok(FaissIndex::add_vector(self, id, vector)?)
+    }
+
+    fn add_vectors(&mut self, entries: &[(Uuid, &[f32])]) -> crate::error::Result<()> {
+        if let Some((_, vector)) = entries.iter().find(|(_, v)| v.len() != self.dim) {
+            return Err(crate::error::MemoryError::invalid_param("vector dimension", vector.len()));
+        }
+        Ok(FaissIndex::add_vectors(self, entries)?)
+    }
+
+    fn remove_vector(&mut self, id: Uuid) -> crate::error::Result<()> {
+        Ok(FaissIndex::remove_vector(self, id)?)
+    }
+
+    fn search(&self, query: &[f32], k: usize) -> crate::error::Result<Vec<(f32, Uuid)>> {
+        Ok(FaissIndex::search(self, query, k)?)
+    }
+
+    fn rebuild(&mut self, entries: &[(Uuid, &[f32])]) -> crate::error::Result<()> {
+        let dim = entries.first().map_or(self.dim, |(_, v)| v.len());
+        *self = FaissIndex::new(dim)?;
+        crate::vector_index::VectorIndex::add_vectors(self, entries)
+    }
+}
+
 #[cfg(not(feature = "faiss"))]
 /// Dummy index used when the `faiss` feature is disabled.
 pub struct FaissIndex;
@@ -56,6 +137,46 @@ pub struct FaissIndex;
 impl FaissIndex {
     pub fn new(_dim: usize) -> Result<Self, ()> { Ok(Self) }
     pub fn add_vector(&mut self, _id: uuid::Uuid, _v: &[f32]) -> Result<(), ()> { Ok(()) }
+    pub fn add_vectors(&mut self, _entries: &[(uuid::Uuid, &[f32])]) -> Result<(), ()> { Ok(()) }
+    pub fn remove_vector(&mut self, _id: uuid::Uuid) -> Result<(), ()> { Ok(()) }
     pub fn search(&self, _q: &[f32], _k: usize) -> Result<Vec<(f32, uuid::Uuid)>, ()> { Ok(Vec::new()) }
 }
 
+#[cfg(all(test, feature = "faiss"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_caps_k_at_index_size() {
+        let mut index = FaissIndex::new(2).unwrap();
+        let ids = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        index
+            .add_vectors(&[(ids[0], &[1.0, 0.0]), (ids[1], &[0.0, 1.0]), (ids[2], &[1.0, 1.0])])
+            .unwrap();
+
+        let results = index.search(&[1.0, 0.0], 10).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].1, ids[0]);
+
+        index.remove_vector(ids[0]).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.search(&[1.0, 0.0], 10).unwrap().len(), 2);
+        assert!(FaissIndex::new(2).unwrap().search(&[1.0, 0.0], 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_add_vector_replaces_existing_entry() {
+        let mut index = FaissIndex::new(2).unwrap();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        index.add_vector(a, &[1.0, 0.0]).unwrap();
+        index.add_vector(b, &[0.0, 1.0]).unwrap();
+        index.add_vector(a, &[0.0, 0.9]).unwrap();
+        index.add_vectors(&[(b, &[1.0, 0.0])]).unwrap();
+
+        assert_eq!(index.len(), 2);
+        let results = index.search(&[0.0, 1.0], 10).unwrap();
+        assert_eq!(results.iter().map(|(_, id)| *id).collect::<Vec<_>>(), vec![a, b]);
+        index.remove_vector(a).unwrap();
+        assert_eq!(index.search(&[0.0, 1.0], 10).unwrap().len(), 1);
+    }
+}
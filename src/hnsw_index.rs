@@ -0,0 +1,416 @@
+//! Pure-Rust approximate nearest-neighbour search with HNSW.
+//!
+//! [`HnswIndex`] builds a hierarchical navigable small world graph over the
+//! memory vectors: every vector is linked to its nearest neighbours on the
+//! bottom layer, and a geometrically shrinking subset of vectors is linked
+//! on each layer above. A search descends greedily from the sparse top
+//! layer and then explores the bottom layer best-first, visiting a small
+//! fraction of the vectors. It offers the same interface as the FAISS index
+//! without a C++ dependency, and the store uses it when the `hnsw` feature
+//! is enabled and `faiss` is not.
+
+use crate::error::{MemoryError, Result};
+use crate::vector_index::VectorIndex;
+use ordered_float::OrderedFloat;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use uuid::Uuid;
+
+/// Maximum number of links per node on the layers above the bottom one.
+const MAX_LINKS: usize = 16;
+
+/// Maximum number of links per node on the bottom layer.
+const MAX_LINKS_BOTTOM: usize = 2 * MAX_LINKS;
+
+/// Candidate list size used while inserting.
+const EF_CONSTRUCTION: usize = 100;
+
+/// Minimum candidate list size used while searching.
+const EF_SEARCH: usize = 64;
+
+/// Seed used for level assignment so index construction is reproducible.
+const LEVEL_SEED: u64 = 0x4e5_3eed;
+
+/// One vector in the graph, with its links on every layer it belongs to.
+#[derive(Debug, Clone)]
+struct Node {
+    id: Uuid,
+    vector: Vec<f32>,
+    /// Neighbour indices per layer, from the bottom layer up.
+    links: Vec<Vec<usize>>,
+    /// Whether the vector has been removed; removed nodes still route
+    /// searches but are never returned.
+    removed: bool,
+}
+
+/// Approximate nearest-neighbour index over memory embeddings.
+///
+/// Distances are squared Euclidean (L2) distances, as with the FAISS index,
+/// so callers re-score the returned candidates with their own metric.
+/// Removal leaves a tombstone in the graph. Once tombstones outnumber the
+/// live vectors, the graph is rebuilt from the live vectors, so search cost
+/// and memory stay proportional to the index's size however many upserts
+/// and removals it sees.
+#[derive(Debug, Clone)]
+pub struct HnswIndex {
+    dim: usize,
+    nodes: Vec<Node>,
+    ids: HashMap<Uuid, usize>,
+    entry: Option<usize>,
+    removed: usize,
+    rng: StdRng,
+}
+
+impl HnswIndex {
+    /// Creates an empty index for vectors of dimension `dim`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `dim` is zero.
+    pub fn new(dim: usize) -> Result<Self> {
+        if dim == 0 {
+            return Err(MemoryError::invalid_param("dim", dim));
+        }
+        Ok(Self {
+            dim,
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            entry: None,
+            removed: 0,
+            rng: StdRng::seed_from_u64(LEVEL_SEED),
+        })
+    }
+
+    /// Gets the number of vectors in the index.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns `true` if the index holds no vectors.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Adds a vector with the associated memory `Uuid`, replacing any vector
+    /// already stored for it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if the vector's dimension
+    /// does not match the index.
+    pub fn add_vector(&mut self, id: Uuid, vector: &[f32]) -> Result<()> {
+        if vector.len() != self.dim {
+            return Err(MemoryError::invalid_param("vector.len()", vector.len()));
+        }
+        self.remove_vector(id)?;
+        self.insert(id, vector);
+        Ok(())
+    }
+
+    /// Adds several vectors.
+    ///
+    /// # Errors
+    ///
+    /// As for [`add_vector`](HnswIndex::add_vector); vectors before the
+    /// offending one are kept.
+    pub fn add_vectors(&mut self, entries: &[(Uuid, &[f32])]) -> Result<()> {
+        for (id, vector) in entries {
+            self.add_vector(*id, vector)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the vector associated with a memory `Uuid`, if present.
+    pub fn remove_vector(&mut self, id: Uuid) -> Result<()> {
+        if let Some(index) = self.ids.remove(&id) {
+            self.nodes[index].removed = true;
+            self.removed += 1;
+            if self.removed > self.ids.len() {
+                self.compact();
+            }
+        }
+        Ok(())
+    }
+
+    /// Searches for the approximate `k` nearest neighbours of `query`,
+    /// returning `(squared L2 distance, id)` pairs, closest first.
+    ///
+    /// A query of the wrong dimension returns no results.
+    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<(f32, Uuid)>> {
+        let Some(entry) = self.entry.filter(|_| query.len() == self.dim && k > 0) else {
+            return Ok(Vec::new());
+        };
+        let mut nearest = entry;
+        for layer in (1..self.nodes[entry].links.len()).rev() {
+            nearest = self.greedy_closest(query, nearest, layer);
+        }
+        let ef = EF_SEARCH.max(k).saturating_add(self.removed);
+        Ok(self
+            .search_layer(query, &[nearest], ef, 0)
+            .into_iter()
+            .filter(|&(_, n)| !self.nodes[n].removed)
+            .take(k)
+            .map(|(distance, n)| (distance, self.nodes[n].id))
+            .collect())
+    }
+
+    /// Rebuilds the graph from the live vectors, dropping every tombstone.
+    fn compact(&mut self) {
+        let live: Vec<Node> = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .filter(|node| !node.removed)
+            .collect();
+        self.ids.clear();
+        self.entry = None;
+        self.removed = 0;
+        for node in live {
+            self.insert(node.id, &node.vector);
+        }
+    }
+
+    /// Links a new node for `id` into the graph; `id` must not be present.
+    fn insert(&mut self, id: Uuid, vector: &[f32]) {
+        let level = self.random_level();
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            id,
+            vector: vector.to_vec(),
+            links: vec![Vec::new(); level + 1],
+            removed: false,
+        });
+        self.ids.insert(id, index);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(index);
+            return;
+        };
+        let top = self.nodes[entry].links.len() - 1;
+        let mut nearest = entry;
+        for layer in (level + 1..=top).rev() {
+            nearest = self.greedy_closest(vector, nearest, layer);
+        }
+        let mut entries = vec![nearest];
+        for layer in (0..=level.min(top)).rev() {
+            let candidates = self.search_layer(vector, &entries, EF_CONSTRUCTION, layer);
+            let max_links = if layer == 0 { MAX_LINKS_BOTTOM } else { MAX_LINKS };
+            let neighbours: Vec<usize> = candidates.iter().take(max_links).map(|&(_, n)| n).collect();
+            for &neighbour in &neighbours {
+                self.link(neighbour, index, layer, max_links);
+            }
+            self.nodes[index].links[layer] = neighbours;
+            entries = candidates.into_iter().map(|(_, n)| n).collect();
+        }
+        if level > top {
+            self.entry = Some(index);
+        }
+    }
+
+    /// Draws a node level from the geometric distribution HNSW uses.
+    fn random_level(&mut self) -> usize {
+        let scale = 1.0 / (MAX_LINKS as f64).ln();
+        let uniform: f64 = self.rng.gen_range(f64::EPSILON..1.0);
+        (-uniform.ln() * scale) as usize
+    }
+
+    /// Squared L2 distance between `query` and the vector of `node`.
+    fn distance(&self, query: &[f32], node: usize) -> f32 {
+        query
+            .iter()
+            .zip(&self.nodes[node].vector)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum()
+    }
+
+    /// Walks `layer` from `start` to the node closest to `query`, always
+    /// moving to the closest neighbour.
+    fn greedy_closest(&self, query: &[f32], start: usize, layer: usize) -> usize {
+        let mut current = start;
+        let mut best = self.distance(query, current);
+        loop {
+            let closer = self.nodes[current].links[layer]
+                .iter()
+                .map(|&n| (self.distance(query, n), n))
+                .filter(|&(d, _)| d < best)
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            match closer {
+                Some((d, n)) => {
+                    best = d;
+                    current = n;
+                }
+                None => return current,
+            }
+        }
+    }
+
+    /// Best-first search of `layer` from `entries`, returning up to `ef`
+    /// `(distance, node)` pairs sorted closest first.
+    fn search_layer(&self, query: &[f32], entries: &[usize], ef: usize, layer: usize) -> Vec<(f32, usize)> {
+        let mut visited: HashSet<usize> = entries.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut found = BinaryHeap::new();
+        for &entry in entries {
+            let d = OrderedFloat(self.distance(query, entry));
+            candidates.push(Reverse((d, entry)));
+            found.push((d, entry));
+        }
+        while found.len() > ef {
+            found.pop();
+        }
+
+        while let Some(Reverse((d, node))) = candidates.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|&(worst, _)| d > worst) {
+                break;
+            }
+            for &neighbour in &self.nodes[node].links[layer] {
+                if !visited.insert(neighbour) {
+                    continue;
+                }
+                let d = OrderedFloat(self.distance(query, neighbour));
+                if found.len() < ef || found.peek().is_some_and(|&(worst, _)| d < worst) {
+                    candidates.push(Reverse((d, neighbour)));
+                    found.push((d, neighbour));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+        let mut found: Vec<(f32, usize)> = found.into_iter().map(|(d, n)| (d.0, n)).collect();
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
+        found
+    }
+
+    /// Links `from` to `to` on `layer`, keeping only the `max_links` closest
+    /// neighbours of `from`.
+    fn link(&mut self, from: usize, to: usize, layer: usize, max_links: usize) {
+        self.nodes[from].links[layer].push(to);
+        if self.nodes[from].links[layer].len() > max_links {
+            let origin = self.nodes[from].vector.clone();
+            let mut links = std::mem::take(&mut self.nodes[from].links[layer]);
+            links.sort_by_cached_key(|&n| OrderedFloat(self.distance(&origin, n)));
+            links.truncate(max_links);
+            self.nodes[from].links[layer] = links;
+        }
+    }
+}
+
+impl VectorIndex for HnswIndex {
+    fn add_vector(&mut self, id: Uuid, vector: &[f32]) -> Result<()> {
+        HnswIndex::add_vector(self, id, vector)
+    }
+
+    fn add_vectors(&mut self, entries: &[(Uuid, &[f32])]) -> Result<()> {
+        HnswIndex::add_vectors(self, entries)
+    }
+
+    fn remove_vector(&mut self, id: Uuid) -> Result<()> {
+        HnswIndex::remove_vector(self, id)
+    }
+
+    fn search(&self, query: &[f32], k: usize) -> Result<Vec<(f32, Uuid)>> {
+        HnswIndex::search(self, query, k)
+    }
+
+    fn rebuild(&mut self, entries: &[(Uuid, &[f32])]) -> Result<()> {
+        let dim = entries.first().map_or(self.dim, |(_, v)| v.len());
+        *self = HnswIndex::new(dim)?;
+        HnswIndex::add_vectors(self, entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_vectors(count: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count)
+            .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect()
+    }
+
+    fn exact_top_k(vectors: &[(Uuid, Vec<f32>)], query: &[f32], k: usize) -> Vec<Uuid> {
+        let mut scored: Vec<(f32, Uuid)> = vectors
+            .iter()
+            .map(|(id, v)| (v.iter().zip(query).map(|(a, b)| (a - b) * (a - b)).sum(), *id))
+            .collect();
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+        scored.into_iter().take(k).map(|(_, id)| id).collect()
+    }
+
+    #[test]
+    fn test_hnsw_results_overlap_exact_top_k() {
+        let vectors: Vec<(Uuid, Vec<f32>)> = random_vectors(1000, 16, 7)
+            .into_iter()
+            .map(|v| (Uuid::new_v4(), v))
+            .collect();
+        let mut index = HnswIndex::new(16).unwrap();
+        for (id, vector) in &vectors {
+            index.add_vector(*id, vector).unwrap();
+        }
+        assert_eq!(index.len(), vectors.len());
+
+        let k = 10;
+        let queries = random_vectors(20, 16, 11);
+        let mut hits = 0;
+        for query in &queries {
+            let exact: HashSet<Uuid> = exact_top_k(&vectors, query, k).into_iter().collect();
+            let approx = index.search(query, k).unwrap();
+            assert_eq!(approx.len(), k);
+            assert!(approx.windows(2).all(|w| w[0].0 <= w[1].0));
+            hits += approx.iter().filter(|(_, id)| exact.contains(id)).count();
+        }
+        let recall = hits as f32 / (k * queries.len()) as f32;
+        assert!(recall >= 0.9, "recall {}", recall);
+    }
+
+    #[test]
+    fn test_hnsw_remove_and_dimension_mismatch() {
+        let mut index = HnswIndex::new(2).unwrap();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        index.add_vectors(&[(a, &[1.0, 0.0]), (b, &[0.0, 1.0])]).unwrap();
+        assert_eq!(index.search(&[1.0, 0.1], 1).unwrap()[0].1, a);
+
+        index.remove_vector(a).unwrap();
+        let results = index.search(&[1.0, 0.1], 2).unwrap();
+        assert_eq!(results.iter().map(|(_, id)| *id).collect::<Vec<_>>(), vec![b]);
+
+        assert!(index.add_vector(a, &[1.0]).unwrap_err().is_invalid_parameter());
+        assert!(index.search(&[1.0, 0.0, 0.0], 1).unwrap().is_empty());
+        assert!(HnswIndex::new(0).is_err());
+    }
+
+    #[test]
+    fn test_hnsw_compacts_tombstones_under_churn() {
+        let dim = 8;
+        let ids: Vec<Uuid> = (0..50).map(|_| Uuid::new_v4()).collect();
+        let mut index = HnswIndex::new(dim).unwrap();
+        let rounds = random_vectors(ids.len() * 10, dim, 5);
+        for (round, vectors) in rounds.chunks(ids.len()).enumerate() {
+            // Upsert every id, then add and remove a short-lived vector.
+            for (id, vector) in ids.iter().zip(vectors) {
+                index.add_vector(*id, vector).unwrap();
+                assert!(index.removed <= index.len());
+                assert!(index.nodes.len() <= 2 * index.len());
+            }
+            let temporary = Uuid::new_v4();
+            index.add_vector(temporary, &vectors[round % vectors.len()]).unwrap();
+            index.remove_vector(temporary).unwrap();
+            assert!(index.nodes.len() <= 2 * index.len());
+        }
+        assert_eq!(index.len(), ids.len());
+
+        let latest = &rounds[rounds.len() - ids.len()..];
+        for (id, vector) in ids.iter().zip(latest) {
+            assert_eq!(index.search(vector, 1).unwrap()[0].1, *id);
+        }
+
+        for id in &ids {
+            index.remove_vector(*id).unwrap();
+        }
+        assert!(index.nodes.is_empty());
+        assert!(index.search(&latest[0], 1).unwrap().is_empty());
+    }
+}
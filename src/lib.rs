@@ -48,6 +48,7 @@
 //! let query = vec![0.15, 0.25, 0.35];
 //! ```
 
+#![cfg_attr(feature = "portable_simd", feature(portable_simd))]
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_crate_level_docs)]
 #![doc(html_root_url = "https://docs.rs/memory-module/0.1.0")]
@@ -56,33 +57,68 @@
 
 pub mod error;
 pub mod model;
+pub mod retention;
 pub mod store;
+pub mod archiving_store;
+pub mod similarity;
+pub mod observer;
 pub mod storage;
-pub mod simd_utils;
+pub mod simd;
+pub mod vector_index;
 #[cfg(feature = "concurrent")]
 pub mod concurrent_store;
 #[cfg(feature = "concurrent")]
 pub mod sharded_store;
-#[cfg(any(feature = "faiss"))]
+#[cfg(feature = "faiss")]
 pub mod faiss_index;
+#[cfg(feature = "hnsw")]
+pub mod hnsw_index;
+#[cfg(feature = "pq")]
+pub mod pq;
+#[cfg(feature = "half")]
+pub mod compact;
+#[cfg(feature = "serde")]
 pub mod persistence;
+#[cfg(feature = "serde")]
+pub mod persistent_store;
+#[cfg(feature = "prometheus")]
+pub mod metrics;
 #[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
 pub mod migration;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 
 // Re-exports
 pub use chrono;
-pub use model::{AgentProfile, AgentState, Memory};
-pub use store::MemoryStore;
+pub use model::{AgentProfile, AgentState, Memory, PROFILE_FORMAT_VERSION};
+pub use retention::{DefaultRetentionModel, ExponentialRetentionModel, RetentionModel};
+pub use store::{ConflictPolicy, EvictionPolicy, MaintainProgress, MemoryStore, MetadataPolicy, NoMatchPolicy, RetrievalResult, ScoreNormalization, ScoringPolicy, StoreSummary};
+pub use archiving_store::ArchivingMemoryStore;
+pub use similarity::{SimilarityMetric, SimilarityTransform};
+pub use observer::StoreObserver;
+pub use vector_index::VectorIndex;
+#[cfg(feature = "prometheus")]
+pub use metrics::PrometheusObserver;
 #[cfg(feature = "serde")]
-pub use storage::{FileBackend, StoredData};
+pub use storage::FileBackend;
+pub use storage::{MemoryBackend, StoredData};
+#[cfg(feature = "bincode")]
+pub use storage::BincodeBackend;
 #[cfg(all(feature = "serde", feature = "sqlite"))]
 pub use storage::SqliteBackend;
-pub use storage::StorageBackend;
+#[cfg(all(feature = "serde", feature = "postgres"))]
+pub use storage::PostgresBackend;
+pub use storage::{AsyncStorageBackend, StorageBackend};
 #[cfg(feature = "concurrent")]
 pub use concurrent_store::ConcurrentMemoryStore;
 #[cfg(feature = "concurrent")]
 pub use sharded_store::ShardedMemoryStore;
+#[cfg(feature = "serde")]
 pub use persistence::{Load, Save};
+#[cfg(feature = "serde")]
+pub use persistent_store::PersistentMemoryStore;
+#[cfg(feature = "half")]
+pub use compact::CompactMemory;
 pub use uuid;
 #[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
 pub use migration::run_migrations;
@@ -99,15 +135,28 @@ pub use migration::run_migrations;
 pub mod prelude {
     pub use crate::error::{MemoryError, Result};
     pub use crate::model::{AgentProfile, AgentState, Memory};
-    pub use crate::store::MemoryStore;
+    pub use crate::retention::{DefaultRetentionModel, ExponentialRetentionModel, RetentionModel};
+    pub use crate::store::{ConflictPolicy, EvictionPolicy, MaintainProgress, MemoryStore, MetadataPolicy, NoMatchPolicy, RetrievalResult, ScoreNormalization, ScoringPolicy, StoreSummary};
+    pub use crate::archiving_store::ArchivingMemoryStore;
+    pub use crate::similarity::{SimilarityMetric, SimilarityTransform};
+    pub use crate::observer::StoreObserver;
+    pub use crate::vector_index::VectorIndex;
+    #[cfg(feature = "serde")]
     pub use crate::persistence::{Load, Save};
     pub use crate::StorageBackend;
     #[cfg(feature = "serde")]
     pub use crate::FileBackend;
+    #[cfg(feature = "bincode")]
+    pub use crate::BincodeBackend;
     #[cfg(all(feature = "serde", feature = "sqlite"))]
     pub use crate::SqliteBackend;
+    #[cfg(all(feature = "serde", feature = "postgres"))]
+    pub use crate::PostgresBackend;
+    pub use crate::{MemoryBackend, StoredData};
     #[cfg(feature = "serde")]
-    pub use crate::StoredData;
+    pub use crate::PersistentMemoryStore;
+    #[cfg(feature = "half")]
+    pub use crate::CompactMemory;
     #[cfg(feature = "concurrent")]
     pub use crate::concurrent_store::ConcurrentMemoryStore;
     #[cfg(feature = "concurrent")]
@@ -118,10 +167,7 @@ pub mod prelude {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use approx::assert_relative_eq;
-    #[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
-    use sqlx::AnyPool;
+    use super::prelude::*;
 
     #[test]
     fn test_prelude_reexports() {
@@ -133,12 +179,17 @@ mod tests {
         let _ = Memory::new(vec![], 0.0, 0.0, 0.0);
         let _ = MemoryStore::default();
         // Ensure Save/Load traits are in scope
-        fn assert_save_load<T: Save + Load>() {}
-        let _ = assert_save_load::<MemoryStore>;
+        #[cfg(feature = "serde")]
+        {
+            fn assert_save_load<T: Save + Load>() {}
+            let _ = assert_save_load::<MemoryStore>;
+        }
         #[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
         {
-            let _fn: fn(&sqlx::AnyPool) -> Result<()> = run_migrations;
-            let _ = _fn;
+            async fn assert_run_migrations(pool: &sqlx::AnyPool) -> Result<()> {
+                run_migrations(pool).await
+            }
+            let _ = assert_run_migrations;
         }
     }
 }
@@ -0,0 +1,147 @@
+//! Prometheus metrics exporter.
+//!
+//! [`PrometheusObserver`] implements [`StoreObserver`] and records store
+//! activity in a Prometheus [`Registry`] that can be scraped in the text
+//! exposition format.
+
+use crate::model::Memory;
+use crate::observer::StoreObserver;
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, Registry, TextEncoder};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// A [`StoreObserver`] that records store activity as Prometheus metrics.
+///
+/// The following metrics are exported:
+///
+/// - `memories_total`: memories added
+/// - `retrievals_total`: memories returned by retrieval queries
+/// - `prunes_total`: memories pruned by maintenance
+/// - `find_relevant_duration_seconds`: histogram of retrieval query latency
+///
+/// # Examples
+///
+/// ```
+/// use memory_module::metrics::PrometheusObserver;
+/// use memory_module::prelude::*;
+/// use std::sync::Arc;
+///
+/// let observer = Arc::new(PrometheusObserver::new());
+/// let mut store = MemoryStore::default();
+/// store.add_observer(observer.clone());
+/// store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+///
+/// assert!(observer.encode().contains("memories_total 1"));
+/// ```
+pub struct PrometheusObserver {
+    registry: Registry,
+    memories_total: IntCounter,
+    retrievals_total: IntCounter,
+    prunes_total: IntCounter,
+    find_relevant_duration_seconds: Histogram,
+}
+
+impl PrometheusObserver {
+    /// Creates an observer with its own, fresh [`Registry`].
+    pub fn new() -> Self {
+        Self::with_registry(Registry::new())
+            .expect("metric names are unique within a fresh registry")
+    }
+
+    /// Creates an observer that registers its metrics in an existing registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a metric with the same name is already registered.
+    pub fn with_registry(registry: Registry) -> prometheus::Result<Self> {
+        let memories_total = IntCounter::new("memories_total", "Memories added to the store")?;
+        let retrievals_total =
+            IntCounter::new("retrievals_total", "Memories returned by retrieval queries")?;
+        let prunes_total = IntCounter::new("prunes_total", "Memories pruned by maintenance")?;
+        let find_relevant_duration_seconds = Histogram::with_opts(HistogramOpts::new(
+            "find_relevant_duration_seconds",
+            "Latency of retrieval queries in seconds",
+        ))?;
+
+        registry.register(Box::new(memories_total.clone()))?;
+        registry.register(Box::new(retrievals_total.clone()))?;
+        registry.register(Box::new(prunes_total.clone()))?;
+        registry.register(Box::new(find_relevant_duration_seconds.clone()))?;
+
+        Ok(Self {
+            registry,
+            memories_total,
+            retrievals_total,
+            prunes_total,
+            find_relevant_duration_seconds,
+        })
+    }
+
+    /// Returns the registry holding the exported metrics.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Encodes all registered metrics in the Prometheus text format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        // Encoding gathered metric families into a Vec cannot fail.
+        let _ = TextEncoder::new().encode(&self.registry.gather(), &mut buffer);
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+impl Default for PrometheusObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StoreObserver for PrometheusObserver {
+    fn on_add(&self, _memory: &Memory) {
+        self.memories_total.inc();
+    }
+
+    fn on_retrieve(&self, _id: &Uuid, _score: f32) {
+        self.retrievals_total.inc();
+    }
+
+    fn on_prune(&self, _id: &Uuid) {
+        self.prunes_total.inc();
+    }
+
+    fn on_query(&self, _results: usize, elapsed: Duration) {
+        self.find_relevant_duration_seconds.observe(elapsed.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{AgentProfile, AgentState};
+    use crate::store::MemoryStore;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_metrics_are_exported() {
+        let observer = Arc::new(PrometheusObserver::new());
+        let mut store = MemoryStore::new(AgentProfile::default(), AgentState::default());
+        store.add_observer(observer.clone());
+
+        for i in 0..3 {
+            store.add_memory(Memory::new(vec![i as f32, 1.0], 0.0, 25.0, 1.0));
+        }
+        store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+
+        store.find_relevant(&[1.0, 1.0], 2).unwrap();
+        // Retention never reaches 1.0, so this prunes every memory.
+        let pruned = store.maintain(1.0);
+        assert_eq!(pruned, 4);
+
+        let output = observer.encode();
+        assert!(output.contains("memories_total 4"), "{}", output);
+        assert!(output.contains("retrievals_total 2"), "{}", output);
+        assert!(output.contains("prunes_total 4"), "{}", output);
+        assert!(output.contains("find_relevant_duration_seconds_count 1"), "{}", output);
+    }
+}
@@ -1,3 +1,8 @@
+//! Embedded database migrations.
+//!
+//! The SQL files in the crate's `migrations` directory are compiled into the
+//! library; [`run_migrations`] applies any that a database has not seen yet.
+
 #[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
 use sqlx::AnyPool;
 
@@ -5,7 +10,7 @@ use sqlx::AnyPool;
 use crate::error::{MemoryError, Result};
 
 #[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
-static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("migrations");
+static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
 
 #[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
 /// Run embedded database migrations against the provided connection pool.
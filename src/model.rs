@@ -4,118 +4,14 @@
 //! behaviors that make up the memory system.
 
 use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
 use uuid::Uuid;
 
-use crate::error::Result;
-
-/// Represents the current cognitive and emotional state of an agent.
-///
-/// This state can influence how memories are formed, retained, and retrieved.
-/// All values are normalized to the range [0.0, 1.0].
-///
-/// # Examples
-///
-/// ```
-/// use memory_module::model::AgentState;
-///
-/// // Create a stressed and fatigued agent state
-/// let state = AgentState {
-///     stress: 0.8,    // High stress
-///     fatigue: 0.7,   // High fatigue
-///     focus: 0.3,     // Low focus
-/// };
-/// ```
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct AgentState {
-    /// Current stress level (0.0 = none, 1.0 = maximum stress)
-    ///
-    /// Higher stress levels can negatively impact memory formation and retrieval.
-    pub stress: f32,
-    
-    /// Current fatigue level (0.0 = fully rested, 1.0 = completely fatigued)
-    ///
-    /// Fatigue affects the agent's ability to form and retrieve memories.
-    pub fatigue: f32,
-    
-    /// Current focus level (0.0 = completely distracted, 1.0 = fully focused)
-    ///
-    /// Higher focus improves memory formation and recall accuracy.
-    pub focus: f32,
-}
-
-impl Default for AgentState {
-    /// Creates a default `AgentState` with neutral values.
-    ///
-    /// ```
-    /// use memory_module::model::AgentState;
-    ///
-    /// let state = AgentState::default();
-    /// assert_eq!(state.stress, 0.0);
-    /// assert_eq!(state.fatigue, 0.0);
-    /// assert_eq!(state.focus, 1.0);
-    /// ```
-    fn default() -> Self {
-        Self {
-            stress: 0.0,
-            fatigue: 0.0,
-            focus: 1.0,
-        }
-    }
-}
-
-/// Configuration parameters that define an agent's memory characteristics.
-///
-/// These parameters control how memories are formed, retained, and forgotten.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct AgentProfile {
-    /// Base decay rate for memories (higher = faster decay)
-    ///
-    /// This controls how quickly memories naturally decay over time.
-    /// Typical range: 0.01 (slow decay) to 0.5 (fast decay)
-    pub decay_rate: f32,
-    
-    /// Emotional bias factor (how much emotion affects memory strength)
-    ///
-    /// Higher values make emotional memories more resistant to forgetting.
-    /// Typical range: 0.0 (no effect) to 2.0 (strong effect)
-    pub emotional_bias: f32,
-    
-    /// Capacity factor (affects how quickly memories interfere with each other)
-    ///
-    /// Higher values mean the agent has more limited memory capacity.
-    /// Typical range: 0.1 (large capacity) to 2.0 (limited capacity)
-    pub capacity_factor: f32,
-    
-    /// Interference factor (how much similar memories affect each other)
-    ///
-    /// Higher values mean more interference between similar memories.
-    /// Typical range: 0.0 (no interference) to 1.0 (strong interference)
-    pub interference_factor: f32,
-}
-
-impl Default for AgentProfile {
-    /// Creates a default `AgentProfile` with balanced parameters.
-    ///
-    /// ```
-    /// use memory_module::model::AgentProfile;
-    ///
-    /// let profile = AgentProfile::default();
-    /// assert_eq!(profile.decay_rate, 0.1);
-    /// assert_eq!(profile.emotional_bias, 0.5);
-    /// assert_eq!(profile.capacity_factor, 1.0);
-    /// assert_eq!(profile.interference_factor, 0.3);
-    /// ```
-    fn default() -> Self {
-        Self {
-            decay_rate: 0.1,
-            emotional_bias: 0.5,
-            capacity_factor: 1.0,
-            interference_factor: 0.3,
-        }
-    }
-}
+use crate::error::{MemoryError, Result};
 
 /// Represents a single memory with associated metadata and retrieval history.
 ///
@@ -140,7 +36,8 @@ impl Default for AgentProfile {
 /// // Add metadata
 /// let memory = memory.with_metadata("source", "conversation");
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Memory {
     /// Unique identifier for the memory
     pub id: Uuid,
@@ -182,6 +79,7 @@ pub struct Memory {
     /// Additional metadata as key-value pairs
     ///
     /// This can be used to store application-specific information about the memory.
+    #[cfg_attr(feature = "serde", serde(with = "metadata_serde"))]
     pub metadata: serde_json::Value,
     
     /// History of when this memory was retrieved
@@ -194,14 +92,74 @@ pub struct Memory {
     pub decay_params: DecayParams,
 }
 
+/// (De)serializes memory metadata.
+///
+/// Self-describing formats such as JSON store the value as-is. Compact binary
+/// formats like bincode cannot deserialize an arbitrary `serde_json::Value`,
+/// so for those the metadata is stored as a JSON string instead.
+#[cfg(feature = "serde")]
+mod metadata_serde {
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &serde_json::Value,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            value.serialize(serializer)
+        } else {
+            serializer.serialize_str(&value.to_string())
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<serde_json::Value, D::Error> {
+        if deserializer.is_human_readable() {
+            serde_json::Value::deserialize(deserializer)
+        } else {
+            let json = String::deserialize(deserializer)?;
+            serde_json::from_str(&json).map_err(D::Error::custom)
+        }
+    }
+}
+
+/// Fraction of its post-review strength a memory's `Decay(t)` term may fall
+/// to before it is due for review; see [`Memory::optimal_review_interval`].
+pub const REVIEW_RETENTION_TARGET: f32 = 0.9;
+
+/// Default number of recalls a memory keeps in its `recall_history`.
+pub const DEFAULT_MAX_RECALL_HISTORY: usize = 64;
+
+/// Smallest decay rate [`Memory::new_with_decay`] accepts; lower values are
+/// raised to it.
+pub const MIN_DECAY_ALPHA: f32 = 1e-3;
+
 /// Parameters that control memory decay
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+///
+/// Use [`DecayParams::new`] to construct validated parameters; with
+/// `alpha <= 0` or a negative `beta_0` retention would grow over time
+/// instead of decaying.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DecayParams {
     /// Base decay rate (α)
     pub alpha: f32,
     
     /// Initial time scale (β₀)
     pub beta_0: f32,
+
+    /// Maximum number of recalls kept in `recall_history`
+    ///
+    /// Once the history is full, recording a recall drops the oldest one.
+    #[cfg_attr(feature = "serde", serde(default = "default_max_recall_history"))]
+    pub max_recall_history: usize,
+}
+
+#[cfg(feature = "serde")]
+fn default_max_recall_history() -> usize {
+    DEFAULT_MAX_RECALL_HISTORY
 }
 
 impl Default for DecayParams {
@@ -209,7 +167,53 @@ impl Default for DecayParams {
         Self {
             alpha: 0.8,
             beta_0: 0.01,
+            max_recall_history: DEFAULT_MAX_RECALL_HISTORY,
+        }
+    }
+}
+
+impl DecayParams {
+    /// Creates decay parameters, validating them.
+    ///
+    /// `max_recall_history` is set to [`DEFAULT_MAX_RECALL_HISTORY`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `alpha` is not positive
+    /// or `beta_0` is negative, or if either is not finite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::DecayParams;
+    ///
+    /// let params = DecayParams::new(0.5, 0.02).unwrap();
+    /// assert_eq!(params.alpha, 0.5);
+    /// assert!(DecayParams::new(0.0, 0.02).is_err());
+    /// ```
+    pub fn new(alpha: f32, beta_0: f32) -> Result<Self> {
+        let params = Self {
+            alpha,
+            beta_0,
+            max_recall_history: DEFAULT_MAX_RECALL_HISTORY,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Checks that `alpha` is positive and `beta_0` is non-negative.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] naming the offending field.
+    pub fn validate(&self) -> Result<()> {
+        if !(self.alpha.is_finite() && self.alpha > 0.0) {
+            return Err(MemoryError::invalid_param("alpha", self.alpha));
+        }
+        if !(self.beta_0.is_finite() && self.beta_0 >= 0.0) {
+            return Err(MemoryError::invalid_param("beta_0", self.beta_0));
         }
+        Ok(())
     }
 }
 
@@ -253,6 +257,96 @@ impl Memory {
         }
     }
 
+    /// Creates a new memory with its own decay parameters.
+    ///
+    /// Like [`new`](Memory::new), but lets memories of different intrinsic
+    /// durability decay at different rates. In
+    /// [`calculate_retention`](Memory::calculate_retention) the `Decay(t)`
+    /// term is `(1 + β·t)^-α`, so a higher `alpha` makes the memory fade
+    /// faster and a higher `beta_0` makes that fading start sooner.
+    ///
+    /// Out-of-range parameters are clamped rather than rejected: `alpha` is
+    /// raised to at least [`MIN_DECAY_ALPHA`] and `beta_0` to at least `0.0`,
+    /// with NaN taking those minimums, so retention never grows over time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::{DecayParams, Memory};
+    ///
+    /// // A phone number fades much faster than a life event.
+    /// let phone_number = Memory::new_with_decay(vec![0.1, 0.2], 0.0, 25.0, 0.2, DecayParams::new(2.0, 0.5).unwrap());
+    /// let life_event = Memory::new_with_decay(vec![0.3, 0.4], 0.8, 25.0, 1.0, DecayParams::new(0.3, 0.01).unwrap());
+    /// assert!(phone_number.decay_params.alpha > life_event.decay_params.alpha);
+    /// ```
+    pub fn new_with_decay(
+        semantic_vector: Vec<f32>,
+        emotion: f32,
+        age_at_formation: f64,
+        capacity_weight: f32,
+        decay_params: DecayParams,
+    ) -> Self {
+        let clamp = |value: f32, min: f32| if value.is_nan() { min } else { value.clamp(min, f32::MAX) };
+        let mut memory = Self::new(semantic_vector, emotion, age_at_formation, capacity_weight);
+        memory.decay_params = DecayParams {
+            alpha: clamp(decay_params.alpha, MIN_DECAY_ALPHA),
+            beta_0: clamp(decay_params.beta_0, 0.0),
+            ..decay_params
+        };
+        memory
+    }
+
+    /// Sets `key` in the metadata and returns the memory, for chaining.
+    ///
+    /// See [`set_meta`](Memory::set_meta).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::Memory;
+    ///
+    /// let memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0)
+    ///     .with_metadata("source", "conversation")
+    ///     .with_metadata("turn", 3);
+    /// assert_eq!(memory.get_meta_str("source"), Some("conversation"));
+    /// assert_eq!(memory.get_meta_f64("turn"), Some(3.0));
+    /// ```
+    pub fn with_metadata(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.set_meta(key, value);
+        self
+    }
+
+    /// Sets `key` in the metadata to `value`.
+    ///
+    /// Metadata is expected to be a JSON object; if it holds any other
+    /// value, it is replaced by an object holding just `key`.
+    pub fn set_meta(&mut self, key: &str, value: impl Into<serde_json::Value>) {
+        if !self.metadata.is_object() {
+            self.metadata = serde_json::Value::Object(serde_json::Map::new());
+        }
+        if let Some(map) = self.metadata.as_object_mut() {
+            map.insert(key.to_string(), value.into());
+        }
+    }
+
+    /// Returns the metadata string under `key`, or `None` if the key is
+    /// missing or does not hold a string.
+    pub fn get_meta_str(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key)?.as_str()
+    }
+
+    /// Returns the metadata number under `key` as an `f64`, or `None` if the
+    /// key is missing or does not hold a number.
+    pub fn get_meta_f64(&self, key: &str) -> Option<f64> {
+        self.metadata.get(key)?.as_f64()
+    }
+
+    /// Returns the metadata boolean under `key`, or `None` if the key is
+    /// missing or does not hold a boolean.
+    pub fn get_meta_bool(&self, key: &str) -> Option<bool> {
+        self.metadata.get(key)?.as_bool()
+    }
+
     /// Calculates the current retention strength of the memory.
     ///
     /// The retention strength is a value between 0.0 (completely forgotten)
@@ -269,6 +363,22 @@ impl Memory {
     ///
     /// A value between 0.0 and 1.0 representing the current retention strength.
     ///
+    /// The `Decay(t)` term is `(1 + β·t)^-α` with `t` in days, where
+    /// `β = β₀·(1 + β_c·cortisol_level + β_s·sleep_debt + β_f·fatigue)` and
+    /// the coefficients come from the [`AgentProfile`].
+    ///
+    /// Emotion scales retention by `1 + γ·|emotion|` when `|emotion|`
+    /// exceeds `θ_shock`, giving shocking events flashbulb-like persistence
+    /// whatever their valence, and by `1 + η·emotion` otherwise. Near the
+    /// threshold the two are blended over
+    /// [`shock_blend_width`](AgentProfile::shock_blend_width), so retention
+    /// has no jump there.
+    ///
+    /// A lone memory cannot see its neighbours, so this leaves out
+    /// interference; see [`calculate_retention_with_interference`].
+    ///
+    /// [`calculate_retention_with_interference`]: Memory::calculate_retention_with_interference
+    ///
     /// # Examples
     ///
     /// ```
@@ -285,41 +395,516 @@ impl Memory {
     pub fn calculate_retention(&self, now: DateTime<Utc>, agent_state: &AgentState, profile: &AgentProfile) -> f32 {
         // Time since formation in days
         let t_days = (now - self.timestamp).num_seconds() as f32 / 86_400.0;
-        
+
+        let (static_factor, beta) = self.retention_factors(agent_state, profile);
+
+        // Decay(t)
+        let decay = (1.0 + beta * t_days).powf(-self.decay_params.alpha);
+
+        // Retention calculation
+        let retention = static_factor * decay;
+        // Unlike `clamp`, this also maps NaN to 0.0.
+        #[allow(clippy::manual_clamp)]
+        retention.max(0.0).min(1.0)
+    }
+
+    /// Calculates retention including interference from `similar_memories`
+    /// other memories that overlap with this one.
+    ///
+    /// The result is [`calculate_retention`] multiplied by
+    /// `exp(-κ·s·t)`, where `κ` is [`AgentProfile::kappa`], `s` is
+    /// `similar_memories` and `t` is the time since formation in days, so
+    /// memories in crowded regions of semantic space fade faster. Use
+    /// [`MemoryStore::calculate_retention_with_interference`] to have the
+    /// neighbours counted from a store.
+    ///
+    /// [`calculate_retention`]: Memory::calculate_retention
+    /// [`MemoryStore::calculate_retention_with_interference`]: crate::store::MemoryStore::calculate_retention_with_interference
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::{Memory, AgentState, AgentProfile};
+    /// use chrono::Duration;
+    ///
+    /// let memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// let (state, profile) = (AgentState::default(), AgentProfile::default());
+    /// let later = memory.timestamp + Duration::days(7);
+    ///
+    /// let alone = memory.calculate_retention_with_interference(later, &state, &profile, 0);
+    /// assert_eq!(alone, memory.calculate_retention(later, &state, &profile));
+    /// assert!(memory.calculate_retention_with_interference(later, &state, &profile, 10) < alone);
+    /// ```
+    pub fn calculate_retention_with_interference(
+        &self,
+        now: DateTime<Utc>,
+        agent_state: &AgentState,
+        profile: &AgentProfile,
+        similar_memories: usize,
+    ) -> f32 {
+        self.calculate_retention(now, agent_state, profile) * self.interference_factor(now, profile, similar_memories)
+    }
+
+    /// The `exp(-κ·s·t)` factor of [`calculate_retention_with_interference`].
+    ///
+    /// [`calculate_retention_with_interference`]: Memory::calculate_retention_with_interference
+    pub(crate) fn interference_factor(&self, now: DateTime<Utc>, profile: &AgentProfile, similar_memories: usize) -> f32 {
+        let t_days = ((now - self.timestamp).num_seconds() as f32 / 86_400.0).max(0.0);
+        (-profile.kappa * similar_memories as f32 * t_days).exp()
+    }
+
+    /// Samples [`calculate_retention`] at `steps` evenly spaced instants from
+    /// `from` to `to`, both included.
+    ///
+    /// Returns an empty curve when `steps` is 0 and a single point when
+    /// `steps` is 1 or `from == to`. Sampling does not record a retrieval.
+    ///
+    /// [`calculate_retention`]: Memory::calculate_retention
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::{Memory, AgentState, AgentProfile};
+    ///
+    /// let memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// let (state, profile) = (AgentState::default(), AgentProfile::default());
+    /// let from = memory.timestamp;
+    /// let to = from + chrono::Duration::days(30);
+    ///
+    /// let curve = memory.retention_curve(&state, &profile, from, to, 31);
+    /// assert_eq!(curve.len(), 31);
+    /// assert_eq!(curve.last().unwrap().0, to);
+    /// assert!(curve.windows(2).all(|w| w[1].1 <= w[0].1));
+    /// ```
+    pub fn retention_curve(
+        &self,
+        agent_state: &AgentState,
+        profile: &AgentProfile,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        steps: usize,
+    ) -> Vec<(DateTime<Utc>, f32)> {
+        let sample = |at: DateTime<Utc>| (at, self.calculate_retention(at, agent_state, profile));
+        if steps == 0 {
+            return Vec::new();
+        }
+        if steps == 1 || from == to {
+            return vec![sample(from)];
+        }
+
+        let span_ms = (to - from).num_milliseconds();
+        let intervals = (steps - 1) as i64;
+        (0..steps)
+            .map(|i| {
+                // Pin the last sample to `to` so rounding cannot move it.
+                let at = if i == steps - 1 {
+                    to
+                } else {
+                    from + chrono::Duration::milliseconds(span_ms * i as i64 / intervals)
+                };
+                sample(at)
+            })
+            .collect()
+    }
+
+    /// Returns the time-independent part of the retention formula and the
+    /// decay rate `beta` used by the `Decay(t)` term.
+    fn retention_factors(&self, agent_state: &AgentState, profile: &AgentProfile) -> (f32, f32) {
         // Phase(a)
         let phase = 1.0 / (1.0 + 
-            (profile.capacity_factor * (self.age_at_formation - profile.capacity_factor) as f64).exp() as f32
-        ) + profile.interference_factor;
+            (profile.k * (self.age_at_formation - profile.a_mid)).exp() as f32
+        ) + profile.epsilon;
         
-        // Decay(t)
-        let beta = self.decay_params.beta_0 * 
-            (1.0 + agent_state.stress + agent_state.fatigue);
-        let decay = (1.0 + beta * t_days).powf(-self.decay_params.alpha);
+        // Decay rate for Decay(t): stress, poor sleep and fatigue speed up forgetting
+        let beta = self.decay_params.beta_0 * (1.0
+            + profile.beta_cortisol * agent_state.cortisol_level
+            + profile.beta_sleep * agent_state.sleep_debt
+            + profile.beta_fatigue * agent_state.fatigue);
         
-        // Emotional bias
-        let emo_bias = if self.emotion.abs() > profile.emotional_bias {
-            1.0 + profile.emotional_bias * self.emotion.abs()
+        // Emotional bias: shocking memories get the trauma boost regardless
+        // of valence. The regimes are blended so retention stays continuous
+        // in emotion.
+        let excess = self.emotion.abs() - profile.theta_shock;
+        let shock_weight = if profile.shock_blend_width > 0.0 {
+            1.0 / (1.0 + (-excess / profile.shock_blend_width).exp())
+        } else if excess > 0.0 {
+            1.0
         } else {
-            1.0 + profile.emotional_bias * self.emotion
+            0.0
         };
+        let normal_bias = 1.0 + profile.eta * self.emotion;
+        let shock_bias = 1.0 + profile.gamma * self.emotion.abs();
+        let emo_bias = normal_bias + shock_weight * (shock_bias - normal_bias);
         
         // Capacity competition
-        let c_max = profile.c_base *
-            (1.0 - agent_state.fatigue + agent_state.training_factor);
-        let cap_comp = (self.capacity_weight.min(c_max) / profile.c_base).max(0.0);
-        
-        // Interference (simplified - would use ANN in production)
-        // For now, we'll use a placeholder value
-        let interference = 1.0;  // Would be exp(-kappa * s * t) in full implementation
-        
-        // Retention calculation
-        let retention = phase * decay * emo_bias * cap_comp * interference * self.memory_strength;
-        retention.max(0.0).min(1.0)
+        let cap_comp = if profile.use_capacity_in_retention {
+            let c_max = profile.c_base *
+                (1.0 - agent_state.fatigue + agent_state.training_factor);
+            (self.capacity_weight.min(c_max) / profile.c_base).max(0.0)
+        } else {
+            1.0
+        };
+
+        (phase * emo_bias * cap_comp * self.memory_strength, beta)
+    }
+
+    /// Estimates how long after formation this memory's retention falls to
+    /// `target`.
+    ///
+    /// Inverts the `Decay(t)` term of [`calculate_retention`] analytically,
+    /// holding every other factor constant at its current value. In
+    /// particular, the agent's stress, fatigue and training factor, as well as
+    /// `memory_strength`, are assumed not to change.
+    ///
+    /// Returns `Some(Duration::zero())` if retention is already at or below
+    /// `target`, and `None` if it never gets there: when `target` is not
+    /// positive (decay only approaches zero asymptotically) or when the
+    /// memory does not decay at all.
+    ///
+    /// The result is measured from `timestamp`, not from now.
+    ///
+    /// [`calculate_retention`]: Memory::calculate_retention
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::{Memory, AgentState, AgentProfile};
+    ///
+    /// let memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// let state = AgentState::default();
+    /// let profile = AgentProfile::default();
+    ///
+    /// let initial = memory.calculate_retention(memory.timestamp, &state, &profile);
+    /// let half_life = memory.time_to_retention(initial / 2.0, &state, &profile);
+    /// assert!(half_life.unwrap() > chrono::Duration::zero());
+    /// assert_eq!(memory.time_to_retention(0.0, &state, &profile), None);
+    /// ```
+    pub fn time_to_retention(
+        &self,
+        target: f32,
+        agent_state: &AgentState,
+        profile: &AgentProfile,
+    ) -> Option<chrono::Duration> {
+        if target.partial_cmp(&0.0) != Some(Ordering::Greater) {
+            return None;
+        }
+        let (static_factor, beta) = self.retention_factors(agent_state, profile);
+        if static_factor.min(1.0) <= target {
+            return Some(chrono::Duration::zero());
+        }
+
+        let alpha = self.decay_params.alpha;
+        if alpha.partial_cmp(&0.0) != Some(Ordering::Greater) || beta.partial_cmp(&0.0) != Some(Ordering::Greater) {
+            return None;
+        }
+
+        // Solve static_factor * (1 + beta * t)^-alpha = target for t (in days).
+        let t_days = ((static_factor / target).powf(1.0 / alpha) - 1.0) / beta;
+        let millis = f64::from(t_days) * 86_400_000.0;
+        if !millis.is_finite() || millis > i64::MAX as f64 {
+            return None;
+        }
+        Some(chrono::Duration::milliseconds(millis.ceil() as i64))
+    }
+
+    /// Returns a hash of every field of the memory.
+    ///
+    /// Floating-point fields are hashed by their bit patterns, so the hash
+    /// changes whenever any stored value changes. The hash is deterministic
+    /// for a given build of this crate, but is not guaranteed to be stable
+    /// across Rust or crate versions and should not be persisted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::Memory;
+    ///
+    /// let mut memory = Memory::new(vec![0.1, 0.2], 0.5, 25.0, 1.0);
+    /// let before = memory.content_hash();
+    /// assert_eq!(memory.clone().content_hash(), before);
+    ///
+    /// memory.emotion = 0.6;
+    /// assert_ne!(memory.content_hash(), before);
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash_content(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Feeds every field of the memory into `state`.
+    pub(crate) fn hash_content<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.semantic_vector.len().hash(state);
+        for value in &self.semantic_vector {
+            value.to_bits().hash(state);
+        }
+        self.emotion.to_bits().hash(state);
+        self.age_at_formation.to_bits().hash(state);
+        self.capacity_weight.to_bits().hash(state);
+        self.timestamp.hash(state);
+        self.last_retrieved.hash(state);
+        self.retrieval_count.hash(state);
+        self.metadata.to_string().hash(state);
+        self.recall_history.hash(state);
+        self.memory_strength.to_bits().hash(state);
+        self.decay_params.alpha.to_bits().hash(state);
+        self.decay_params.beta_0.to_bits().hash(state);
+        self.decay_params.max_recall_history.hash(state);
+    }
+
+    /// Checks that the memory's fields are within their documented ranges.
+    ///
+    /// A memory is valid when every component of `semantic_vector` is finite,
+    /// `emotion` is within `-1.0..=1.0`, `capacity_weight` is within
+    /// `0.0..=1.0`, `memory_strength` is finite and non-negative and
+    /// `decay_params` pass [`DecayParams::validate`]. An empty
+    /// `semantic_vector` is accepted, since archived memories have none.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] describing the first
+    /// violation found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::Memory;
+    ///
+    /// assert!(Memory::new(vec![0.1, 0.2], 0.5, 25.0, 1.0).validate().is_ok());
+    /// assert!(Memory::new(vec![f32::NAN], 0.5, 25.0, 1.0).validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<()> {
+        if let Some(i) = self.semantic_vector.iter().position(|v| !v.is_finite()) {
+            return Err(MemoryError::invalid_param(
+                &format!("semantic_vector[{}]", i),
+                self.semantic_vector[i],
+            ));
+        }
+        if !(-1.0..=1.0).contains(&self.emotion) {
+            return Err(MemoryError::invalid_param("emotion", self.emotion));
+        }
+        if !(0.0..=1.0).contains(&self.capacity_weight) {
+            return Err(MemoryError::invalid_param("capacity_weight", self.capacity_weight));
+        }
+        if !(self.memory_strength.is_finite() && self.memory_strength >= 0.0) {
+            return Err(MemoryError::invalid_param("memory_strength", self.memory_strength));
+        }
+        self.decay_params.validate()
+    }
+
+    /// Records a retrieval of this memory.
+    ///
+    /// Marks the memory as seen (see [`touch`](Memory::touch)), increments
+    /// `retrieval_count` and scales `memory_strength` by `1 / (1 + rho)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::Memory;
+    ///
+    /// let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// memory.record_retrieval(0.1);
+    /// assert_eq!(memory.retrieval_count, 1);
+    /// assert_eq!(memory.recall_history.len(), 1);
+    /// ```
+    pub fn record_retrieval(&mut self, rho: f32) {
+        self.touch(Utc::now());
+        self.retrieval_count += 1;
+        self.memory_strength /= 1.0 + rho;
+    }
+
+    /// Returns how long after a review this memory is next due, i.e. when its
+    /// `Decay(t)` term has fallen to [`REVIEW_RETENTION_TARGET`].
+    ///
+    /// The interval uses the memory's base decay rate `beta_0`, independent
+    /// of the agent's current state. Returns `None` if the memory does not
+    /// decay.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::Memory;
+    ///
+    /// let memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// assert_eq!(memory.optimal_review_interval().unwrap().num_days(), 14);
+    /// ```
+    pub fn optimal_review_interval(&self) -> Option<chrono::Duration> {
+        let DecayParams { alpha, beta_0, .. } = self.decay_params;
+        if alpha.partial_cmp(&0.0) != Some(Ordering::Greater) || beta_0.partial_cmp(&0.0) != Some(Ordering::Greater) {
+            return None;
+        }
+        // Solve (1 + beta_0 * t)^-alpha = REVIEW_RETENTION_TARGET for t (in days).
+        let t_days = (REVIEW_RETENTION_TARGET.powf(-1.0 / alpha) - 1.0) / beta_0;
+        let millis = f64::from(t_days) * 86_400_000.0;
+        if !millis.is_finite() || millis > i64::MAX as f64 {
+            return None;
+        }
+        Some(chrono::Duration::milliseconds(millis.ceil() as i64))
+    }
+
+    /// Records a review of this memory at `now`, strengthening it according
+    /// to how well the review was timed.
+    ///
+    /// The time since `last_retrieved` is compared with the
+    /// [`optimal_review_interval`](Memory::optimal_review_interval): a review
+    /// exactly on schedule multiplies `memory_strength` by `1 + rho`, and one
+    /// at `k` times or `1/k` of the interval by `1 + rho / k`, so cramming
+    /// earns little and overdue reviews are discounted as well. Like
+    /// [`record_retrieval`](Memory::record_retrieval), it marks the memory as
+    /// seen and increments `retrieval_count`. A memory that does not decay is
+    /// not strengthened.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::Memory;
+    ///
+    /// let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// let on_time = memory.last_retrieved + memory.optimal_review_interval().unwrap();
+    /// memory.record_retrieval_scheduled(0.5, on_time);
+    /// assert!((memory.memory_strength - 1.5).abs() < 1e-3);
+    /// ```
+    pub fn record_retrieval_scheduled(&mut self, rho: f32, now: DateTime<Utc>) {
+        let elapsed = (now - self.last_retrieved).num_milliseconds() as f64;
+        let timing = match self.optimal_review_interval() {
+            Some(interval) if elapsed > 0.0 && interval > chrono::Duration::zero() => {
+                let ratio = elapsed / interval.num_milliseconds() as f64;
+                ratio.min(1.0 / ratio) as f32
+            }
+            _ => 0.0,
+        };
+        self.touch(now);
+        self.retrieval_count += 1;
+        self.memory_strength *= 1.0 + rho * timing;
+    }
+
+    /// Marks the memory as seen at `now` without strengthening it.
+    ///
+    /// Sets `last_retrieved` and appends `now` to `recall_history`, dropping
+    /// the oldest entries beyond `decay_params.max_recall_history`, but leaves
+    /// `memory_strength` and `retrieval_count` unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::Memory;
+    /// use chrono::Utc;
+    ///
+    /// let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// let now = Utc::now();
+    /// memory.touch(now);
+    /// assert_eq!(memory.last_retrieved, now);
+    /// assert_eq!(memory.memory_strength, 1.0);
+    /// ```
+    pub fn touch(&mut self, now: DateTime<Utc>) {
+        self.last_retrieved = now;
+        self.recall_history.push_back(now);
+        self.trim_recall_history();
+    }
+
+    /// Drops the oldest recalls until `recall_history` fits within
+    /// `decay_params.max_recall_history`.
+    pub(crate) fn trim_recall_history(&mut self) {
+        let excess = self
+            .recall_history
+            .len()
+            .saturating_sub(self.decay_params.max_recall_history);
+        self.recall_history.drain(..excess);
+    }
+
+    /// Returns how many times per day this memory was recalled during the
+    /// last `window`, based on `recall_history`.
+    ///
+    /// Only the retained history is counted, so once more than
+    /// `decay_params.max_recall_history` recalls fall within `window` the
+    /// rate is underestimated. Returns 0.0 for a non-positive `window`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::Memory;
+    /// use chrono::{Duration, Utc};
+    ///
+    /// let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// memory.touch(Utc::now() - Duration::days(10));
+    /// memory.touch(Utc::now());
+    /// memory.touch(Utc::now());
+    /// assert_eq!(memory.recall_rate(Duration::days(2)), 1.0);
+    /// ```
+    pub fn recall_rate(&self, window: chrono::Duration) -> f32 {
+        if window <= chrono::Duration::zero() {
+            return 0.0;
+        }
+        let since = Utc::now() - window;
+        let recalls = self.recall_history.iter().filter(|&&at| at >= since).count();
+        let days = window.num_milliseconds() as f64 / 86_400_000.0;
+        (recalls as f64 / days) as f32
+    }
+
+    /// Returns a comparator that orders memories by retention, strongest first.
+    ///
+    /// The comparator captures the evaluation context (`now`, `agent_state`
+    /// and `profile`) so that every comparison is made against the same point
+    /// in time. `Memory` deliberately does not implement `Ord`, because its
+    /// retention is only meaningful relative to such a context.
+    ///
+    /// Retention values that are `NaN` are ordered after all other values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::{Memory, AgentState, AgentProfile};
+    /// use chrono::Utc;
+    ///
+    /// let state = AgentState::default();
+    /// let profile = AgentProfile::default();
+    /// let mut memories = vec![
+    ///     Memory::new(vec![0.1, 0.2], 0.0, 25.0, 0.5),
+    ///     Memory::new(vec![0.3, 0.4], 0.9, 25.0, 1.0),
+    /// ];
+    ///
+    /// memories.sort_by(Memory::retention_comparator(Utc::now(), &state, &profile));
+    /// ```
+    pub fn retention_comparator<'a>(
+        now: DateTime<Utc>,
+        agent_state: &'a AgentState,
+        profile: &'a AgentProfile,
+    ) -> impl Fn(&Memory, &Memory) -> Ordering + 'a {
+        move |a, b| {
+            let ra = a.calculate_retention(now, agent_state, profile);
+            let rb = b.calculate_retention(now, agent_state, profile);
+            match (ra.is_nan(), rb.is_nan()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => rb.total_cmp(&ra),
+            }
+        }
     }
 }
 
-/// Represents the current state of the agent
-#[derive(Debug, Clone)]
+/// Represents the current cognitive and physical state of an agent.
+///
+/// This state influences how quickly memories decay and how much capacity is
+/// available to them. Fields missing from serialized data default to `0.0`.
+///
+/// # Examples
+///
+/// ```
+/// use memory_module::model::AgentState;
+///
+/// // A stressed and tired agent
+/// let state = AgentState {
+///     cortisol_level: 0.8,
+///     fatigue: 0.7,
+///     ..AgentState::default()
+/// };
+/// assert_eq!(state.sleep_debt, 0.0);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct AgentState {
     /// Current age of the agent in years
     pub current_age: f64,
@@ -337,9 +922,80 @@ pub struct AgentState {
     pub training_factor: f32,
 }
 
-/// Agent-specific parameters that control memory formation and retention
-#[derive(Debug, Clone)]
+impl AgentState {
+    /// Creates an agent state, clamping each field into its documented range.
+    ///
+    /// `sleep_debt`, `cortisol_level`, `fatigue` and `training_factor` are
+    /// clamped to `0.0..=1.0` and `current_age` to at least `0.0`; NaN becomes
+    /// `0.0`. Building the struct directly skips this, so out-of-range values
+    /// can still reach [`Memory::calculate_retention`]; use
+    /// [`validate`](AgentState::validate) to check such a state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::AgentState;
+    ///
+    /// let state = AgentState::new(30.0, 0.2, 1.7, -0.5, 0.1);
+    /// assert_eq!(state.cortisol_level, 1.0);
+    /// assert_eq!(state.fatigue, 0.0);
+    /// assert!(state.validate().is_ok());
+    /// ```
+    pub fn new(current_age: f64, sleep_debt: f32, cortisol_level: f32, fatigue: f32, training_factor: f32) -> Self {
+        let unit = |value: f32| if value.is_nan() { 0.0 } else { value.clamp(0.0, 1.0) };
+        Self {
+            current_age: if current_age.is_nan() { 0.0 } else { current_age.max(0.0) },
+            sleep_debt: unit(sleep_debt),
+            cortisol_level: unit(cortisol_level),
+            fatigue: unit(fatigue),
+            training_factor: unit(training_factor),
+        }
+    }
+
+    /// Checks that the state's fields are within their documented ranges.
+    ///
+    /// A state is valid when `current_age` is finite and non-negative and the
+    /// normalized fields are within `0.0..=1.0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] naming the first field out
+    /// of range.
+    pub fn validate(&self) -> Result<()> {
+        if !(self.current_age.is_finite() && self.current_age >= 0.0) {
+            return Err(MemoryError::invalid_param("current_age", self.current_age));
+        }
+        for (name, value) in [
+            ("sleep_debt", self.sleep_debt),
+            ("cortisol_level", self.cortisol_level),
+            ("fatigue", self.fatigue),
+            ("training_factor", self.training_factor),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(MemoryError::invalid_param(name, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Current schema version for serialized [`AgentProfile`]s.
+pub const PROFILE_FORMAT_VERSION: u32 = 2;
+
+/// Agent-specific parameters that control memory formation and retention.
+///
+/// Fields missing from serialized data take their values from
+/// [`AgentProfile::default`], so profiles written by older versions load
+/// with sensible defaults. Profiles with a `version` newer than
+/// [`PROFILE_FORMAT_VERSION`] are rejected.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AgentProfile {
+    /// Schema version the profile was written with
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_profile_version"))]
+    pub version: u32,
+
     /// Phase steepness (k)
     pub k: f64,
     
@@ -357,6 +1013,13 @@ pub struct AgentProfile {
     
     /// Normal emotional slope (η)
     pub eta: f32,
+
+    /// Width of the blend between the normal and shock regimes (w_shock)
+    ///
+    /// The emotional bias moves from the `η` slope to the `γ` trauma boost
+    /// along a sigmoid in `|emotion| - θ_shock` with this width; `0.0` gives
+    /// a hard switch at `θ_shock`.
+    pub shock_blend_width: f32,
     
     /// Base capacity (C_base)
     pub c_base: f32,
@@ -366,22 +1029,117 @@ pub struct AgentProfile {
     
     /// Interference constant (κ)
     pub kappa: f32,
+
+    /// Decay sensitivity to cortisol level (β_c)
+    pub beta_cortisol: f32,
+
+    /// Decay sensitivity to sleep debt (β_s)
+    pub beta_sleep: f32,
+
+    /// Decay sensitivity to fatigue (β_f)
+    pub beta_fatigue: f32,
+
+    /// Whether the capacity-competition term scales retention
+    ///
+    /// When `false`, `capacity_weight` no longer affects retention and only
+    /// influences which memory a capped store evicts.
+    pub use_capacity_in_retention: bool,
 }
 
 impl Default for AgentProfile {
     fn default() -> Self {
         Self {
+            version: PROFILE_FORMAT_VERSION,
             k: 0.5,
             a_mid: 22.0,
             epsilon: 0.2,
             theta_shock: 0.7,
             gamma: 1.5,
             eta: 0.3,
+            shock_blend_width: 0.05,
             c_base: 100.0,
             rho: 0.1,
             kappa: 0.05,
+            beta_cortisol: 1.0,
+            beta_sleep: 0.5,
+            beta_fatigue: 1.0,
+            use_capacity_in_retention: true,
+        }
+    }
+}
+
+impl AgentProfile {
+    /// Checks that the profile's parameters keep the retention math sound.
+    ///
+    /// Profiles are rejected rather than clamped, since a parameter out of
+    /// range is a configuration mistake rather than noisy input. A profile is
+    /// valid when every parameter is finite, `epsilon` and `theta_shock` are
+    /// within `0.0..=1.0`, `c_base` is positive, and `gamma`,
+    /// `shock_blend_width`, `rho`, `kappa` and the `beta_*` sensitivities are
+    /// non-negative. `k`, `a_mid` and `eta` may take any finite value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] naming the first parameter
+    /// out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memory_module::model::AgentProfile;
+    ///
+    /// assert!(AgentProfile::default().validate().is_ok());
+    /// let profile = AgentProfile { c_base: 0.0, ..AgentProfile::default() };
+    /// assert!(profile.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<()> {
+        for (name, value) in [("k", self.k), ("a_mid", self.a_mid)] {
+            if !value.is_finite() {
+                return Err(MemoryError::invalid_param(name, value));
+            }
+        }
+        for (name, value) in [("epsilon", self.epsilon), ("theta_shock", self.theta_shock)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(MemoryError::invalid_param(name, value));
+            }
+        }
+        if !self.eta.is_finite() {
+            return Err(MemoryError::invalid_param("eta", self.eta));
+        }
+        if !(self.c_base.is_finite() && self.c_base > 0.0) {
+            return Err(MemoryError::invalid_param("c_base", self.c_base));
         }
+        for (name, value) in [
+            ("gamma", self.gamma),
+            ("shock_blend_width", self.shock_blend_width),
+            ("rho", self.rho),
+            ("kappa", self.kappa),
+            ("beta_cortisol", self.beta_cortisol),
+            ("beta_sleep", self.beta_sleep),
+            ("beta_fatigue", self.beta_fatigue),
+        ] {
+            if !(value.is_finite() && value >= 0.0) {
+                return Err(MemoryError::invalid_param(name, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads a profile version, rejecting versions this build does not know.
+#[cfg(feature = "serde")]
+fn deserialize_profile_version<'de, D>(deserializer: D) -> std::result::Result<u32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let version = u32::deserialize(deserializer)?;
+    if version > PROFILE_FORMAT_VERSION {
+        return Err(serde::de::Error::custom(format!(
+            "unsupported agent profile version {} (newest supported is {})",
+            version, PROFILE_FORMAT_VERSION
+        )));
     }
+    Ok(version)
 }
 
 #[cfg(test)]
@@ -401,6 +1159,233 @@ mod tests {
         assert!(memory.recall_history.is_empty());
     }
     
+    #[test]
+    fn test_time_to_retention_matches_forward_evaluation() {
+        let memory = Memory::new(vec![0.1, 0.2], 0.6, 25.0, 1.0);
+        let state = AgentState::default();
+        let profile = AgentProfile::default();
+        let initial = memory.calculate_retention(memory.timestamp, &state, &profile);
+
+        for fraction in [0.9, 0.5, 0.1] {
+            let target = initial * fraction;
+            let elapsed = memory.time_to_retention(target, &state, &profile).unwrap();
+            let retention = memory.calculate_retention(memory.timestamp + elapsed, &state, &profile);
+            assert_relative_eq!(retention, target, max_relative = 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_retention_curve_edge_cases() {
+        let memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+        let (state, profile) = (AgentState::default(), AgentProfile::default());
+        let from = memory.timestamp;
+        let to = from + Duration::days(10);
+
+        assert!(memory.retention_curve(&state, &profile, from, to, 0).is_empty());
+        let single = memory.retention_curve(&state, &profile, from, from, 5);
+        assert_eq!(single, vec![(from, memory.calculate_retention(from, &state, &profile))]);
+
+        let curve = memory.retention_curve(&state, &profile, from, to, 11);
+        let times: Vec<_> = curve.iter().map(|(at, _)| *at).collect();
+        assert_eq!(times, (0..=10).map(|d| from + Duration::days(d)).collect::<Vec<_>>());
+        assert_eq!(curve[4].1, memory.calculate_retention(from + Duration::days(4), &state, &profile));
+    }
+
+    #[test]
+    fn test_time_to_retention_edge_cases() {
+        let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+        let state = AgentState::default();
+        let profile = AgentProfile::default();
+        let initial = memory.calculate_retention(memory.timestamp, &state, &profile);
+
+        assert_eq!(memory.time_to_retention(initial, &state, &profile), Some(Duration::zero()));
+        assert_eq!(memory.time_to_retention(0.0, &state, &profile), None);
+
+        memory.decay_params.beta_0 = 0.0;
+        assert_eq!(memory.time_to_retention(initial / 2.0, &state, &profile), None);
+    }
+
+    #[test]
+    fn test_decay_params_new_validates() {
+        for (alpha, beta_0, field, value) in [
+            (0.0, 0.01, "alpha", 0.0),
+            (-0.5, 0.01, "alpha", -0.5),
+            (0.8, -0.01, "beta_0", -0.01),
+        ] {
+            assert_eq!(DecayParams::new(alpha, beta_0), Err(MemoryError::invalid_param(field, value)));
+        }
+        assert!(DecayParams::new(f32::NAN, 0.01).unwrap_err().is_invalid_parameter());
+        assert_eq!(
+            DecayParams::new(0.8, 0.0),
+            Ok(DecayParams {
+                alpha: 0.8,
+                beta_0: 0.0,
+                max_recall_history: DEFAULT_MAX_RECALL_HISTORY,
+            })
+        );
+
+        let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+        memory.decay_params = DecayParams::new(0.5, 0.1).unwrap();
+        let (state, profile) = (AgentState::default(), AgentProfile::default());
+        let fresh = memory.calculate_retention(memory.timestamp, &state, &profile);
+        let later = memory.calculate_retention(memory.timestamp + Duration::days(30), &state, &profile);
+        assert!(later < fresh);
+
+        memory.decay_params.alpha = -1.0;
+        assert_eq!(memory.validate(), Err(MemoryError::invalid_param("alpha", -1.0)));
+    }
+
+    #[test]
+    fn test_new_with_decay_higher_alpha_decays_faster() {
+        let params = |alpha| DecayParams {
+            alpha,
+            ..DecayParams::default()
+        };
+        let slow = Memory::new_with_decay(vec![0.1, 0.2], 0.0, 25.0, 1.0, params(0.4));
+        let mut fast = Memory::new_with_decay(vec![0.1, 0.2], 0.0, 25.0, 1.0, params(1.6));
+        fast.timestamp = slow.timestamp;
+        let (state, profile) = (AgentState::default(), AgentProfile::default());
+        let later = slow.timestamp + Duration::days(30);
+        assert!(fast.calculate_retention(later, &state, &profile) < slow.calculate_retention(later, &state, &profile));
+
+        for alpha in [0.0, -2.0, f32::NAN] {
+            let clamped = Memory::new_with_decay(vec![0.1], 0.0, 25.0, 1.0, params(alpha));
+            assert_eq!(clamped.decay_params.alpha, MIN_DECAY_ALPHA);
+            assert!(clamped.validate().is_ok());
+        }
+        let negative_beta = DecayParams {
+            beta_0: -1.0,
+            ..DecayParams::default()
+        };
+        assert_eq!(Memory::new_with_decay(vec![0.1], 0.0, 25.0, 1.0, negative_beta).decay_params.beta_0, 0.0);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Memory::new(vec![], 0.0, 25.0, 1.0).validate(), Ok(()));
+
+        let mut memory = Memory::new(vec![0.1, f32::INFINITY], 0.0, 25.0, 1.0);
+        assert_eq!(
+            memory.validate(),
+            Err(MemoryError::invalid_param("semantic_vector[1]", f32::INFINITY))
+        );
+        memory.semantic_vector[1] = 0.2;
+        memory.emotion = 1.5;
+        assert_eq!(memory.validate(), Err(MemoryError::invalid_param("emotion", 1.5)));
+        memory.emotion = -1.0;
+        memory.capacity_weight = -0.1;
+        assert_eq!(memory.validate(), Err(MemoryError::invalid_param("capacity_weight", -0.1)));
+        memory.capacity_weight = 0.0;
+        memory.memory_strength = f32::NAN;
+        assert!(memory.validate().unwrap_err().is_invalid_parameter());
+    }
+
+    #[test]
+    fn test_sleep_debt_accelerates_decay() {
+        let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+        let now = Utc::now();
+        memory.timestamp = now - Duration::days(30);
+        let profile = AgentProfile::default();
+        let rested = AgentState::default();
+        let tired = AgentState {
+            sleep_debt: 0.8,
+            ..AgentState::default()
+        };
+
+        let rested_retention = memory.calculate_retention(now, &rested, &profile);
+        let tired_retention = memory.calculate_retention(now, &tired, &profile);
+        assert!(tired_retention < rested_retention);
+
+        let insensitive = AgentProfile {
+            beta_sleep: 0.0,
+            ..AgentProfile::default()
+        };
+        assert_eq!(
+            memory.calculate_retention(now, &tired, &insensitive),
+            memory.calculate_retention(now, &rested, &insensitive)
+        );
+    }
+
+    #[test]
+    fn test_shocking_memories_get_trauma_boost() {
+        let now = Utc::now();
+        let mut shocking = Memory::new(vec![0.1, 0.2], -0.9, 25.0, 1.0);
+        let mut upsetting = Memory::new(vec![0.1, 0.2], -0.5, 25.0, 1.0);
+        shocking.timestamp = now - Duration::days(30);
+        upsetting.timestamp = shocking.timestamp;
+        let state = AgentState::default();
+        let profile = AgentProfile {
+            theta_shock: 0.7,
+            shock_blend_width: 0.0,
+            ..AgentProfile::default()
+        };
+
+        let shocked = shocking.calculate_retention(now, &state, &profile);
+        let upset = upsetting.calculate_retention(now, &state, &profile);
+        // (1 + 1.5 * 0.9) / (1 - 0.3 * 0.5)
+        assert!((shocked / upset - 2.35 / 0.85).abs() < 1e-4);
+
+        let stronger = AgentProfile {
+            gamma: 3.0,
+            ..profile.clone()
+        };
+        let boosted = shocking.calculate_retention(now, &state, &stronger);
+        assert!((boosted / shocked - 3.7 / 2.35).abs() < 1e-4);
+        assert_eq!(upsetting.calculate_retention(now, &state, &stronger), upset);
+    }
+
+    #[test]
+    fn test_retention_is_continuous_in_emotion() {
+        let now = Utc::now();
+        let state = AgentState::default();
+        let sweep = |profile: &AgentProfile| -> f32 {
+            let retention: Vec<f32> = (-1000..=1000)
+                .map(|i| {
+                    let mut memory = Memory::new(vec![0.1, 0.2], i as f32 / 1000.0, 25.0, 1.0);
+                    memory.timestamp = now;
+                    memory.calculate_retention(now, &state, profile)
+                })
+                .collect();
+            // Largest relative change between neighbouring emotions.
+            retention.windows(2).map(|w| (w[1] - w[0]).abs() / w[0].max(w[1])).fold(0.0, f32::max)
+        };
+
+        let profile = AgentProfile::default();
+        assert!(sweep(&profile) < 0.01);
+        let shocked = Memory::new(vec![0.1, 0.2], -0.95, 25.0, 1.0);
+        let calm = Memory::new(vec![0.1, 0.2], -0.5, 25.0, 1.0);
+        assert!(shocked.calculate_retention(now, &state, &profile) > 2.0 * calm.calculate_retention(now, &state, &profile));
+
+        let hard = AgentProfile {
+            shock_blend_width: 0.0,
+            ..AgentProfile::default()
+        };
+        assert!(sweep(&hard) > 0.5);
+    }
+
+    #[test]
+    fn test_capacity_term_can_be_excluded_from_retention() {
+        let now = Utc::now();
+        let mut light = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 0.2);
+        let mut heavy = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 0.9);
+        heavy.timestamp = light.timestamp;
+        let state = AgentState::default();
+
+        let profile = AgentProfile::default();
+        assert!(light.calculate_retention(now, &state, &profile) < heavy.calculate_retention(now, &state, &profile));
+
+        let profile = AgentProfile {
+            use_capacity_in_retention: false,
+            ..AgentProfile::default()
+        };
+        assert_eq!(
+            light.calculate_retention(now, &state, &profile),
+            heavy.calculate_retention(now, &state, &profile)
+        );
+        light.capacity_weight = 0.0;
+        assert!(light.calculate_retention(now, &state, &profile) > 0.0);
+    }
+
     #[test]
     fn test_retrieval_recording() {
         let mut memory = Memory::new(vec![], 0.0, 25.0, 1.0);
@@ -412,6 +1397,108 @@ mod tests {
         assert_eq!(memory.recall_history.len(), 1);
     }
     
+    #[test]
+    fn test_recall_history_is_bounded() {
+        let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+        for _ in 0..1000 {
+            memory.record_retrieval(0.0);
+        }
+        assert_eq!(memory.retrieval_count, 1000);
+        assert_eq!(memory.recall_history.len(), DEFAULT_MAX_RECALL_HISTORY);
+
+        memory.decay_params.max_recall_history = 4;
+        let now = Utc::now();
+        memory.touch(now);
+        assert_eq!(memory.recall_history.len(), 4);
+        assert_eq!(memory.recall_history.back(), Some(&now));
+        assert_eq!(memory.recall_rate(Duration::days(1)), 4.0);
+        assert_eq!(memory.recall_rate(Duration::zero()), 0.0);
+    }
+
+    #[test]
+    fn test_well_timed_review_strengthens_more_than_cramming() {
+        let mut well_timed = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+        let mut crammed = well_timed.clone();
+        let interval = well_timed.optimal_review_interval().unwrap();
+
+        well_timed.record_retrieval_scheduled(0.5, well_timed.last_retrieved + interval);
+        crammed.record_retrieval_scheduled(0.5, crammed.last_retrieved + Duration::hours(1));
+        assert!(well_timed.memory_strength > crammed.memory_strength);
+        assert!(crammed.memory_strength > 1.0);
+        assert_eq!(crammed.retrieval_count, 1);
+
+        let now = Utc::now();
+        let state = AgentState::default();
+        let profile = AgentProfile::default();
+        let later = now + Duration::days(60);
+        assert!(
+            well_timed.calculate_retention(later, &state, &profile) > crammed.calculate_retention(later, &state, &profile)
+        );
+
+        let mut static_memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+        static_memory.decay_params.beta_0 = 0.0;
+        assert_eq!(static_memory.optimal_review_interval(), None);
+        static_memory.record_retrieval_scheduled(0.5, now + Duration::days(30));
+        assert_eq!(static_memory.memory_strength, 1.0);
+    }
+
+    #[test]
+    fn test_typed_metadata_accessors() {
+        let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0)
+            .with_metadata("source", "conversation")
+            .with_metadata("confidence", 0.75)
+            .with_metadata("verified", true);
+        assert_eq!(memory.get_meta_str("source"), Some("conversation"));
+        assert_eq!(memory.get_meta_f64("confidence"), Some(0.75));
+        assert_eq!(memory.get_meta_bool("verified"), Some(true));
+
+        assert_eq!(memory.get_meta_str("missing"), None);
+        assert_eq!(memory.get_meta_str("confidence"), None);
+        assert_eq!(memory.get_meta_f64("source"), None);
+        assert_eq!(memory.get_meta_bool("confidence"), None);
+
+        memory.metadata = serde_json::json!(["not", "an", "object"]);
+        assert_eq!(memory.get_meta_str("source"), None);
+        memory.set_meta("source", "dream");
+        assert_eq!(memory.metadata, serde_json::json!({ "source": "dream" }));
+    }
+
+    #[test]
+    fn test_with_metadata_reads_back_and_recovers_from_non_object() {
+        let memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0).with_metadata("source", "conversation");
+        assert_eq!(memory.metadata["source"], "conversation");
+
+        let mut memory = memory;
+        memory.metadata = serde_json::Value::Null;
+        let memory = memory.with_metadata("source", "dream").with_metadata("lucid", false);
+        assert_eq!(memory.metadata, serde_json::json!({ "source": "dream", "lucid": false }));
+    }
+
+    #[test]
+    fn test_touch_leaves_strength_unchanged() {
+        let now = Utc::now();
+        let mut memory = Memory::new(vec![0.1, 0.2, 0.3], 0.5, 25.0, 1.0);
+        memory.timestamp = now - Duration::days(3);
+        memory.last_retrieved = memory.timestamp;
+        let agent_state = AgentState {
+            current_age: 30.0,
+            sleep_debt: 0.0,
+            cortisol_level: 0.0,
+            fatigue: 0.0,
+            training_factor: 0.0,
+        };
+        let agent_profile = AgentProfile::default();
+        let before = memory.calculate_retention(now, &agent_state, &agent_profile);
+
+        memory.touch(now);
+
+        assert_eq!(memory.last_retrieved, now);
+        assert_eq!(memory.recall_history.back(), Some(&now));
+        assert_eq!(memory.memory_strength, 1.0);
+        assert_eq!(memory.retrieval_count, 0);
+        assert_eq!(memory.calculate_retention(now, &agent_state, &agent_profile), before);
+    }
+
     #[test]
     fn test_retention_calculation() {
         let now = Utc::now();
@@ -435,4 +1522,100 @@ mod tests {
         // Just verify it's in the expected range
         assert!(retention > 0.0 && retention <= 1.0);
     }
+
+    #[test]
+    fn test_retention_comparator_orders_by_retention() {
+        let now = Utc::now();
+        let agent_state = AgentState {
+            current_age: 30.0,
+            sleep_debt: 0.0,
+            cortisol_level: 0.0,
+            fatigue: 0.0,
+            training_factor: 0.0,
+        };
+        let agent_profile = AgentProfile::default();
+
+        let mut memories: Vec<Memory> = (0..5)
+            .map(|i| {
+                let mut memory = Memory::new(vec![0.1, 0.2, 0.3], 0.1 * i as f32, 25.0, 1.0);
+                memory.timestamp = now - Duration::days(10 * (5 - i));
+                memory
+            })
+            .collect();
+
+        memories.sort_by(Memory::retention_comparator(now, &agent_state, &agent_profile));
+
+        let retentions: Vec<f32> = memories
+            .iter()
+            .map(|m| m.calculate_retention(now, &agent_state, &agent_profile))
+            .collect();
+        assert!(retentions.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_minimal_profile_json_fills_defaults() {
+        let json = r#"{
+            "k": 0.6, "a_mid": 20.0, "epsilon": 0.1, "theta_shock": 0.8, "gamma": 1.2,
+            "eta": 0.4, "c_base": 50.0, "rho": 0.2, "kappa": 0.1
+        }"#;
+        let profile: AgentProfile = serde_json::from_str(json).unwrap();
+        let defaults = AgentProfile::default();
+        assert_eq!(profile.k, 0.6);
+        assert_eq!(profile.kappa, 0.1);
+        assert_eq!(profile.version, PROFILE_FORMAT_VERSION);
+        assert_eq!(profile.beta_cortisol, defaults.beta_cortisol);
+        assert_eq!(profile.beta_sleep, defaults.beta_sleep);
+        assert_eq!(profile.beta_fatigue, defaults.beta_fatigue);
+        assert!(profile.use_capacity_in_retention);
+
+        let future = format!(r#"{{ "version": {} }}"#, PROFILE_FORMAT_VERSION + 1);
+        assert!(serde_json::from_str::<AgentProfile>(&future).is_err());
+    }
+
+    #[test]
+    fn test_agent_state_new_clamps_out_of_range_values() {
+        let state = AgentState::new(-3.0, 5.0, -0.4, f32::NAN, 1.2);
+        assert_eq!(
+            state,
+            AgentState {
+                current_age: 0.0,
+                sleep_debt: 1.0,
+                cortisol_level: 0.0,
+                fatigue: 0.0,
+                training_factor: 1.0,
+            }
+        );
+        assert!(state.validate().is_ok());
+
+        let raw = AgentState {
+            fatigue: 5.0,
+            ..AgentState::default()
+        };
+        assert_eq!(raw.validate(), Err(MemoryError::invalid_param("fatigue", 5.0)));
+        let aged = AgentState {
+            current_age: f64::INFINITY,
+            ..AgentState::default()
+        };
+        assert!(aged.validate().unwrap_err().is_invalid_parameter());
+    }
+
+    #[test]
+    fn test_agent_profile_validate_rejects_out_of_range_values() {
+        assert!(AgentProfile::default().validate().is_ok());
+        let cases = [
+            (AgentProfile { epsilon: 1.5, ..AgentProfile::default() }, "epsilon", 1.5),
+            (AgentProfile { c_base: 0.0, ..AgentProfile::default() }, "c_base", 0.0),
+            (AgentProfile { rho: -0.1, ..AgentProfile::default() }, "rho", -0.1),
+            (AgentProfile { beta_fatigue: f32::INFINITY, ..AgentProfile::default() }, "beta_fatigue", f32::INFINITY),
+        ];
+        for (profile, field, value) in cases {
+            assert_eq!(profile.validate(), Err(MemoryError::invalid_param(field, value)));
+        }
+        let profile = AgentProfile {
+            k: f64::NAN,
+            ..AgentProfile::default()
+        };
+        assert!(profile.validate().unwrap_err().is_invalid_parameter());
+    }
 }
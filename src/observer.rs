@@ -0,0 +1,113 @@
+//! Observation hooks for memory lifecycle events.
+//!
+//! A [`StoreObserver`] is notified when memories are added, retrieved,
+//! removed or pruned, which lets applications export metrics or logs without
+//! the crate depending on a particular telemetry library.
+
+use crate::model::Memory;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Receives notifications about operations on a memory store.
+///
+/// All methods have empty default implementations, so an observer only needs
+/// to override the events it cares about. Methods take `&self`; observers
+/// that keep state must use interior mutability (atomics, locks, ...).
+///
+/// # Examples
+///
+/// ```
+/// use memory_module::observer::StoreObserver;
+/// use memory_module::prelude::*;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+///
+/// #[derive(Default)]
+/// struct AddCounter(AtomicUsize);
+///
+/// impl StoreObserver for AddCounter {
+///     fn on_add(&self, _memory: &Memory) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counter = Arc::new(AddCounter::default());
+/// let mut store = MemoryStore::default();
+/// store.add_observer(counter.clone());
+/// store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+/// assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+/// ```
+pub trait StoreObserver: Send + Sync {
+    /// Called after a memory has been added to the store.
+    fn on_add(&self, _memory: &Memory) {}
+
+    /// Called for every memory returned by a retrieval, with its score.
+    fn on_retrieve(&self, _id: &Uuid, _score: f32) {}
+
+    /// Called after a memory has been explicitly removed from the store.
+    fn on_remove(&self, _id: &Uuid) {}
+
+    /// Called for every memory pruned during maintenance.
+    fn on_prune(&self, _id: &Uuid) {}
+
+    /// Called once per retrieval query with the number of results returned
+    /// and the time the query took.
+    fn on_query(&self, _results: usize, _elapsed: Duration) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn assert_send_sync<T: Send + Sync + ?Sized>() {}
+
+    #[test]
+    fn test_observers_are_thread_safe() {
+        assert_send_sync::<dyn StoreObserver>();
+        assert_send_sync::<MemoryStore>();
+        #[cfg(feature = "concurrent")]
+        assert_send_sync::<crate::concurrent_store::ConcurrentMemoryStore>();
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        adds: AtomicUsize,
+        retrievals: AtomicUsize,
+        prunes: AtomicUsize,
+    }
+
+    impl StoreObserver for Counter {
+        fn on_add(&self, _memory: &Memory) {
+            self.adds.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_retrieve(&self, _id: &Uuid, _score: f32) {
+            self.retrievals.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_prune(&self, _id: &Uuid) {
+            self.prunes.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_observer_counts_lifecycle_events() {
+        let counter = Arc::new(Counter::default());
+        let mut store = MemoryStore::default();
+        store.add_observer(counter.clone());
+
+        for i in 0..3 {
+            store.add_memory(Memory::new(vec![i as f32, 1.0], 0.0, 25.0, 1.0));
+        }
+        store.find_relevant(&[1.0, 1.0], 2).unwrap();
+        let pruned = store.maintain(1.0);
+
+        assert_eq!(counter.adds.load(Ordering::Relaxed), 3);
+        assert_eq!(counter.retrievals.load(Ordering::Relaxed), 2);
+        assert_eq!(counter.prunes.load(Ordering::Relaxed), pruned);
+        assert_eq!(pruned, 3);
+    }
+}
@@ -5,25 +5,20 @@
 use crate::error::{MemoryError, Result};
 use std::fs;
 use std::path::Path;
-
-#[cfg(feature = "serde")]
 use serde::{de::DeserializeOwned, Serialize};
 
 /// Trait for saving a value to persistent storage.
-#[cfg(feature = "serde")]
 pub trait Save {
     /// Save the value to the specified path.
     fn save<P: AsRef<Path>>(&self, path: P) -> Result<()>;
 }
 
 /// Trait for loading a value from persistent storage.
-#[cfg(feature = "serde")]
 pub trait Load: Sized {
     /// Load the value from the specified path.
     fn load<P: AsRef<Path>>(path: P) -> Result<Self>;
 }
 
-#[cfg(feature = "serde")]
 impl<T> Save for T
 where
     T: Serialize,
@@ -35,7 +30,6 @@ where
     }
 }
 
-#[cfg(feature = "serde")]
 impl<T> Load for T
 where
     T: DeserializeOwned,
@@ -0,0 +1,193 @@
+//! Write-through persistence for a [`MemoryStore`].
+//!
+//! [`PersistentMemoryStore`] serves reads from memory and saves the store to
+//! a [`StorageBackend`] after it changes.
+
+#![cfg(feature = "serde")]
+
+use crate::error::Result;
+use crate::model::{AgentState, Memory};
+use crate::storage::StorageBackend;
+use crate::store::{MemoryStore, RetrievalResult};
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// A [`MemoryStore`] that persists itself to a [`StorageBackend`].
+///
+/// Reads go straight to the in-memory store, which is reachable through
+/// [`Deref`]. Mutations mark the store dirty and save it once the flush
+/// interval has passed since the last save; with the default interval of
+/// zero every mutation is written through. A dirty store is also saved by
+/// [`flush`](PersistentMemoryStore::flush) and, ignoring errors, when it is
+/// dropped.
+///
+/// # Example
+///
+/// ```
+/// use memory_module::prelude::*;
+///
+/// let path = std::env::temp_dir().join(format!("mm_doc_{}.json", uuid::Uuid::new_v4()));
+/// let mut store = PersistentMemoryStore::open(FileBackend::new(&path)).unwrap();
+/// let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0)).unwrap();
+/// drop(store);
+///
+/// let reopened = PersistentMemoryStore::open(FileBackend::new(&path)).unwrap();
+/// assert!(reopened.get_memory(&id).is_some());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct PersistentMemoryStore {
+    store: MemoryStore,
+    backend: Box<dyn StorageBackend>,
+    flush_interval: Option<Duration>,
+    last_flush: Instant,
+    dirty: bool,
+}
+
+impl PersistentMemoryStore {
+    /// Loads the store from `backend` and keeps persisting to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error the backend reports while loading.
+    pub fn open<B: StorageBackend + 'static>(backend: B) -> Result<Self> {
+        let store = MemoryStore::from_stored_data(backend.load()?)?;
+        Ok(Self {
+            store,
+            backend: Box::new(backend),
+            flush_interval: Some(Duration::ZERO),
+            last_flush: Instant::now(),
+            dirty: false,
+        })
+    }
+
+    /// Sets how long after the last save a mutation triggers the next one.
+    ///
+    /// `Some(Duration::ZERO)`, the default, writes every mutation through;
+    /// a longer interval debounces bursts of mutations. With `None` the
+    /// store is only saved by [`flush`](PersistentMemoryStore::flush) and on
+    /// drop.
+    pub fn with_flush_interval(mut self, interval: Option<Duration>) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Returns `true` if the store has changes not yet saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Saves the store to the backend if it has unsaved changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error the backend reports; the store stays dirty.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.dirty {
+            self.backend.save(&self.store.to_stored_data())?;
+            self.dirty = false;
+            self.last_flush = Instant::now();
+        }
+        Ok(())
+    }
+
+    /// Returns the underlying store for mutations without a wrapper here.
+    ///
+    /// The store is marked dirty, and saved on the next flush.
+    pub fn store_mut(&mut self) -> &mut MemoryStore {
+        self.dirty = true;
+        &mut self.store
+    }
+
+    /// Adds a memory; see [`MemoryStore::add_memory`].
+    ///
+    /// # Errors
+    ///
+    /// Returns any error the backend reports if the store is saved. The
+    /// memory is added either way.
+    pub fn add_memory(&mut self, memory: Memory) -> Result<Uuid> {
+        let id = self.store.add_memory(memory);
+        self.mutated()?;
+        Ok(id)
+    }
+
+    /// Adds many memories; see [`MemoryStore::add_memories`].
+    ///
+    /// # Errors
+    ///
+    /// As for [`add_memory`](PersistentMemoryStore::add_memory).
+    pub fn add_memories(&mut self, memories: impl IntoIterator<Item = Memory>) -> Result<Vec<Uuid>> {
+        let ids = self.store.add_memories(memories);
+        self.mutated()?;
+        Ok(ids)
+    }
+
+    /// Removes a memory; see [`MemoryStore::remove_memory`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::NotFound`](crate::error::MemoryError::NotFound)
+    /// if the memory does not exist, or any error the backend reports.
+    pub fn remove_memory(&mut self, id: &Uuid) -> Result<()> {
+        self.store.remove_memory(id)?;
+        self.mutated()
+    }
+
+    /// Finds relevant memories; see [`MemoryStore::find_relevant`].
+    ///
+    /// Retrieval updates the returned memories' history, so this counts as
+    /// a mutation.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from the query or the backend.
+    pub fn find_relevant(&mut self, query_vector: &[f32], limit: usize) -> Result<Vec<RetrievalResult>> {
+        let results = self.store.find_relevant(query_vector, limit)?;
+        self.mutated()?;
+        Ok(results)
+    }
+
+    /// Prunes weak memories; see [`MemoryStore::maintain`].
+    ///
+    /// # Errors
+    ///
+    /// Returns any error the backend reports if the store is saved.
+    pub fn maintain(&mut self, retention_threshold: f32) -> Result<usize> {
+        let pruned = self.store.maintain(retention_threshold);
+        self.mutated()?;
+        Ok(pruned)
+    }
+
+    /// Updates the agent's state; see [`MemoryStore::update_agent_state`].
+    ///
+    /// # Errors
+    ///
+    /// Returns any error the backend reports if the store is saved.
+    pub fn update_agent_state(&mut self, state: AgentState) -> Result<()> {
+        self.store.update_agent_state(state);
+        self.mutated()
+    }
+
+    /// Marks the store dirty and saves it if the flush interval has passed.
+    fn mutated(&mut self) -> Result<()> {
+        self.dirty = true;
+        match self.flush_interval {
+            Some(interval) if self.last_flush.elapsed() >= interval => self.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Deref for PersistentMemoryStore {
+    type Target = MemoryStore;
+
+    fn deref(&self) -> &MemoryStore {
+        &self.store
+    }
+}
+
+impl Drop for PersistentMemoryStore {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
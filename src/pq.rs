@@ -0,0 +1,403 @@
+//! Product quantization for compact approximate similarity search.
+//!
+//! A [`ProductQuantizer`] splits each vector into `m` equally sized subspaces
+//! and learns a small k-means codebook for every subspace. A vector is then
+//! represented by one centroid index per subspace, so a `d`-dimensional `f32`
+//! vector shrinks to `m` bytes of codes.
+//!
+//! Similarity against a query is approximated with asymmetric distance
+//! computation: the query is kept exact, and per-subspace lookup tables of
+//! query/centroid dot products are summed over a vector's codes.
+
+use crate::error::{MemoryError, Result};
+use crate::similarity::SimilarityMetric;
+use crate::simd;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Number of Lloyd iterations used when training codebooks.
+const KMEANS_ITERATIONS: usize = 25;
+
+/// Seed used for codebook initialization so training is reproducible.
+const KMEANS_SEED: u64 = 0x5eed_0f9a;
+
+/// Learned codebooks that map vectors to compact product-quantization codes.
+#[derive(Debug, Clone)]
+pub struct ProductQuantizer {
+    dim: usize,
+    m: usize,
+    sub_dim: usize,
+    k: usize,
+    /// Centroids per subspace, each stored as `k * sub_dim` contiguous floats.
+    codebooks: Vec<Vec<f32>>,
+    /// Squared norm of every centroid, indexed by `subspace * k + code`.
+    centroid_norms: Vec<f32>,
+}
+
+impl ProductQuantizer {
+    /// Trains a quantizer on the given vectors.
+    ///
+    /// # Arguments
+    ///
+    /// * `vectors` - Training vectors; all must share the same dimension
+    /// * `m` - Number of subspaces; must evenly divide the vector dimension
+    /// * `bits` - Bits per code (`1..=8`), giving `2^bits` centroids per subspace
+    ///
+    /// If fewer training vectors than `2^bits` are supplied, the codebook size
+    /// is reduced to the number of vectors.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `vectors` is empty or of
+    /// mixed dimension, if `m` is zero or does not divide the dimension, or if
+    /// `bits` is outside `1..=8`.
+    pub fn train(vectors: &[&[f32]], m: usize, bits: usize) -> Result<Self> {
+        if !(1..=8).contains(&bits) {
+            return Err(MemoryError::invalid_param("bits", bits));
+        }
+        let dim = match vectors.first() {
+            Some(v) if !v.is_empty() => v.len(),
+            _ => {
+                return Err(MemoryError::InvalidParameter(
+                    "Product quantization requires at least one non-empty training vector".to_string(),
+                ))
+            }
+        };
+        if vectors.iter().any(|v| v.len() != dim) {
+            return Err(MemoryError::InvalidParameter(
+                "All training vectors must have the same dimension".to_string(),
+            ));
+        }
+        if m == 0 || dim % m != 0 {
+            return Err(MemoryError::invalid_param("m", m));
+        }
+
+        let sub_dim = dim / m;
+        let k = (1usize << bits).min(vectors.len());
+        let mut rng = StdRng::seed_from_u64(KMEANS_SEED);
+
+        let codebooks: Vec<Vec<f32>> = (0..m)
+            .map(|s| {
+                let points: Vec<&[f32]> = vectors
+                    .iter()
+                    .map(|v| &v[s * sub_dim..(s + 1) * sub_dim])
+                    .collect();
+                kmeans(&points, k, sub_dim, &mut rng)
+            })
+            .collect();
+
+        let centroid_norms = codebooks
+            .iter()
+            .flat_map(|book| book.chunks(sub_dim).map(|c| simd::dot(c, c)))
+            .collect();
+
+        Ok(Self {
+            dim,
+            m,
+            sub_dim,
+            k,
+            codebooks,
+            centroid_norms,
+        })
+    }
+
+    /// Returns the vector dimension this quantizer was trained on.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Returns the number of subspaces (and therefore code bytes per vector).
+    pub fn subspaces(&self) -> usize {
+        self.m
+    }
+
+    /// Encodes a vector into one centroid index per subspace.
+    ///
+    /// Returns `None` if the vector's dimension does not match the quantizer.
+    pub fn encode(&self, vector: &[f32]) -> Option<Vec<u8>> {
+        if vector.len() != self.dim {
+            return None;
+        }
+        let codes = self
+            .codebooks
+            .iter()
+            .enumerate()
+            .map(|(s, book)| {
+                let sub = &vector[s * self.sub_dim..(s + 1) * self.sub_dim];
+                nearest_centroid(sub, book, self.sub_dim) as u8
+            })
+            .collect();
+        Some(codes)
+    }
+
+    /// Reconstructs an approximate vector from its codes.
+    pub fn decode(&self, codes: &[u8]) -> Vec<f32> {
+        codes
+            .iter()
+            .zip(&self.codebooks)
+            .flat_map(|(&code, book)| {
+                let start = code as usize * self.sub_dim;
+                book[start..start + self.sub_dim].iter().copied()
+            })
+            .collect()
+    }
+
+    /// Precomputes lookup tables for approximate scoring against `query`.
+    ///
+    /// Returns `None` if the query's dimension does not match the quantizer.
+    pub fn query_table(&self, query: &[f32]) -> Option<QueryTable<'_>> {
+        if query.len() != self.dim {
+            return None;
+        }
+        let dots = self
+            .codebooks
+            .iter()
+            .enumerate()
+            .flat_map(|(s, book)| {
+                let sub = &query[s * self.sub_dim..(s + 1) * self.sub_dim];
+                book.chunks(self.sub_dim).map(move |c| simd::dot(sub, c))
+            })
+            .collect();
+        Some(QueryTable {
+            quantizer: self,
+            dots,
+            query_norm_sq: simd::dot(query, query),
+        })
+    }
+}
+
+/// Per-query lookup tables used to score encoded vectors.
+#[derive(Debug)]
+pub struct QueryTable<'a> {
+    quantizer: &'a ProductQuantizer,
+    dots: Vec<f32>,
+    query_norm_sq: f32,
+}
+
+impl QueryTable<'_> {
+    /// Approximates the similarity between the query and an encoded vector.
+    pub fn similarity(&self, codes: &[u8], metric: SimilarityMetric) -> f32 {
+        let k = self.quantizer.k;
+        let (dot, norm_sq) = codes
+            .iter()
+            .enumerate()
+            .fold((0.0f32, 0.0f32), |(dot, norm), (s, &code)| {
+                let idx = s * k + code as usize;
+                (dot + self.dots[idx], norm + self.quantizer.centroid_norms[idx])
+            });
+        match metric {
+            SimilarityMetric::Cosine => {
+                let denom = (self.query_norm_sq * norm_sq).sqrt();
+                if denom == 0.0 {
+                    0.0
+                } else {
+                    dot / denom
+                }
+            }
+            SimilarityMetric::DotProduct => dot,
+            SimilarityMetric::NegativeL2 => {
+                -(self.query_norm_sq + norm_sq - 2.0 * dot).max(0.0).sqrt()
+            }
+        }
+    }
+}
+
+/// A trained [`ProductQuantizer`] together with the codes of indexed memories.
+#[derive(Debug, Clone)]
+pub struct PqIndex {
+    quantizer: ProductQuantizer,
+    codes: HashMap<Uuid, Vec<u8>>,
+}
+
+impl PqIndex {
+    /// Creates an empty index around a trained quantizer.
+    pub fn new(quantizer: ProductQuantizer) -> Self {
+        Self {
+            quantizer,
+            codes: HashMap::new(),
+        }
+    }
+
+    /// Returns the underlying quantizer.
+    pub fn quantizer(&self) -> &ProductQuantizer {
+        &self.quantizer
+    }
+
+    /// Encodes and stores the codes for a memory vector.
+    ///
+    /// Vectors whose dimension does not match the quantizer are not indexed,
+    /// and any codes previously stored for `id` are discarded.
+    pub fn insert(&mut self, id: Uuid, vector: &[f32]) {
+        match self.quantizer.encode(vector) {
+            Some(codes) => {
+                self.codes.insert(id, codes);
+            }
+            None => {
+                self.codes.remove(&id);
+            }
+        }
+    }
+
+    /// Removes the codes for a memory.
+    pub fn remove(&mut self, id: &Uuid) {
+        self.codes.remove(id);
+    }
+
+    /// Keeps only the codes whose id satisfies the predicate.
+    pub fn retain<F: FnMut(&Uuid) -> bool>(&mut self, mut f: F) {
+        self.codes.retain(|id, _| f(id));
+    }
+
+    /// Returns the codes stored for a memory, if any.
+    pub fn codes(&self, id: &Uuid) -> Option<&[u8]> {
+        self.codes.get(id).map(Vec::as_slice)
+    }
+
+    /// Returns the number of indexed memories.
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    /// Returns `true` if no memories are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+}
+
+/// Runs k-means over `points` and returns `k` centroids flattened into one vector.
+fn kmeans(points: &[&[f32]], k: usize, sub_dim: usize, rng: &mut StdRng) -> Vec<f32> {
+    let mut centroids: Vec<f32> = points
+        .choose_multiple(rng, k)
+        .flat_map(|p| p.iter().copied())
+        .collect();
+    let mut assignments = vec![0usize; points.len()];
+
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut changed = false;
+        for (assignment, point) in assignments.iter_mut().zip(points) {
+            let nearest = nearest_centroid(point, &centroids, sub_dim);
+            if nearest != *assignment {
+                *assignment = nearest;
+                changed = true;
+            }
+        }
+
+        let mut sums = vec![0.0f32; k * sub_dim];
+        let mut counts = vec![0usize; k];
+        for (&assignment, point) in assignments.iter().zip(points) {
+            counts[assignment] += 1;
+            for (sum, &x) in sums[assignment * sub_dim..(assignment + 1) * sub_dim]
+                .iter_mut()
+                .zip(point.iter())
+            {
+                *sum += x;
+            }
+        }
+        for (c, &count) in counts.iter().enumerate() {
+            // Empty clusters keep their previous centroid.
+            if count > 0 {
+                for d in 0..sub_dim {
+                    centroids[c * sub_dim + d] = sums[c * sub_dim + d] / count as f32;
+                }
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    centroids
+}
+
+/// Returns the index of the centroid closest (in L2) to `point`.
+fn nearest_centroid(point: &[f32], centroids: &[f32], sub_dim: usize) -> usize {
+    centroids
+        .chunks(sub_dim)
+        .map(|c| {
+            c.iter()
+                .zip(point)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f32>()
+        })
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn random_vectors(n: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let vectors = random_vectors(10, 8, 1);
+        let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+
+        assert!(ProductQuantizer::train(&refs, 3, 4).unwrap_err().is_invalid_parameter());
+        assert!(ProductQuantizer::train(&refs, 2, 0).unwrap_err().is_invalid_parameter());
+        assert!(ProductQuantizer::train(&refs, 2, 9).unwrap_err().is_invalid_parameter());
+        assert!(ProductQuantizer::train(&[], 2, 4).unwrap_err().is_invalid_parameter());
+    }
+
+    #[test]
+    fn test_encode_decode_shape() {
+        let vectors = random_vectors(50, 8, 2);
+        let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+        let pq = ProductQuantizer::train(&refs, 4, 4).unwrap();
+
+        let codes = pq.encode(&vectors[0]).unwrap();
+        assert_eq!(codes.len(), 4);
+        assert_eq!(pq.decode(&codes).len(), 8);
+        assert!(pq.encode(&[1.0, 2.0]).is_none());
+    }
+
+    #[test]
+    fn test_pq_recall_against_brute_force() {
+        let dim = 32;
+        let vectors = random_vectors(500, dim, 3);
+        let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+        let pq = ProductQuantizer::train(&refs, 8, 6).unwrap();
+        let codes: Vec<Vec<u8>> = vectors.iter().map(|v| pq.encode(v).unwrap()).collect();
+
+        let queries = random_vectors(20, dim, 4);
+        let mut hits = 0;
+        for query in &queries {
+            let top_k = |scores: Vec<f32>| {
+                let mut idx: Vec<usize> = (0..scores.len()).collect();
+                idx.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+                idx.truncate(10);
+                idx
+            };
+            let exact = top_k(
+                vectors
+                    .iter()
+                    .map(|v| simd::cosine_similarity(query, v))
+                    .collect(),
+            );
+            let table = pq.query_table(query).unwrap();
+            let approx = top_k(
+                codes
+                    .iter()
+                    .map(|c| table.similarity(c, SimilarityMetric::Cosine))
+                    .collect(),
+            );
+            hits += exact.iter().filter(|i| approx.contains(i)).count();
+        }
+
+        let recall = hits as f32 / (queries.len() * 10) as f32;
+        assert!(recall > 0.5, "recall@10 was {}", recall);
+    }
+}
@@ -0,0 +1,113 @@
+//! Pluggable retention curves.
+//!
+//! A [`MemoryStore`](crate::store::MemoryStore) asks its [`RetentionModel`]
+//! how strongly each memory is retained whenever it scores, prunes or
+//! summarizes memories. [`DefaultRetentionModel`] is the crate's power-law
+//! formula, [`Memory::calculate_retention`]; [`ExponentialRetentionModel`]
+//! is an Ebbinghaus-style exponential curve, and custom curves can be
+//! plugged in with [`MemoryStore::with_retention_model`](crate::store::MemoryStore::with_retention_model).
+
+use crate::error::{MemoryError, Result};
+use crate::model::{AgentProfile, AgentState, Memory};
+use chrono::{DateTime, Utc};
+
+/// A function giving a memory's retention at a point in time.
+///
+/// # Example
+///
+/// ```
+/// use memory_module::prelude::*;
+/// use memory_module::chrono::{DateTime, Utc};
+///
+/// /// Never forgets anything.
+/// struct PerfectRecall;
+///
+/// impl RetentionModel for PerfectRecall {
+///     fn retention(&self, _: &Memory, _: DateTime<Utc>, _: &AgentState, _: &AgentProfile) -> f32 {
+///         1.0
+///     }
+/// }
+///
+/// let mut store = MemoryStore::default().with_retention_model(PerfectRecall);
+/// store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+/// assert_eq!(store.maintain(0.5), 0);
+/// ```
+pub trait RetentionModel: Send + Sync {
+    /// Gets `memory`'s retention at `now`, between 0.0 and 1.0.
+    fn retention(&self, memory: &Memory, now: DateTime<Utc>, agent_state: &AgentState, profile: &AgentProfile) -> f32;
+}
+
+/// The crate's power-law retention formula; see
+/// [`Memory::calculate_retention`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DefaultRetentionModel;
+
+impl RetentionModel for DefaultRetentionModel {
+    fn retention(&self, memory: &Memory, now: DateTime<Utc>, agent_state: &AgentState, profile: &AgentProfile) -> f32 {
+        memory.calculate_retention(now, agent_state, profile)
+    }
+}
+
+/// Ebbinghaus-style exponential forgetting.
+///
+/// Retention is `strength · exp(-t / S)`, clamped to `0.0..=1.0`, where
+/// `strength` is the memory's [`memory_strength`](Memory::memory_strength),
+/// `t` is the time since formation in days and `S` is the stability in
+/// days. The agent's state and profile are ignored.
+///
+/// # Example
+///
+/// ```
+/// use memory_module::prelude::*;
+/// use memory_module::chrono::Duration;
+///
+/// let model = ExponentialRetentionModel::new(2.0).unwrap();
+/// let memory = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+/// let (state, profile) = (AgentState::default(), AgentProfile::default());
+///
+/// let later = memory.timestamp + Duration::days(2);
+/// let retention = model.retention(&memory, later, &state, &profile);
+/// assert!((retention - (-1.0f32).exp()).abs() < 1e-4);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialRetentionModel {
+    stability_days: f32,
+}
+
+impl ExponentialRetentionModel {
+    /// Creates a model whose retention falls by a factor of `e` every
+    /// `stability_days` days.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `stability_days` is not
+    /// finite and positive.
+    pub fn new(stability_days: f32) -> Result<Self> {
+        if !(stability_days.is_finite() && stability_days > 0.0) {
+            return Err(MemoryError::invalid_param("stability_days", stability_days));
+        }
+        Ok(Self { stability_days })
+    }
+
+    /// Gets the stability in days
+    pub fn stability_days(&self) -> f32 {
+        self.stability_days
+    }
+}
+
+impl Default for ExponentialRetentionModel {
+    /// A stability of one day.
+    fn default() -> Self {
+        Self { stability_days: 1.0 }
+    }
+}
+
+impl RetentionModel for ExponentialRetentionModel {
+    fn retention(&self, memory: &Memory, now: DateTime<Utc>, _agent_state: &AgentState, _profile: &AgentProfile) -> f32 {
+        let t_days = ((now - memory.timestamp).num_seconds() as f32 / 86_400.0).max(0.0);
+        let retention = memory.memory_strength * (-t_days / self.stability_days).exp();
+        // Unlike `clamp`, this also maps NaN to 0.0.
+        #[allow(clippy::manual_clamp)]
+        retention.max(0.0).min(1.0)
+    }
+}
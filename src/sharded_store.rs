@@ -1,3 +1,9 @@
+//! A memory store partitioned across independently locked shards.
+//!
+//! [`ShardedMemoryStore`] assigns each memory to a shard by its id, so
+//! writers to different shards do not contend, and queries score every
+//! shard and merge the results.
+
 #![cfg(feature = "concurrent")]
 
 use crate::error::{MemoryError, Result};
@@ -5,8 +11,10 @@ use crate::model::{AgentProfile, AgentState, Memory};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use chrono::Utc;
-use crate::simd_utils;
+use crate::simd;
+use crate::store::{rank_order, RetrievalResult};
 use dashmap::DashMap;
+use std::sync::{PoisonError, RwLock};
 use uuid::Uuid;
 
 /// Memory store that partitions data across multiple shards for scalability.
@@ -14,7 +22,7 @@ use uuid::Uuid;
 pub struct ShardedMemoryStore {
     shards: Vec<DashMap<Uuid, Memory>>,
     agent_profile: AgentProfile,
-    agent_state: AgentState,
+    agent_state: RwLock<AgentState>,
 }
 
 impl ShardedMemoryStore {
@@ -25,7 +33,7 @@ impl ShardedMemoryStore {
         Self {
             shards,
             agent_profile,
-            agent_state,
+            agent_state: RwLock::new(agent_state),
         }
     }
 
@@ -57,26 +65,47 @@ impl ShardedMemoryStore {
     }
 
     /// Finds memories matching a query vector, ordered by relevance across all shards.
-    pub fn find_relevant(&self, query_vector: &[f32], limit: usize) -> Result<Vec<(f32, Memory)>> {
+    ///
+    /// Each shard ranks its own top `limit` memories, and those candidates
+    /// are merged into the overall top `limit`. With the `parallel` feature
+    /// the shards are scored concurrently on the rayon pool. Retrievals are
+    /// recorded after the merge, so only the returned memories are updated.
+    /// An empty store returns no results rather than an error.
+    pub fn find_relevant(&self, query_vector: &[f32], limit: usize) -> Result<Vec<RetrievalResult>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
         let now = Utc::now();
-        let mut scored: Vec<_> = self
-            .shards
-            .iter()
-            .flat_map(|shard| {
-                shard.iter().map(|entry| {
+        let agent_state = self.agent_state();
+        let top_of_shard = |shard: &DashMap<Uuid, Memory>| {
+            let mut scored: Vec<_> = shard
+                .iter()
+                .map(|entry| {
                     let id = *entry.key();
                     let mem = entry.value();
-                    let similarity = cosine_similarity(query_vector, &mem.semantic_vector);
-                    let retention = mem.calculate_retention(now, &self.agent_state, &self.agent_profile);
-                    (id, similarity * retention)
+                    let similarity = simd::cosine_similarity(query_vector, &mem.semantic_vector);
+                    let retention = mem.calculate_retention(now, &agent_state, &self.agent_profile);
+                    (id, similarity * retention, similarity, retention, mem.timestamp)
                 })
-            })
-            .collect();
-
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+                .collect();
+            scored.sort_by(|a, b| rank_order((a.1, a.4, &a.0), (b.1, b.4, &b.0)));
+            scored.truncate(limit);
+            scored
+        };
+
+        #[cfg(feature = "parallel")]
+        let candidates: Vec<Vec<_>> = {
+            use rayon::prelude::*;
+            self.shards.par_iter().map(top_of_shard).collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let candidates: Vec<Vec<_>> = self.shards.iter().map(top_of_shard).collect();
+
+        let mut scored: Vec<_> = candidates.into_iter().flatten().collect();
+        scored.sort_by(|a, b| rank_order((a.1, a.4, &a.0), (b.1, b.4, &b.0)));
         let top_n: Vec<_> = scored.into_iter().take(limit).collect();
 
-        for (id, _) in &top_n {
+        for (id, ..) in &top_n {
             let idx = self.shard_index(id);
             if let Some(mut mem) = self.shards[idx].get_mut(id) {
                 mem.record_retrieval(self.agent_profile.rho);
@@ -85,9 +114,16 @@ impl ShardedMemoryStore {
 
         let result = top_n
             .into_iter()
-            .filter_map(|(id, score)| {
+            .filter_map(|(id, score, similarity, retention, _)| {
                 let idx = self.shard_index(&id);
-                self.shards[idx].get(&id).map(|mem| (score, mem.clone()))
+                self.shards[idx].get(&id).map(|mem| RetrievalResult {
+                    score,
+                    similarity,
+                    retention,
+                    last_retrieved: mem.last_retrieved,
+                    retrieval_count: mem.retrieval_count,
+                    memory: mem.clone(),
+                })
             })
             .collect();
 
@@ -99,7 +135,7 @@ impl ShardedMemoryStore {
         &self,
         query_vectors: &[Vec<f32>],
         limit: usize,
-    ) -> Result<Vec<Vec<(f32, Memory)>>> {
+    ) -> Result<Vec<Vec<RetrievalResult>>> {
         query_vectors
             .iter()
             .map(|q| self.find_relevant(q, limit))
@@ -108,23 +144,45 @@ impl ShardedMemoryStore {
 
     /// Performs maintenance operations like pruning old memories on all shards.
     pub fn maintain(&self, retention_threshold: f32) -> usize {
+        self.maintain_collect(retention_threshold).len()
+    }
+
+    /// Like [`maintain`](ShardedMemoryStore::maintain), but returns the
+    /// pruned memories from all shards.
+    ///
+    /// Retention is checked again when each memory is removed, so a memory
+    /// replaced by another thread during the scan is only pruned if the
+    /// replacement is also below the threshold.
+    pub fn maintain_collect(&self, retention_threshold: f32) -> Vec<Memory> {
         assert!((0.0..=1.0).contains(&retention_threshold));
         let now = Utc::now();
-        let mut total_pruned = 0;
+        let agent_state = self.agent_state();
+        let expired = |mem: &Memory| {
+            mem.calculate_retention(now, &agent_state, &self.agent_profile) < retention_threshold
+        };
+        let mut pruned = Vec::new();
         for shard in &self.shards {
-            let before = shard.len();
-            shard.retain(|_id, mem| {
-                let retention = mem.calculate_retention(now, &self.agent_state, &self.agent_profile);
-                retention >= retention_threshold
-            });
-            total_pruned += before - shard.len();
+            let candidates: Vec<Uuid> = shard
+                .iter()
+                .filter(|entry| expired(entry.value()))
+                .map(|entry| *entry.key())
+                .collect();
+            pruned.extend(
+                candidates
+                    .iter()
+                    .filter_map(|id| shard.remove_if(id, |_, mem| expired(mem)))
+                    .map(|(_, mem)| mem),
+            );
         }
-        total_pruned
+        pruned
     }
 
     /// Updates the agent's state.
+    ///
+    /// The state is swapped atomically behind a lock, so this may be called
+    /// from any thread while other threads are querying the store.
     pub fn update_agent_state(&self, state: AgentState) {
-        self.agent_state = state;
+        *self.agent_state.write().unwrap_or_else(PoisonError::into_inner) = state;
     }
 
     /// Returns a reference to the agent profile.
@@ -132,13 +190,11 @@ impl ShardedMemoryStore {
         &self.agent_profile
     }
 
-    /// Returns a reference to the agent state.
-    pub fn agent_state(&self) -> &AgentState {
-        &self.agent_state
+    /// Returns a snapshot of the current agent state.
+    pub fn agent_state(&self) -> AgentState {
+        self.agent_state
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
     }
 }
-
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    simd_utils::cosine_similarity(a, b)
-}
-
@@ -0,0 +1,276 @@
+//! Vector kernels used for similarity scoring.
+//!
+//! [`dot`], [`norm`], [`cosine_similarity`] and [`normalize`] have the
+//! same signatures on every target. The implementation is chosen at compile time:
+//!
+//! 1. With the `simd` feature, SSE2 intrinsics on `x86_64` and NEON
+//!    intrinsics on `aarch64`. Both are part of their architecture's
+//!    baseline, so no runtime detection is needed.
+//! 2. Otherwise, with the `portable_simd` feature, `core::simd`. This
+//!    requires a nightly toolchain.
+//! 3. Otherwise, plain scalar loops, which build on stable Rust.
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+pub use sse2::{dot, norm};
+
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+pub use neon::{dot, norm};
+
+#[cfg(all(
+    feature = "portable_simd",
+    not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64")))
+))]
+pub use portable::{dot, norm};
+
+#[cfg(not(any(
+    feature = "portable_simd",
+    all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))
+)))]
+pub use scalar::{dot, norm};
+
+/// Computes cosine similarity between two vectors.
+///
+/// Returns 0.0 if either vector is empty, the lengths differ, either
+/// vector has zero norm, or the result is not finite because a vector
+/// contains NaN or infinite values.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.is_empty() || b.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot_product = dot(a, b);
+    let norm_a = norm(a);
+    let norm_b = norm(b);
+
+    let similarity = dot_product / (norm_a * norm_b);
+    if similarity.is_finite() {
+        similarity
+    } else {
+        0.0
+    }
+}
+
+/// Scales `v` in place to unit Euclidean length.
+///
+/// Zero vectors and vectors with a non-finite norm are left unchanged.
+///
+/// # Example
+///
+/// ```
+/// use memory_module::simd;
+///
+/// let mut v = [3.0, 4.0];
+/// simd::normalize(&mut v);
+/// assert_eq!(v, [0.6, 0.8]);
+/// ```
+pub fn normalize(v: &mut [f32]) {
+    let n = norm(v);
+    if n > 0.0 && n.is_finite() {
+        for x in v.iter_mut() {
+            *x /= n;
+        }
+    }
+}
+
+/// Plain loops; the default path and the reference for the others.
+#[cfg_attr(
+    any(feature = "portable_simd", all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))),
+    allow(dead_code)
+)]
+mod scalar {
+    /// Dot product of two `f32` slices.
+    ///
+    /// Returns 0.0 if the slices are of different lengths.
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    /// Euclidean norm of a vector.
+    pub fn norm(a: &[f32]) -> f32 {
+        a.iter().map(|x| x * x).sum::<f32>().sqrt()
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use std::arch::x86_64::{_mm_add_ps, _mm_loadu_ps, _mm_mul_ps, _mm_setzero_ps, _mm_storeu_ps};
+
+    const LANES: usize = 4;
+
+    /// SSE2 dot product for `f32` slices.
+    ///
+    /// Returns 0.0 if the slices are of different lengths.
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+        let chunks = a.len() / LANES;
+
+        // SAFETY: SSE2 is always available on x86_64, and every load reads
+        // `LANES` elements that lie within the first `chunks * LANES`.
+        let mut result = unsafe {
+            let mut sum = _mm_setzero_ps();
+            for i in 0..chunks {
+                let start = i * LANES;
+                let va = _mm_loadu_ps(a.as_ptr().add(start));
+                let vb = _mm_loadu_ps(b.as_ptr().add(start));
+                sum = _mm_add_ps(sum, _mm_mul_ps(va, vb));
+            }
+            let mut lanes = [0.0f32; LANES];
+            _mm_storeu_ps(lanes.as_mut_ptr(), sum);
+            lanes.iter().sum::<f32>()
+        };
+        let tail = chunks * LANES;
+        for (x, y) in a[tail..].iter().zip(&b[tail..]) {
+            result += x * y;
+        }
+        result
+    }
+
+    /// Calculates the Euclidean norm of a vector using SSE2.
+    pub fn norm(a: &[f32]) -> f32 {
+        dot(a, a).sqrt()
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+mod neon {
+    use std::arch::aarch64::{vaddvq_f32, vdupq_n_f32, vfmaq_f32, vld1q_f32};
+
+    const LANES: usize = 4;
+
+    /// NEON dot product for `f32` slices.
+    ///
+    /// Returns 0.0 if the slices are of different lengths.
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+        let chunks = a.len() / LANES;
+
+        // SAFETY: NEON is always available on aarch64, and every load reads
+        // `LANES` elements that lie within the first `chunks * LANES`.
+        let mut result = unsafe {
+            let mut sum = vdupq_n_f32(0.0);
+            for i in 0..chunks {
+                let start = i * LANES;
+                let va = vld1q_f32(a.as_ptr().add(start));
+                let vb = vld1q_f32(b.as_ptr().add(start));
+                sum = vfmaq_f32(sum, va, vb);
+            }
+            vaddvq_f32(sum)
+        };
+        let tail = chunks * LANES;
+        for (x, y) in a[tail..].iter().zip(&b[tail..]) {
+            result += x * y;
+        }
+        result
+    }
+
+    /// Calculates the Euclidean norm of a vector using NEON.
+    pub fn norm(a: &[f32]) -> f32 {
+        let chunks = a.len() / LANES;
+
+        // SAFETY: as in `dot`.
+        let mut result = unsafe {
+            let mut sum = vdupq_n_f32(0.0);
+            for i in 0..chunks {
+                let va = vld1q_f32(a.as_ptr().add(i * LANES));
+                sum = vfmaq_f32(sum, va, va);
+            }
+            vaddvq_f32(sum)
+        };
+        for x in &a[chunks * LANES..] {
+            result += x * x;
+        }
+        result.sqrt()
+    }
+}
+
+#[cfg(all(
+    feature = "portable_simd",
+    not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64")))
+))]
+mod portable {
+    use core::simd::{num::SimdFloat, Simd};
+
+    const LANES: usize = 8;
+
+    /// Portable SIMD dot product for `f32` slices.
+    ///
+    /// Returns 0.0 if the slices are of different lengths.
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+        let chunks = a.len() / LANES;
+
+        let mut sum = Simd::<f32, LANES>::splat(0.0);
+        for i in 0..chunks {
+            let start = i * LANES;
+            let va = Simd::from_slice(&a[start..start + LANES]);
+            let vb = Simd::from_slice(&b[start..start + LANES]);
+            sum += va * vb;
+        }
+
+        let mut result = sum.reduce_sum();
+        let tail = chunks * LANES;
+        for (x, y) in a[tail..].iter().zip(&b[tail..]) {
+            result += x * y;
+        }
+        result
+    }
+
+    /// Calculates the Euclidean norm of a vector using portable SIMD.
+    pub fn norm(a: &[f32]) -> f32 {
+        dot(a, a).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn test_selected_path_matches_scalar_on_random_vectors() {
+        let mut rng = StdRng::seed_from_u64(7);
+        // Lengths cover empty vectors, partial chunks and several full ones.
+        for len in 0..=37 {
+            let a: Vec<f32> = (0..len).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let b: Vec<f32> = (0..len).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+            let expected_dot = scalar::dot(&a, &b);
+            assert_relative_eq!(dot(&a, &b), expected_dot, epsilon = 1e-5, max_relative = 1e-5);
+            assert_relative_eq!(norm(&a), scalar::norm(&a), epsilon = 1e-5, max_relative = 1e-5);
+            if len > 0 {
+                let expected = expected_dot / (scalar::norm(&a) * scalar::norm(&b));
+                assert_relative_eq!(cosine_similarity(&a, &b), expected, epsilon = 1e-5);
+            }
+        }
+        assert_eq!(dot(&[1.0, 2.0], &[1.0]), 0.0);
+    }
+
+    #[test]
+    fn test_normalize_gives_unit_norm() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut v: Vec<f32> = (0..13).map(|_| rng.gen_range(-10.0..10.0)).collect();
+        normalize(&mut v);
+        assert_relative_eq!(norm(&v), 1.0, epsilon = 1e-5);
+
+        let mut zero = [0.0, 0.0];
+        normalize(&mut zero);
+        assert_eq!(zero, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_cosine_similarity_of_non_finite_vectors_is_zero() {
+        assert_eq!(cosine_similarity(&[f32::NAN, 1.0], &[1.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0, 1.0], &[f32::INFINITY, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+}
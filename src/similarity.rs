@@ -0,0 +1,277 @@
+//! Similarity metrics used to compare semantic vectors.
+//!
+//! This module defines [`SimilarityMetric`], which selects how a query vector
+//! is compared against stored memory vectors during retrieval.
+
+use crate::simd;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Metric used to compare two semantic vectors.
+///
+/// Every metric follows the convention that a higher value means "more
+/// similar", so results can always be ranked in descending order.
+///
+/// # Examples
+///
+/// ```
+/// use memory_module::similarity::SimilarityMetric;
+///
+/// let a = [1.0, 0.0];
+/// let b = [2.0, 0.0];
+/// assert_eq!(SimilarityMetric::Cosine.similarity(&a, &b), 1.0);
+/// assert_eq!(SimilarityMetric::DotProduct.similarity(&a, &b), 2.0);
+/// assert_eq!(SimilarityMetric::NegativeL2.similarity(&a, &b), -1.0);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SimilarityMetric {
+    /// Cosine of the angle between the vectors, in `[-1.0, 1.0]`.
+    #[default]
+    Cosine,
+
+    /// Raw dot product of the vectors.
+    ///
+    /// Equivalent to cosine similarity for unit-length embeddings, but cheaper
+    /// since no norms have to be computed.
+    DotProduct,
+
+    /// Negated Euclidean (L2) distance between the vectors.
+    ///
+    /// Identical vectors score `0.0`; more distant vectors score lower.
+    /// Since every value is non-positive, a store scores memories with
+    /// `1 / (1 + d)` for a distance `d` instead, so that better-retained
+    /// memories still rank higher.
+    NegativeL2,
+}
+
+impl SimilarityMetric {
+    /// Maps a similarity from this metric to the value a store combines
+    /// with retention: `1 / (1 + d)` for [`NegativeL2`], which is positive
+    /// and grows as the distance `d` shrinks, and the similarity itself
+    /// otherwise.
+    ///
+    /// [`NegativeL2`]: SimilarityMetric::NegativeL2
+    pub(crate) fn scoring_similarity(&self, similarity: f32) -> f32 {
+        match self {
+            SimilarityMetric::NegativeL2 => 1.0 / (1.0 - similarity),
+            _ => similarity,
+        }
+    }
+
+    /// Computes the similarity between `a` and `b` using this metric.
+    ///
+    /// If the vectors are empty or their lengths differ, [`Cosine`] and
+    /// [`DotProduct`] return `0.0`, while [`NegativeL2`] returns `f32::MIN`
+    /// so that mismatched vectors never rank as close matches.
+    ///
+    /// [`Cosine`]: SimilarityMetric::Cosine
+    /// [`DotProduct`]: SimilarityMetric::DotProduct
+    /// [`NegativeL2`]: SimilarityMetric::NegativeL2
+    pub fn similarity(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            SimilarityMetric::Cosine => simd::cosine_similarity(a, b),
+            SimilarityMetric::DotProduct => simd::dot(a, b),
+            SimilarityMetric::NegativeL2 => {
+                self.similarity_with_norms(a, simd::norm(a), b, simd::norm(b))
+            }
+        }
+    }
+
+    /// Computes the similarity between `a` and `b` using precomputed
+    /// Euclidean norms, avoiding a norm computation per comparison.
+    ///
+    /// The result matches [`similarity`](Self::similarity) as long as
+    /// `norm_a` and `norm_b` are the norms of `a` and `b`.
+    pub fn similarity_with_norms(&self, a: &[f32], norm_a: f32, b: &[f32], norm_b: f32) -> f32 {
+        if a.is_empty() || b.is_empty() || a.len() != b.len() {
+            return match self {
+                SimilarityMetric::NegativeL2 => f32::MIN,
+                _ => 0.0,
+            };
+        }
+        self.similarity_from_dot(simd::dot(a, b), norm_a, norm_b)
+    }
+
+    /// Computes the similarity from the dot product and Euclidean norms of
+    /// two non-empty vectors of equal length.
+    pub(crate) fn similarity_from_dot(&self, dot: f32, norm_a: f32, norm_b: f32) -> f32 {
+        match self {
+            SimilarityMetric::Cosine => {
+                // Zero norms and non-finite components both give 0.0, as in
+                // `simd::cosine_similarity`.
+                let similarity = dot / (norm_a * norm_b);
+                if similarity.is_finite() {
+                    similarity
+                } else {
+                    0.0
+                }
+            }
+            SimilarityMetric::DotProduct => dot,
+            SimilarityMetric::NegativeL2 => {
+                -(norm_a * norm_a + norm_b * norm_b - 2.0 * dot).max(0.0).sqrt()
+            }
+        }
+    }
+}
+
+/// Computes the cosine similarity of `a` and `b` with each dimension scaled
+/// by its weight in `weights`.
+///
+/// This is `Σ wᵢ·aᵢ·bᵢ / (√(Σ wᵢ·aᵢ²) · √(Σ wᵢ·bᵢ²))`, i.e. the cosine of the
+/// vectors after scaling each dimension by `√wᵢ`, so a larger weight makes a
+/// dimension count more. Uniform weights give plain cosine similarity.
+/// Returns `0.0` if any of the slices is empty, their lengths differ, or a
+/// weighted norm is zero.
+///
+/// # Examples
+///
+/// ```
+/// use memory_module::similarity::weighted_cosine_similarity;
+///
+/// let a = [1.0, 1.0];
+/// let b = [1.0, 0.0];
+/// assert!((weighted_cosine_similarity(&a, &b, &[1.0, 1.0]) - 0.5f32.sqrt()).abs() < 1e-6);
+/// assert!(weighted_cosine_similarity(&a, &b, &[4.0, 1.0]) > 0.8);
+/// assert_eq!(weighted_cosine_similarity(&a, &b, &[0.0, 1.0]), 0.0);
+/// ```
+pub fn weighted_cosine_similarity(a: &[f32], b: &[f32], weights: &[f32]) -> f32 {
+    if a.is_empty() || a.len() != b.len() || a.len() != weights.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for ((x, y), w) in a.iter().zip(b).zip(weights) {
+        dot += w * x * y;
+        norm_a += w * x * x;
+        norm_b += w * y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a.sqrt() * norm_b.sqrt())
+    }
+}
+
+/// Transformation applied to a raw similarity before it is combined with
+/// retention.
+///
+/// Multiplying a negative similarity by retention yields negative scores, and
+/// stronger memories then rank *lower*. The non-`Raw` variants avoid this.
+///
+/// # Examples
+///
+/// ```
+/// use memory_module::similarity::SimilarityTransform;
+///
+/// assert_eq!(SimilarityTransform::Raw.apply(-0.5), -0.5);
+/// assert_eq!(SimilarityTransform::ClampNonNegative.apply(-0.5), 0.0);
+/// assert_eq!(SimilarityTransform::Rescale01.apply(-0.5), 0.25);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SimilarityTransform {
+    /// Use the similarity unchanged.
+    #[default]
+    Raw,
+
+    /// Replace negative similarities with `0.0`.
+    ClampNonNegative,
+
+    /// Map `[-1.0, 1.0]` linearly onto `[0.0, 1.0]`.
+    ///
+    /// Intended for [`SimilarityMetric::Cosine`]; results outside `[0.0, 1.0]`
+    /// (possible with the other metrics) are clamped.
+    Rescale01,
+}
+
+impl SimilarityTransform {
+    /// Applies the transformation to a similarity value.
+    pub fn apply(&self, similarity: f32) -> f32 {
+        match self {
+            SimilarityTransform::Raw => similarity,
+            SimilarityTransform::ClampNonNegative => similarity.max(0.0),
+            SimilarityTransform::Rescale01 => ((similarity + 1.0) / 2.0).clamp(0.0, 1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_metrics() {
+        let a = [1.0, 2.0, 3.0];
+        let b = [4.0, 5.0, 6.0];
+
+        assert_relative_eq!(
+            SimilarityMetric::Cosine.similarity(&a, &b),
+            32.0 / (14.0f32.sqrt() * 77.0f32.sqrt()),
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(SimilarityMetric::DotProduct.similarity(&a, &b), 32.0, epsilon = 1e-6);
+        assert_relative_eq!(
+            SimilarityMetric::NegativeL2.similarity(&a, &b),
+            -27.0f32.sqrt(),
+            epsilon = 1e-4
+        );
+    }
+
+    #[test]
+    fn test_similarity_with_norms_matches_similarity() {
+        let a = [0.3, -1.2, 2.5];
+        let b = [1.1, 0.4, -0.7];
+        let (norm_a, norm_b) = (simd::norm(&a), simd::norm(&b));
+
+        for metric in [SimilarityMetric::Cosine, SimilarityMetric::DotProduct, SimilarityMetric::NegativeL2] {
+            assert_relative_eq!(
+                metric.similarity_with_norms(&a, norm_a, &b, norm_b),
+                metric.similarity(&a, &b),
+                epsilon = 1e-5
+            );
+        }
+    }
+
+    #[test]
+    fn test_mismatched_lengths() {
+        let a = [1.0, 2.0];
+        let b = [1.0, 2.0, 3.0];
+
+        assert_eq!(SimilarityMetric::Cosine.similarity(&a, &b), 0.0);
+        assert_eq!(SimilarityMetric::DotProduct.similarity(&a, &b), 0.0);
+        assert_eq!(SimilarityMetric::NegativeL2.similarity(&a, &b), f32::MIN);
+    }
+
+    #[test]
+    fn test_weighted_cosine_similarity() {
+        let a = [0.3, -1.2, 2.5];
+        let b = [1.1, 0.4, -0.7];
+
+        assert_relative_eq!(
+            weighted_cosine_similarity(&a, &b, &[2.0, 2.0, 2.0]),
+            SimilarityMetric::Cosine.similarity(&a, &b),
+            epsilon = 1e-6
+        );
+        let weights = [4.0f32, 0.25, 1.0];
+        let scale = |v: &[f32]| v.iter().zip(weights).map(|(x, w)| x * w.sqrt()).collect::<Vec<f32>>();
+        assert_relative_eq!(
+            weighted_cosine_similarity(&a, &b, &weights),
+            SimilarityMetric::Cosine.similarity(&scale(&a), &scale(&b)),
+            epsilon = 1e-6
+        );
+        assert_eq!(weighted_cosine_similarity(&a, &b, &[1.0, 1.0]), 0.0);
+        assert_eq!(weighted_cosine_similarity(&a, &b, &[0.0, 0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_transforms() {
+        assert_eq!(SimilarityTransform::Raw.apply(-1.0), -1.0);
+        assert_eq!(SimilarityTransform::ClampNonNegative.apply(-1.0), 0.0);
+        assert_eq!(SimilarityTransform::ClampNonNegative.apply(0.4), 0.4);
+        assert_eq!(SimilarityTransform::Rescale01.apply(-1.0), 0.0);
+        assert_eq!(SimilarityTransform::Rescale01.apply(0.0), 0.5);
+        assert_eq!(SimilarityTransform::Rescale01.apply(1.0), 1.0);
+        assert_eq!(SimilarityTransform::Rescale01.apply(5.0), 1.0);
+    }
+}
@@ -1,18 +1,40 @@
+//! Storage backends for persisting a [`MemoryStore`].
+//!
+//! A [`StorageBackend`] loads and saves [`StoredData`]; [`FileBackend`]
+//! writes JSON files, [`MemoryBackend`] keeps the data in memory, and
+//! database backends are available behind feature flags.
+
 use crate::error::{MemoryError, Result};
 use crate::model::{AgentProfile, AgentState, Memory};
-use crate::store::MemoryStore;
+use crate::store::{MemoryStore, DATA_FORMAT_VERSION};
 use std::collections::HashMap;
-use std::fs::{File};
-use std::io::{BufReader, BufWriter};
-use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
 use uuid::Uuid;
 
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
+#[cfg(feature = "serde")]
+use std::cell::RefCell;
+#[cfg(feature = "serde")]
+use std::fs::File;
+#[cfg(feature = "serde")]
+use std::io::{BufRead, BufReader, BufWriter, Write};
+#[cfg(feature = "serde")]
+use std::path::PathBuf;
 
 /// Data container used for serialization of [`MemoryStore`] state.
+///
+/// `StoredData` can be cloned and compared with or without the `serde`
+/// feature, so custom backends can build and check it directly.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
 pub struct StoredData {
+    /// Data format version the data was written with.
+    ///
+    /// Files written before versioning was introduced have no version and
+    /// are treated as version 1.
+    #[cfg_attr(feature = "serde", serde(default = "legacy_version"))]
+    pub version: u8,
     /// All memories indexed by id.
     pub memories: HashMap<Uuid, Memory>,
     /// The agent profile associated with the store.
@@ -21,12 +43,168 @@ pub struct StoredData {
     pub agent_state: AgentState,
 }
 
+impl Default for StoredData {
+    fn default() -> Self {
+        Self {
+            version: DATA_FORMAT_VERSION,
+            memories: HashMap::new(),
+            agent_profile: AgentProfile::default(),
+            agent_state: AgentState::default(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn legacy_version() -> u8 {
+    1
+}
+
+/// Upgrades serialized store data to [`DATA_FORMAT_VERSION`] and decodes it.
+///
+/// `raw` may be a serialized [`StoredData`] or [`MemoryStore`] of any
+/// version up to the current one. The embedded `version` field (treated as
+/// `1` when missing) selects which stepwise upgrades are applied:
+///
+/// - v1 → v2: adds the store's similarity `metric` and
+///   `similarity_transform`, defaulting to cosine similarity and the raw
+///   transform that v1 always used.
+///
+/// # Errors
+///
+/// Returns [`MemoryError::Serialization`] if `raw` is not a JSON object, was
+/// written by a newer version of this crate, or does not match the expected
+/// layout after upgrading.
+///
+/// # Example
+///
+/// ```
+/// use memory_module::storage::migrate_stored_data;
+///
+/// let v1 = serde_json::json!({
+///     "memories": {},
+///     "agent_profile": serde_json::to_value(memory_module::AgentProfile::default()).unwrap(),
+///     "agent_state": serde_json::to_value(memory_module::AgentState::default()).unwrap(),
+/// });
+/// let data = migrate_stored_data(v1).unwrap();
+/// assert_eq!(data.version, memory_module::store::DATA_FORMAT_VERSION);
+/// ```
+#[cfg(feature = "serde")]
+pub fn migrate_stored_data(raw: serde_json::Value) -> Result<StoredData> {
+    serde_json::from_value(upgrade_to_current(raw)?)
+        .map_err(|e| MemoryError::Serialization(e.to_string()))
+}
+
+/// Applies the stepwise format upgrades to raw serialized store data.
+#[cfg(feature = "serde")]
+pub(crate) fn upgrade_to_current(mut raw: serde_json::Value) -> Result<serde_json::Value> {
+    let object = raw
+        .as_object_mut()
+        .ok_or_else(|| MemoryError::Serialization("stored data is not a JSON object".to_string()))?;
+    let mut version = match object.get("version").and_then(|v| v.as_u64()) {
+        // Version 0 was only ever produced as a serde default for version 1.
+        None | Some(0) => 1,
+        Some(v) => v,
+    };
+    if version > u64::from(DATA_FORMAT_VERSION) {
+        return Err(MemoryError::Serialization(format!(
+            "Incompatible data format version: expected at most {}, found {}",
+            DATA_FORMAT_VERSION, version
+        )));
+    }
+
+    while version < u64::from(DATA_FORMAT_VERSION) {
+        match version {
+            1 => {
+                object.entry("metric").or_insert_with(|| "Cosine".into());
+                object.entry("similarity_transform").or_insert_with(|| "Raw".into());
+            }
+            _ => unreachable!("no upgrade defined from version {}", version),
+        }
+        version += 1;
+    }
+    object.insert("version".to_string(), DATA_FORMAT_VERSION.into());
+    Ok(raw)
+}
+
 /// Trait describing a persistence backend for [`MemoryStore`].
 pub trait StorageBackend {
     /// Load stored data from the backend.
     fn load(&self) -> Result<StoredData>;
     /// Save data to the backend.
     fn save(&self, data: &StoredData) -> Result<()>;
+
+    /// Save memories from an iterator along with the agent profile and state.
+    ///
+    /// Backends that can serialize memories one at a time should override
+    /// this to avoid building an intermediate [`StoredData`]. The default
+    /// implementation clones the memories into a [`StoredData`] and calls
+    /// [`save`](StorageBackend::save).
+    fn save_streaming<'a, I>(&self, memories: I, profile: &AgentProfile, state: &AgentState) -> Result<()>
+    where
+        I: Iterator<Item = &'a Memory>,
+        Self: Sized,
+    {
+        let data = StoredData {
+            version: DATA_FORMAT_VERSION,
+            memories: memories.map(|m| (m.id, m.clone())).collect(),
+            agent_profile: profile.clone(),
+            agent_state: state.clone(),
+        };
+        self.save(&data)
+    }
+}
+
+/// Async counterpart of [`StorageBackend`].
+///
+/// Implementations never block the calling thread, so stores can be
+/// persisted from async request handlers without tying up a worker thread. See
+/// [`MemoryStore::save_async`] and [`MemoryStore::load_async`].
+pub trait AsyncStorageBackend {
+    /// Load stored data from the backend.
+    fn load(&self) -> impl std::future::Future<Output = Result<StoredData>> + Send;
+    /// Save data to the backend.
+    fn save(&self, data: &StoredData) -> impl std::future::Future<Output = Result<()>> + Send;
+}
+
+/// Serializes memories lazily in the same layout as [`StoredData`].
+#[cfg(feature = "serde")]
+struct StreamedData<'r, I> {
+    memories: RefCell<Option<I>>,
+    agent_profile: &'r AgentProfile,
+    agent_state: &'r AgentState,
+}
+
+#[cfg(feature = "serde")]
+impl<'a, 'r, I> Serialize for StreamedData<'r, I>
+where
+    I: Iterator<Item = &'a Memory>,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::{Error, SerializeStruct};
+
+        struct Memories<'c, I>(&'c RefCell<Option<I>>);
+
+        impl<'a, 'c, I> Serialize for Memories<'c, I>
+        where
+            I: Iterator<Item = &'a Memory>,
+        {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                let memories = self
+                    .0
+                    .borrow_mut()
+                    .take()
+                    .ok_or_else(|| S::Error::custom("memories can only be serialized once"))?;
+                serializer.collect_map(memories.map(|m| (m.id, m)))
+            }
+        }
+
+        let mut state = serializer.serialize_struct("StoredData", 4)?;
+        state.serialize_field("version", &DATA_FORMAT_VERSION)?;
+        state.serialize_field("memories", &Memories(&self.memories))?;
+        state.serialize_field("agent_profile", self.agent_profile)?;
+        state.serialize_field("agent_state", self.agent_state)?;
+        state.end()
+    }
 }
 
 /// Simple JSON file-based storage backend.
@@ -47,17 +225,13 @@ impl FileBackend {
 impl StorageBackend for FileBackend {
     fn load(&self) -> Result<StoredData> {
         if !self.path.exists() {
-            return Ok(StoredData {
-                memories: HashMap::new(),
-                agent_profile: AgentProfile::default(),
-                agent_state: AgentState::default(),
-            });
+            return Ok(StoredData::default());
         }
         let file = File::open(&self.path).map_err(|e| MemoryError::Storage(e.to_string()))?;
         let reader = BufReader::new(file);
-        let data: StoredData = serde_json::from_reader(reader)
+        let raw: serde_json::Value = serde_json::from_reader(reader)
             .map_err(|e| MemoryError::Serialization(e.to_string()))?;
-        Ok(data)
+        migrate_stored_data(raw)
     }
 
     fn save(&self, data: &StoredData) -> Result<()> {
@@ -70,122 +244,674 @@ impl StorageBackend for FileBackend {
             .map_err(|e| MemoryError::Serialization(e.to_string()))?;
         Ok(())
     }
+
+    fn save_streaming<'a, I>(&self, memories: I, profile: &AgentProfile, state: &AgentState) -> Result<()>
+    where
+        I: Iterator<Item = &'a Memory>,
+    {
+        let data = StreamedData {
+            memories: RefCell::new(Some(memories)),
+            agent_profile: profile,
+            agent_state: state,
+        };
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| MemoryError::Storage(e.to_string()))?;
+        }
+        let file = File::create(&self.path).map_err(|e| MemoryError::Storage(e.to_string()))?;
+        let writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(writer, &data)
+            .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// In-memory storage backend that persists nothing to disk.
+///
+/// Saving replaces the held [`StoredData`] and loading returns a copy of it,
+/// or an empty store if nothing was saved yet. Clones share the same data,
+/// so a test can hand one clone to the code under test and inspect what it
+/// saved through another. It also serves as a transient backend for stores
+/// that need no persistence.
+///
+/// # Example
+///
+/// ```
+/// use memory_module::prelude::*;
+///
+/// let backend = MemoryBackend::new();
+/// let mut store = MemoryStore::default();
+/// let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+/// store.save(&backend).unwrap();
+///
+/// assert!(backend.data().unwrap().memories.contains_key(&id));
+/// assert!(MemoryStore::load(&backend).unwrap().get_memory(&id).is_some());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    data: Arc<Mutex<Option<StoredData>>>,
+}
+
+impl MemoryBackend {
+    /// Create an empty [`MemoryBackend`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets a copy of the last saved data, or `None` if nothing was saved.
+    pub fn data(&self) -> Option<StoredData> {
+        self.lock().ok()?.clone()
+    }
+
+    /// Discards the saved data.
+    pub fn clear(&self) {
+        if let Ok(mut data) = self.lock() {
+            *data = None;
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Option<StoredData>>> {
+        self.data.lock().map_err(|e| MemoryError::Storage(e.to_string()))
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn load(&self) -> Result<StoredData> {
+        Ok(self.lock()?.clone().unwrap_or_default())
+    }
+
+    fn save(&self, data: &StoredData) -> Result<()> {
+        *self.lock()? = Some(data.clone());
+        Ok(())
+    }
+}
+
+/// Compact binary file-based storage backend using `bincode`.
+///
+/// Produces much smaller files than [`FileBackend`] and is faster to read and
+/// write, at the cost of not being human-readable.
+#[cfg(feature = "bincode")]
+pub struct BincodeBackend {
+    path: PathBuf,
+}
+
+#[cfg(feature = "bincode")]
+impl BincodeBackend {
+    /// Create a new [`BincodeBackend`] with the given path.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl StorageBackend for BincodeBackend {
+    fn load(&self) -> Result<StoredData> {
+        if !self.path.exists() {
+            return Ok(StoredData::default());
+        }
+        let file = File::open(&self.path).map_err(|e| MemoryError::Storage(e.to_string()))?;
+        let reader = BufReader::new(file);
+        let data: StoredData = bincode::deserialize_from(reader)
+            .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+        // Bincode is not self-describing, so older layouts cannot be upgraded.
+        if data.version != DATA_FORMAT_VERSION {
+            return Err(MemoryError::Serialization(format!(
+                "Incompatible data format version: expected {}, found {}",
+                DATA_FORMAT_VERSION, data.version
+            )));
+        }
+        Ok(data)
+    }
+
+    fn save(&self, data: &StoredData) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| MemoryError::Storage(e.to_string()))?;
+        }
+        let file = File::create(&self.path).map_err(|e| MemoryError::Storage(e.to_string()))?;
+        let writer = BufWriter::new(file);
+        bincode::serialize_into(writer, data)
+            .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// A connection pool created on first use, together with the runtime that
+/// drives it.
+///
+/// The pool is bound to that runtime, so all of its I/O runs there:
+/// blocking callers wait with [`block_on`](SharedPool::block_on) and async
+/// callers await a task started with [`spawn`](SharedPool::spawn), whatever
+/// runtime they are on themselves.
+#[cfg(all(feature = "serde", any(feature = "sqlite", feature = "postgres")))]
+struct SharedPool<DB: sqlx::Database> {
+    runtime: std::sync::OnceLock<tokio::runtime::Runtime>,
+    pool: tokio::sync::OnceCell<sqlx::Pool<DB>>,
+}
+
+#[cfg(all(feature = "serde", any(feature = "sqlite", feature = "postgres")))]
+impl<DB: sqlx::Database> SharedPool<DB> {
+    fn new() -> Self {
+        Self {
+            runtime: std::sync::OnceLock::new(),
+            pool: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    fn runtime(&self) -> &tokio::runtime::Runtime {
+        self.runtime.get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .enable_all()
+                .build()
+                .expect("create runtime")
+        })
+    }
+
+    /// Returns the pool, running `connect` on the pool's runtime on first
+    /// use.
+    async fn get<F>(&self, connect: impl FnOnce() -> F) -> Result<sqlx::Pool<DB>>
+    where
+        F: std::future::Future<Output = Result<sqlx::Pool<DB>>> + Send + 'static,
+    {
+        self.pool.get_or_try_init(|| self.spawn(connect())).await.cloned()
+    }
+
+    /// Runs `fut` on the pool's runtime and awaits it without blocking the
+    /// caller's thread.
+    async fn spawn<T, F>(&self, fut: F) -> Result<T>
+    where
+        F: std::future::Future<Output = Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        match self.runtime().spawn(fut).await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(e) => Err(MemoryError::Storage(e.to_string())),
+        }
+    }
+
+    /// Runs `fut` to completion on the pool's runtime, blocking the caller.
+    ///
+    /// When the caller is already inside a Tokio runtime, blocking its thread
+    /// would panic, so the future is driven from a scoped helper thread.
+    fn block_on<F>(&self, fut: F) -> F::Output
+    where
+        F: std::future::Future + Send,
+        F::Output: Send,
+    {
+        let runtime = self.runtime();
+        if tokio::runtime::Handle::try_current().is_ok() {
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| runtime.block_on(fut))
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+        } else {
+            runtime.block_on(fut)
+        }
+    }
+}
+
+#[cfg(all(feature = "serde", any(feature = "sqlite", feature = "postgres")))]
+impl<DB: sqlx::Database> Drop for SharedPool<DB> {
+    fn drop(&mut self) {
+        // Close the pool while its runtime is still alive, then shut the
+        // runtime down without blocking, which is allowed even when the
+        // backend is dropped from async code.
+        drop(self.pool.take());
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
 }
 
 /// SQLite-based storage backend using `sqlx`.
+///
+/// Each agent's data is stored as one JSON row in the `agent_memory_store`
+/// table, keyed by the agent id, so many agents can share one database file.
+/// Backends created with [`new`](SqliteBackend::new) use the agent id
+/// `"default"`.
+///
+/// The connection pool and the runtime driving it are created on first use
+/// and reused by every later call, so repeated saves and loads do not pay
+/// for reconnecting. The [`AsyncStorageBackend`] methods run their queries
+/// on that runtime too and await them without blocking the caller.
 #[cfg(all(feature = "serde", feature = "sqlite"))]
 pub struct SqliteBackend {
     url: String,
+    agent_id: String,
+    shared: SharedPool<sqlx::Sqlite>,
 }
 
 #[cfg(all(feature = "serde", feature = "sqlite"))]
 impl SqliteBackend {
+    /// Agent id used when none is supplied.
+    pub const DEFAULT_AGENT_ID: &'static str = "default";
+
     /// Create a new [`SqliteBackend`] with the given connection URL.
     pub fn new<U: Into<String>>(url: U) -> Self {
-        Self { url: url.into() }
+        Self {
+            url: url.into(),
+            agent_id: Self::DEFAULT_AGENT_ID.to_string(),
+            shared: SharedPool::new(),
+        }
     }
 
-    fn block_on<F: std::future::Future>(&self, fut: F) -> F::Output {
-        tokio::runtime::Runtime::new()
-            .expect("create runtime")
-            .block_on(fut)
+    /// Sets the id of the agent whose data this backend reads and writes.
+    pub fn with_agent_id<A: Into<String>>(mut self, agent_id: A) -> Self {
+        self.agent_id = agent_id.into();
+        self
     }
-}
 
-#[cfg(all(feature = "serde", feature = "sqlite"))]
-impl StorageBackend for SqliteBackend {
-    fn load(&self) -> Result<StoredData> {
-        use sqlx::sqlite::SqlitePoolOptions;
+    /// Returns the id of the agent whose data this backend reads and writes.
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
 
-        let url = self.url.clone();
-        self.block_on(async move {
-            let pool = SqlitePoolOptions::new()
-                .connect(&url)
+    /// Lists the ids of all agents stored in the database, in sorted order.
+    pub fn list_agents(&self) -> Result<Vec<String>> {
+        self.shared.block_on(async {
+            let pool = self.pool().await?;
+            let rows = sqlx::query_as::<_, (String,)>(
+                "SELECT agent_id FROM agent_memory_store ORDER BY agent_id",
+            )
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| MemoryError::Storage(e.to_string()))?;
+            Ok(rows.into_iter().map(|(id,)| id).collect())
+        })
+    }
+
+    /// Deletes all data stored for the agent `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::NotFound`] if no data is stored for `id`.
+    pub fn delete_agent(&self, id: &str) -> Result<()> {
+        self.shared.block_on(async {
+            let pool = self.pool().await?;
+            let result = sqlx::query("DELETE FROM agent_memory_store WHERE agent_id = ?1")
+                .bind(id)
+                .execute(&pool)
                 .await
                 .map_err(|e| MemoryError::Storage(e.to_string()))?;
+            if result.rows_affected() == 0 {
+                return Err(MemoryError::NotFound(format!("Agent {} not found", id)));
+            }
+            Ok(())
+        })
+    }
 
-            sqlx::query(
-                "CREATE TABLE IF NOT EXISTS memory_store (id INTEGER PRIMARY KEY, data TEXT NOT NULL)",
-            )
-            .execute(&pool)
+    /// Returns the shared connection pool, connecting on first use.
+    async fn pool(&self) -> Result<sqlx::SqlitePool> {
+        self.shared.get(|| Self::connect(self.url.clone())).await
+    }
+
+    /// Connects to the database and creates the table if it is missing.
+    async fn connect(url: String) -> Result<sqlx::SqlitePool> {
+        use sqlx::sqlite::SqlitePoolOptions;
+
+        let pool = SqlitePoolOptions::new()
+            .connect(&url)
             .await
             .map_err(|e| MemoryError::Storage(e.to_string()))?;
 
-            if let Some(row) = sqlx::query_as::<_, (String,)>(
-                "SELECT data FROM memory_store WHERE id = 1",
-            )
-            .fetch_optional(&pool)
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS agent_memory_store (agent_id TEXT PRIMARY KEY, data TEXT NOT NULL)",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| MemoryError::Storage(e.to_string()))?;
+
+        Ok(pool)
+    }
+
+    /// Reads the single-agent row written by earlier versions, if present.
+    async fn load_legacy(pool: &sqlx::SqlitePool) -> Result<Option<String>> {
+        let legacy_table = sqlx::query_as::<_, (String,)>(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'memory_store'",
+        )
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| MemoryError::Storage(e.to_string()))?;
+        if legacy_table.is_none() {
+            return Ok(None);
+        }
+
+        let row = sqlx::query_as::<_, (String,)>("SELECT data FROM memory_store WHERE id = 1")
+            .fetch_optional(pool)
             .await
-            .map_err(|e| MemoryError::Storage(e.to_string()))?
-            {
-                let data: StoredData = serde_json::from_str(&row.0)
-                    .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+            .map_err(|e| MemoryError::Storage(e.to_string()))?;
+        Ok(row.map(|(data,)| data))
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "sqlite"))]
+impl SqliteBackend {
+    /// Reads this agent's data, falling back to the legacy table.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "sqlite_load", level = "debug", skip_all, fields(agent_id = %self.agent_id))
+    )]
+    async fn load_data(&self) -> Result<StoredData> {
+        let pool = self.pool().await?;
+        let agent_id = self.agent_id.clone();
+
+        let data = self
+            .shared
+            .spawn(async move {
+                let data = sqlx::query_as::<_, (String,)>(
+                    "SELECT data FROM agent_memory_store WHERE agent_id = ?1",
+                )
+                .bind(agent_id.as_str())
+                .fetch_optional(&pool)
+                .await
+                .map_err(|e| MemoryError::Storage(e.to_string()))?
+                .map(|(data,)| data);
+
+                // Databases written before agent ids were introduced hold the
+                // default agent's data in the single-row `memory_store` table.
+                if data.is_none() && agent_id == Self::DEFAULT_AGENT_ID {
+                    return Self::load_legacy(&pool).await;
+                }
                 Ok(data)
-            } else {
-                Ok(StoredData {
-                    memories: HashMap::new(),
-                    agent_profile: AgentProfile::default(),
-                    agent_state: AgentState::default(),
-                })
+            })
+            .await?;
+
+        match data {
+            Some(json) => {
+                let raw: serde_json::Value = serde_json::from_str(&json)
+                    .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+                migrate_stored_data(raw)
             }
-        })
+            None => Ok(StoredData::default()),
+        }
     }
 
-    fn save(&self, data: &StoredData) -> Result<()> {
-        use sqlx::sqlite::SqlitePoolOptions;
-
+    /// Writes this agent's data, replacing any existing row.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "sqlite_save",
+            level = "debug",
+            skip_all,
+            fields(agent_id = %self.agent_id, memories = data.memories.len())
+        )
+    )]
+    async fn save_data(&self, data: &StoredData) -> Result<()> {
         let json = serde_json::to_string(data)
             .map_err(|e| MemoryError::Serialization(e.to_string()))?;
-        let url = self.url.clone();
-        self.block_on(async move {
-            let pool = SqlitePoolOptions::new()
-                .connect(&url)
+        let pool = self.pool().await?;
+        let agent_id = self.agent_id.clone();
+
+        self.shared
+            .spawn(async move {
+                sqlx::query(
+                    "INSERT INTO agent_memory_store (agent_id, data) VALUES (?1, ?2) \
+                     ON CONFLICT(agent_id) DO UPDATE SET data=excluded.data",
+                )
+                .bind(agent_id)
+                .bind(json)
+                .execute(&pool)
                 .await
                 .map_err(|e| MemoryError::Storage(e.to_string()))?;
+                Ok(())
+            })
+            .await
+    }
+}
 
-            sqlx::query(
-                "CREATE TABLE IF NOT EXISTS memory_store (id INTEGER PRIMARY KEY, data TEXT NOT NULL)",
-            )
-            .execute(&pool)
+#[cfg(all(feature = "serde", feature = "sqlite"))]
+impl StorageBackend for SqliteBackend {
+    fn load(&self) -> Result<StoredData> {
+        self.shared.block_on(self.load_data())
+    }
+
+    fn save(&self, data: &StoredData) -> Result<()> {
+        self.shared.block_on(self.save_data(data))
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "sqlite"))]
+impl AsyncStorageBackend for SqliteBackend {
+    async fn load(&self) -> Result<StoredData> {
+        self.load_data().await
+    }
+
+    async fn save(&self, data: &StoredData) -> Result<()> {
+        self.save_data(data).await
+    }
+}
+
+/// PostgreSQL-based storage backend using `sqlx`.
+///
+/// Each agent's data is stored as one JSON row in the `memory_store` table,
+/// keyed by the agent id, so several agents can share a database.
+///
+/// Like [`SqliteBackend`], the backend creates its connection pool and the
+/// runtime driving it on first use and reuses them for every later call.
+#[cfg(all(feature = "serde", feature = "postgres"))]
+pub struct PostgresBackend {
+    url: String,
+    agent_id: String,
+    shared: SharedPool<sqlx::Postgres>,
+}
+
+#[cfg(all(feature = "serde", feature = "postgres"))]
+impl PostgresBackend {
+    /// Create a new [`PostgresBackend`] with the given connection URL and
+    /// the id of the agent whose data it reads and writes.
+    pub fn new<U: Into<String>, A: Into<String>>(url: U, agent_id: A) -> Self {
+        Self {
+            url: url.into(),
+            agent_id: agent_id.into(),
+            shared: SharedPool::new(),
+        }
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "postgres"))]
+impl PostgresBackend {
+    /// Returns the shared connection pool, connecting on first use.
+    async fn pool(&self) -> Result<sqlx::PgPool> {
+        self.shared.get(|| Self::connect(self.url.clone())).await
+    }
+
+    /// Connects to the database and creates the table if it is missing.
+    async fn connect(url: String) -> Result<sqlx::PgPool> {
+        use sqlx::postgres::PgPoolOptions;
+
+        let pool = PgPoolOptions::new()
+            .connect(&url)
             .await
             .map_err(|e| MemoryError::Storage(e.to_string()))?;
 
-            sqlx::query(
-                "INSERT INTO memory_store (id, data) VALUES (1, ?1) \
-                 ON CONFLICT(id) DO UPDATE SET data=excluded.data",
-            )
-            .bind(json)
-            .execute(&pool)
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS memory_store (agent_id TEXT PRIMARY KEY, data TEXT NOT NULL)",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| MemoryError::Storage(e.to_string()))?;
+
+        Ok(pool)
+    }
+
+    /// Reads this agent's data.
+    async fn load_data(&self) -> Result<StoredData> {
+        let pool = self.pool().await?;
+        let agent_id = self.agent_id.clone();
+
+        let row = self
+            .shared
+            .spawn(async move {
+                sqlx::query_as::<_, (String,)>("SELECT data FROM memory_store WHERE agent_id = $1")
+                    .bind(agent_id)
+                    .fetch_optional(&pool)
+                    .await
+                    .map_err(|e| MemoryError::Storage(e.to_string()))
+            })
+            .await?;
+
+        if let Some(row) = row {
+            let raw: serde_json::Value = serde_json::from_str(&row.0)
+                .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+            migrate_stored_data(raw)
+        } else {
+            Ok(StoredData::default())
+        }
+    }
+
+    /// Writes this agent's data, replacing any existing row.
+    async fn save_data(&self, data: &StoredData) -> Result<()> {
+        let json = serde_json::to_string(data)
+            .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+        let pool = self.pool().await?;
+        let agent_id = self.agent_id.clone();
+
+        self.shared
+            .spawn(async move {
+                sqlx::query(
+                    "INSERT INTO memory_store (agent_id, data) VALUES ($1, $2) \
+                     ON CONFLICT (agent_id) DO UPDATE SET data = EXCLUDED.data",
+                )
+                .bind(agent_id)
+                .bind(json)
+                .execute(&pool)
+                .await
+                .map_err(|e| MemoryError::Storage(e.to_string()))?;
+                Ok(())
+            })
             .await
-            .map_err(|e| MemoryError::Storage(e.to_string()))?;
+    }
+}
 
-            Ok(())
-        })
+#[cfg(all(feature = "serde", feature = "postgres"))]
+impl StorageBackend for PostgresBackend {
+    fn load(&self) -> Result<StoredData> {
+        self.shared.block_on(self.load_data())
+    }
+
+    fn save(&self, data: &StoredData) -> Result<()> {
+        self.shared.block_on(self.save_data(data))
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "postgres"))]
+impl AsyncStorageBackend for PostgresBackend {
+    async fn load(&self) -> Result<StoredData> {
+        self.load_data().await
+    }
+
+    async fn save(&self, data: &StoredData) -> Result<()> {
+        self.save_data(data).await
     }
 }
 
-#[cfg(feature = "serde")]
 impl MemoryStore {
     /// Persist the store to the given backend.
+    ///
+    /// Memories are passed to [`StorageBackend::save_streaming`], so backends
+    /// that support streaming do not copy the whole store first.
     pub fn save<B: StorageBackend>(&self, backend: &B) -> Result<()> {
-        let data = StoredData {
-            memories: self.memories.clone(),
-            agent_profile: self.agent_profile.clone(),
-            agent_state: self.agent_state.clone(),
-        };
-        backend.save(&data)
+        backend.save_streaming(self.memories(), self.agent_profile(), self.agent_state())
     }
 
     /// Load a [`MemoryStore`] from the given backend.
+    ///
+    /// When the `faiss` feature is enabled the FAISS index is rebuilt from
+    /// the loaded memories; see [`MemoryStore::rebuild_index`].
     pub fn load<B: StorageBackend>(backend: &B) -> Result<Self> {
-        let data = backend.load()?;
-        Ok(Self {
-            memories: data.memories,
-            agent_profile: data.agent_profile,
-            agent_state: data.agent_state,
-            #[cfg(feature = "faiss")]
-            faiss_index: None,
-        })
+        Self::from_stored_data(backend.load()?)
+    }
+
+    /// Persist the store to the given async backend.
+    ///
+    /// Unlike [`save`](Self::save) this never blocks the calling thread, so
+    /// it can be used from async request handlers.
+    pub async fn save_async<B: AsyncStorageBackend>(&self, backend: &B) -> Result<()> {
+        backend.save(&self.to_stored_data()).await
+    }
+
+    /// Load a [`MemoryStore`] from the given async backend.
+    pub async fn load_async<B: AsyncStorageBackend>(backend: &B) -> Result<Self> {
+        Self::from_stored_data(backend.load().await?)
+    }
+
+    /// Copies the memories, profile and state into a [`StoredData`].
+    pub(crate) fn to_stored_data(&self) -> StoredData {
+        StoredData {
+            version: DATA_FORMAT_VERSION,
+            memories: self.memories().map(|m| (m.id, m.clone())).collect(),
+            agent_profile: self.agent_profile().clone(),
+            agent_state: self.agent_state().clone(),
+        }
+    }
+
+    pub(crate) fn from_stored_data(data: StoredData) -> Result<Self> {
+        Self::from_memories(data.agent_profile, data.agent_state, data.memories)
     }
 }
 
+#[cfg(feature = "serde")]
+impl MemoryStore {
+    /// Writes every memory to `writer` as JSON lines: one standalone
+    /// [`Memory`] object per line.
+    ///
+    /// Unlike [`save`](Self::save), the agent profile and state are not
+    /// written. The order of the lines is unspecified.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::Serialization`] if a memory cannot be encoded
+    /// and [`MemoryError::Storage`] if writing fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    ///
+    /// let mut out = Vec::new();
+    /// store.export_jsonl(&mut out).unwrap();
+    /// let mut copy = MemoryStore::default();
+    /// assert_eq!(copy.import_jsonl(out.as_slice()).unwrap(), 1);
+    /// ```
+    pub fn export_jsonl<W: Write>(&self, writer: W) -> Result<()> {
+        let mut writer = BufWriter::new(writer);
+        for memory in self.memories() {
+            serde_json::to_writer(&mut writer, memory)
+                .map_err(|e| MemoryError::Serialization(e.to_string()))?;
+            writer.write_all(b"\n").map_err(|e| MemoryError::Storage(e.to_string()))?;
+        }
+        writer.flush().map_err(|e| MemoryError::Storage(e.to_string()))
+    }
+
+    /// Reads memories written by [`export_jsonl`](Self::export_jsonl) and
+    /// adds them to the store, returning how many were read.
+    ///
+    /// Lines are decoded one at a time, so the input is never held in memory
+    /// as a whole, and blank lines are skipped. Each memory goes through
+    /// [`add_memory`](MemoryStore::add_memory).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::Storage`] if reading fails and
+    /// [`MemoryError::Serialization`], naming the line, if a line is not a
+    /// valid memory. Memories on earlier lines stay in the store.
+    pub fn import_jsonl<R: BufRead>(&mut self, reader: R) -> Result<usize> {
+        let mut imported = 0;
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| MemoryError::Storage(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let memory: Memory = serde_json::from_str(&line)
+                .map_err(|e| MemoryError::Serialization(format!("line {}: {}", index + 1, e)))?;
+            self.add_memory(memory);
+            imported += 1;
+        }
+        Ok(imported)
+    }
+}
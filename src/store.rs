@@ -7,16 +7,45 @@ use crate::error::{MemoryError, Result};
 use crate::model::{AgentProfile, AgentState, Memory};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use chrono::Utc;
-use crate::simd_utils;
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use crate::similarity::{self, SimilarityMetric, SimilarityTransform};
+use crate::simd;
+use crate::observer::StoreObserver;
+use crate::retention::{DefaultRetentionModel, RetentionModel};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
 use uuid::Uuid;
-#[cfg(feature = "faiss")]
-use crate::faiss_index::FaissIndex;
+use crate::vector_index::VectorIndex;
+#[cfg(feature = "pq")]
+use crate::pq::{PqIndex, ProductQuantizer};
+
+/// Maximum number of hops [`MemoryStore::find_relevant_associative`] spreads
+/// activation over.
+pub const MAX_ASSOCIATIVE_HOPS: usize = 3;
+
+/// Cosine similarity at or above which another memory interferes with a
+/// memory in [`MemoryStore::calculate_retention_with_interference`].
+pub const INTERFERENCE_SIMILARITY_THRESHOLD: f32 = 0.9;
+
+/// How many times `limit` candidates [`MemoryStore::find_relevant`] takes
+/// from the search index when the store's metric is not
+/// [`SimilarityMetric::NegativeL2`], whose ranking the index's L2 distances
+/// may not match.
+pub const INDEX_OVERSAMPLE: usize = 4;
+
+/// Hours of [`MemoryStore::sleep`] that count as one full night.
+pub const SLEEP_HOURS_PER_NIGHT: f32 = 8.0;
+
+/// Emotional intensity above which [`MemoryStore::sleep`] strengthens a
+/// recent memory rather than weakening it.
+pub const SLEEP_SALIENCE_THRESHOLD: f32 = 0.3;
 
-#[cfg(feature = "serde")]
 /// Current data format version for serialized stores.
-pub const DATA_FORMAT_VERSION: u8 = 1;
+///
+/// Older versions are upgraded on load; see
+/// [`migrate_stored_data`](crate::storage::migrate_stored_data).
+pub const DATA_FORMAT_VERSION: u8 = 2;
 
 #[cfg(feature = "serde")]
 #[derive(Serialize, Deserialize)]
@@ -26,15 +55,333 @@ struct MemoryStoreData {
     memories: HashMap<Uuid, Memory>,
     agent_profile: AgentProfile,
     agent_state: AgentState,
+    metric: SimilarityMetric,
+    similarity_transform: SimilarityTransform,
+}
+
+/// Strategy used to choose which memory to evict when a capped
+/// [`MemoryStore`] is full.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    /// Evict the memory with the lowest current retention.
+    #[default]
+    LowestRetention,
+
+    /// Evict the memory with the earliest `timestamp`.
+    Oldest,
+
+    /// Evict the memory with the earliest `last_retrieved`.
+    LeastRecentlyRetrieved,
+}
+
+/// How a [`MemoryStore`] handles the metadata of a memory added with the ID
+/// of one it already holds.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetadataPolicy {
+    /// Keep only the incoming memory's metadata.
+    #[default]
+    Replace,
+
+    /// Deep-merge the incoming metadata into the existing metadata: objects
+    /// are merged key by key, recursively, and any other value, including
+    /// arrays, replaces the existing one.
+    Merge,
+}
+
+/// What a [`MemoryStore`] query returns when no memory scores above zero,
+/// e.g. for a query orthogonal to every stored vector.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoMatchPolicy {
+    /// Return the top `limit` memories anyway, in unspecified order.
+    #[default]
+    ReturnTopN,
+
+    /// Return no results and record no retrieval.
+    ReturnEmpty,
+}
+
+/// Which memory [`MemoryStore::merge`] keeps when both stores hold a memory
+/// with the same ID.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Keep the memory already in the store.
+    #[default]
+    KeepExisting,
+
+    /// Replace it with the incoming memory.
+    KeepIncoming,
+
+    /// Keep whichever has the higher `memory_strength`, preferring the
+    /// existing memory on a tie.
+    KeepStronger,
+}
+
+/// Exponents that weight similarity against retention in a
+/// [`MemoryStore`]'s ranking score.
+///
+/// The score is `similarity^similarity_weight * retention^retention_weight`,
+/// where the exponent is applied to the magnitude of the transformed
+/// similarity and its sign is kept. Raising `retention_weight` favors
+/// stronger memories; raising `similarity_weight` favors closer matches. A
+/// weight of zero ignores that component.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoringPolicy {
+    /// Exponent applied to the transformed similarity.
+    pub similarity_weight: f32,
+
+    /// Exponent applied to the retention.
+    pub retention_weight: f32,
+}
+
+impl Default for ScoringPolicy {
+    /// Weights both components equally, i.e. `similarity * retention`.
+    fn default() -> Self {
+        Self {
+            similarity_weight: 1.0,
+            retention_weight: 1.0,
+        }
+    }
+}
+
+impl ScoringPolicy {
+    /// Combines a transformed similarity and a retention into a score.
+    fn combine(&self, similarity: f32, retention: f32) -> f32 {
+        let similarity = if self.similarity_weight == 1.0 {
+            similarity
+        } else {
+            similarity.signum() * similarity.abs().powf(self.similarity_weight)
+        };
+        let retention = if self.retention_weight == 1.0 {
+            retention
+        } else {
+            retention.powf(self.retention_weight)
+        };
+        similarity * retention
+    }
+}
+
+/// How [`MemoryStore::find_relevant_normalized`] turns the returned scores
+/// into a distribution.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScoreNormalization {
+    /// Divide each score by the sum of the returned scores. Negative scores
+    /// count as zero; if no score is positive, the distribution is uniform.
+    #[default]
+    Sum,
+
+    /// Softmax over the returned scores at the given temperature. Lower
+    /// temperatures concentrate the distribution on the top result.
+    Softmax(f32),
+}
+
+/// Progress report returned by [`MemoryStore::maintain_budgeted`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MaintainProgress {
+    /// Number of memories examined by this call.
+    pub processed: usize,
+
+    /// Number of memories pruned by this call.
+    pub pruned: usize,
+
+    /// Number of memories left to examine in the current pass.
+    pub remaining: usize,
+
+    /// Whether this call completed a pass over the store.
+    pub finished: bool,
+}
+
+/// Aggregate statistics returned by [`MemoryStore::summary`].
+///
+/// The retention and retrieval statistics are `0.0`, and the timestamps and
+/// dimension `None`, for an empty store.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StoreSummary {
+    /// Number of memories in the store.
+    pub memory_count: usize,
+
+    /// Mean retention of the memories.
+    pub mean_retention: f32,
+
+    /// Lowest retention of any memory.
+    pub min_retention: f32,
+
+    /// Highest retention of any memory.
+    pub max_retention: f32,
+
+    /// Mean `retrieval_count` of the memories.
+    pub mean_retrieval_count: f32,
+
+    /// Formation time of the oldest memory.
+    pub oldest: Option<DateTime<Utc>>,
+
+    /// Formation time of the newest memory.
+    pub newest: Option<DateTime<Utc>>,
+
+    /// Dimension of the stored vectors; see [`MemoryStore::embedding_dim`].
+    pub embedding_dim: Option<usize>,
+}
+
+/// A memory returned by a retrieval query, together with the components of
+/// its ranking score.
+///
+/// Results are ordered by [`score`](RetrievalResult::score). Inspecting
+/// `similarity` and `retention` separately shows whether a memory ranked low
+/// because it did not match the query or because it has faded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetrievalResult {
+    /// Ranking score: the transformed similarity multiplied by retention and
+    /// by the recency boost and capacity weighting, if configured.
+    pub score: f32,
+    /// Raw similarity between the query and the memory under the store's
+    /// [`SimilarityMetric`], before the [`SimilarityTransform`] is applied.
+    pub similarity: f32,
+    /// Retention of the memory at query time, before this retrieval was
+    /// recorded.
+    pub retention: f32,
+    /// When the memory was last retrieved, including this retrieval.
+    pub last_retrieved: DateTime<Utc>,
+    /// Number of times the memory has been retrieved, including this
+    /// retrieval.
+    pub retrieval_count: u32,
+    /// Copy of the retrieved memory.
+    pub memory: Memory,
+}
+
+impl From<RetrievalResult> for (f32, Memory) {
+    fn from(result: RetrievalResult) -> Self {
+        (result.score, result.memory)
+    }
+}
+
+/// Score of one memory against a query, with its components.
+#[derive(Debug, Clone, Copy)]
+struct Scored {
+    score: f32,
+    similarity: f32,
+    retention: f32,
+    /// When the memory was formed, used to break ties in the ranking.
+    formed: DateTime<Utc>,
+}
+
+/// Orders retrieval candidates, given as `(score, formed, id)`, by
+/// descending score. Ties go to the more recently formed memory and then to
+/// the lower ID, so equally scored memories rank the same way on every run.
+pub(crate) fn rank_order(a: (f32, DateTime<Utc>, &Uuid), b: (f32, DateTime<Utc>, &Uuid)) -> std::cmp::Ordering {
+    b.0.partial_cmp(&a.0)
+        .unwrap_or(std::cmp::Ordering::Equal)
+        .then_with(|| b.1.cmp(&a.1))
+        .then_with(|| a.2.cmp(b.2))
+}
+
+/// Sorts scored memories into ranking order; see [`rank_order`].
+fn sort_ranked(scored: &mut [(Uuid, Scored)]) {
+    scored.sort_by(|(a_id, a), (b_id, b)| rank_order((a.score, a.formed, a_id), (b.score, b.formed, b_id)));
+}
+
+/// Builds the search index the enabled features provide for vectors of
+/// dimension `dim`: FAISS, then HNSW, and otherwise none.
+fn default_vector_index(dim: usize) -> Result<Option<Box<dyn VectorIndex>>> {
+    #[cfg(feature = "faiss")]
+    let index: Option<Box<dyn VectorIndex>> = Some(Box::new(crate::faiss_index::FaissIndex::new(dim)?));
+    #[cfg(all(feature = "hnsw", not(feature = "faiss")))]
+    let index: Option<Box<dyn VectorIndex>> = Some(Box::new(crate::hnsw_index::HnswIndex::new(dim)?));
+    #[cfg(not(any(feature = "faiss", feature = "hnsw")))]
+    let index = {
+        let _ = dim;
+        None
+    };
+    Ok(index)
+}
+
+/// Copy of a store's data taken by [`MemoryStore::transaction`].
+struct Snapshot {
+    memories: HashMap<Uuid, Memory>,
+    agent_profile: AgentProfile,
+    agent_state: AgentState,
+    metric: SimilarityMetric,
+    similarity_transform: SimilarityTransform,
+    scoring_policy: ScoringPolicy,
+    recency_half_life: Option<chrono::Duration>,
+    capacity_exponent: Option<f32>,
+    use_index: bool,
+    norm_cache: HashMap<Uuid, f32>,
+    #[cfg(feature = "pq")]
+    pq_index: Option<PqIndex>,
+    max_memories: Option<usize>,
+    eviction_policy: EvictionPolicy,
+    metadata_policy: MetadataPolicy,
+    no_match_policy: NoMatchPolicy,
+    normalize_on_insert: bool,
+    maintain_pending: Vec<Uuid>,
+    state_history: VecDeque<(DateTime<Utc>, AgentState)>,
+    state_history_limit: usize,
+    retention_model: Arc<dyn RetentionModel>,
+}
+
+/// Deep-merges `incoming` into `target`: objects are merged key by key and
+/// any other value replaces the one in `target`.
+fn merge_json(target: &mut serde_json::Value, incoming: serde_json::Value) {
+    match (target, incoming) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(incoming)) => {
+            for (key, value) in incoming {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, incoming) => *target = incoming,
+    }
 }
 
+/// Callback that recomputes the vector of an archived memory.
+type Rehydrator = Box<dyn Fn(&Memory) -> Option<Vec<f32>> + Send + Sync>;
+
 /// In-memory storage for memories with basic CRUD operations
 pub struct MemoryStore {
     memories: HashMap<Uuid, Memory>,
     agent_profile: AgentProfile,
     agent_state: AgentState,
-    #[cfg(feature = "faiss")]
-    faiss_index: Option<FaissIndex>,
+    metric: SimilarityMetric,
+    similarity_transform: SimilarityTransform,
+    scoring_policy: ScoringPolicy,
+    recency_half_life: Option<chrono::Duration>,
+    /// Exponent applied to `capacity_weight` when scoring, if enabled.
+    capacity_exponent: Option<f32>,
+    /// Whether queries may use a search index instead of scanning every memory.
+    use_index: bool,
+    /// Dedicated pool for parallel scoring; `None` uses the global rayon pool.
+    #[cfg(feature = "parallel")]
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+    /// Cached Euclidean norms of memory vectors, keyed by memory id.
+    norm_cache: HashMap<Uuid, f32>,
+    /// Candidate index for retrieval, kept in sync with `memories`.
+    vector_index: Option<Box<dyn VectorIndex>>,
+    #[cfg(feature = "pq")]
+    pq_index: Option<PqIndex>,
+    observers: Vec<Arc<dyn StoreObserver>>,
+    rehydrator: Option<Rehydrator>,
+    retention_model: Arc<dyn RetentionModel>,
+    max_memories: Option<usize>,
+    eviction_policy: EvictionPolicy,
+    metadata_policy: MetadataPolicy,
+    no_match_policy: NoMatchPolicy,
+    /// Whether vectors are scaled to unit length when memories are added.
+    normalize_on_insert: bool,
+    /// Memories still to be examined by the current `maintain_budgeted` pass.
+    maintain_pending: Vec<Uuid>,
+    /// Past agent states with the time each took effect, oldest first.
+    state_history: VecDeque<(DateTime<Utc>, AgentState)>,
+    /// Number of entries kept in `state_history`; `0` disables it.
+    state_history_limit: usize,
 }
 
 impl Default for MemoryStore {
@@ -67,308 +414,3983 @@ impl MemoryStore {
             memories: HashMap::new(),
             agent_profile,
             agent_state,
-            #[cfg(feature = "faiss")]
-            faiss_index: None,
+            metric: SimilarityMetric::default(),
+            similarity_transform: SimilarityTransform::default(),
+            scoring_policy: ScoringPolicy::default(),
+            recency_half_life: None,
+            capacity_exponent: None,
+            use_index: true,
+            #[cfg(feature = "parallel")]
+            thread_pool: None,
+            norm_cache: HashMap::new(),
+            vector_index: None,
+            #[cfg(feature = "pq")]
+            pq_index: None,
+            observers: Vec::new(),
+            rehydrator: None,
+            retention_model: Arc::new(DefaultRetentionModel),
+            max_memories: None,
+            eviction_policy: EvictionPolicy::default(),
+            metadata_policy: MetadataPolicy::default(),
+            no_match_policy: NoMatchPolicy::default(),
+            normalize_on_insert: false,
+            maintain_pending: Vec::new(),
+            state_history: VecDeque::new(),
+            state_history_limit: 0,
         }
     }
 
     /// Adds a new memory to the store
+    ///
+    /// If the store has a capacity limit and is full, a memory is evicted
+    /// first; see [`add_memory_capped`](MemoryStore::add_memory_capped).
+    ///
+    /// The memory is not validated; use
+    /// [`try_add_memory`](MemoryStore::try_add_memory) for memories built from
+    /// untrusted input.
     pub fn add_memory(&mut self, memory: Memory) -> Uuid {
         let id = memory.id;
-        #[cfg(feature = "faiss")]
-        {
-            if let Some(index) = &mut self.faiss_index {
-                let _ = index.add_vector(id, &memory.semantic_vector);
-            } else if let Ok(mut idx) = FaissIndex::new(memory.semantic_vector.len()) {
-                let _ = idx.add_vector(id, &memory.semantic_vector);
-                self.faiss_index = Some(idx);
-            }
-        }
-        self.memories.insert(id, memory);
+        self.add_memory_capped(memory);
         id
     }
 
-    /// Retrieves a memory by ID
-    pub fn get_memory(&self, id: &Uuid) -> Option<&Memory> {
-        self.memories.get(id)
+    /// Like [`add_memory`](MemoryStore::add_memory), but rejects memories
+    /// that fail [`Memory::validate`], such as ones whose `semantic_vector`
+    /// contains NaN or infinite values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] describing the first
+    /// violation; the store is left unchanged in that case.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// assert!(store.try_add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0)).is_ok());
+    /// assert!(store.try_add_memory(Memory::new(vec![f32::NAN, 0.2], 0.0, 25.0, 1.0)).is_err());
+    /// ```
+    pub fn try_add_memory(&mut self, memory: Memory) -> Result<Uuid> {
+        memory.validate()?;
+        Ok(self.add_memory(memory))
     }
 
-    /// Retrieves a mutable reference to a memory by ID
-    pub fn get_memory_mut(&mut self, id: &Uuid) -> Option<&mut Memory> {
-        self.memories.get_mut(id)
+    /// Adds a new memory, evicting an existing one if the store is at its
+    /// capacity limit.
+    ///
+    /// The memory to evict is chosen among the memories already in the store
+    /// according to the [`EvictionPolicy`], so the new memory is always kept.
+    /// Returns the ID of the evicted memory, if any.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.set_max_memories(Some(1)).unwrap();
+    /// let first = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    /// let evicted = store.add_memory_capped(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0));
+    /// assert_eq!(evicted, Some(first));
+    /// ```
+    pub fn add_memory_capped(&mut self, mut memory: Memory) -> Option<Uuid> {
+        if self.normalize_on_insert {
+            simd::normalize(&mut memory.semantic_vector);
+        }
+        // Replacing a stored memory does not grow the store.
+        let evicted = if self.memories.contains_key(&memory.id) {
+            None
+        } else {
+            self.make_room()
+        };
+        if self.vector_index.is_none() {
+            self.vector_index = default_vector_index(memory.semantic_vector.len()).ok().flatten();
+        }
+        if let Some(index) = &mut self.vector_index {
+            let _ = index.add_vector(memory.id, &memory.semantic_vector);
+        }
+        self.insert_memory(memory);
+        evicted
     }
 
-    /// Removes a memory by ID.
+    /// Adds many memories at once, returning their IDs in insertion order.
+    ///
+    /// Capacity is reserved up front, and when a search index is set all
+    /// vectors are added to it in a single batch. Prefer this over
+    /// repeated [`add_memory`](MemoryStore::add_memory) calls when restoring
+    /// large histories.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let ids = store.add_memories((0..3).map(|i| Memory::new(vec![i as f32, 1.0], 0.0, 25.0, 1.0)));
+    /// assert_eq!(ids.len(), 3);
+    /// ```
+    pub fn add_memories(&mut self, memories: impl IntoIterator<Item = Memory>) -> Vec<Uuid> {
+        let memories: Vec<Memory> = memories.into_iter().collect();
+        self.memories.reserve(memories.len());
+        self.norm_cache.reserve(memories.len());
+        self.insert_reserved(memories)
+    }
+
+    /// Like [`add_memories`](MemoryStore::add_memories), but validates every
+    /// memory first and fails instead of aborting the process if capacity
+    /// for the memories cannot be reserved.
+    ///
+    /// Use this when the memories come from untrusted input.
     ///
     /// # Errors
     ///
-    /// Returns [`MemoryError::NotFound`] if the requested memory does not exist.
-    pub fn remove_memory(&mut self, id: &Uuid) -> Result<()> {
-        self.memories
-            .remove(id)
-            .map(|_| ())
-            .ok_or_else(|| MemoryError::not_found(id))
+    /// Returns [`MemoryError::InvalidParameter`] if a memory fails
+    /// [`Memory::validate`], and [`MemoryError::Storage`] if the allocation
+    /// fails; the store is left unchanged in either case.
+    pub fn try_add_memories(&mut self, memories: impl IntoIterator<Item = Memory>) -> Result<Vec<Uuid>> {
+        let memories: Vec<Memory> = memories.into_iter().collect();
+        for memory in &memories {
+            memory.validate()?;
+        }
+        self.try_reserve(memories.len())?;
+        Ok(self.insert_reserved(memories))
     }
 
-    /// Finds memories matching a query vector, ordered by relevance.
+    /// Reserves capacity for at least `additional` more memories without
+    /// aborting on allocation failure.
     ///
     /// # Errors
     ///
-    /// Returns [`MemoryError::NotFound`] if no memories exist in the store.
+    /// Returns [`MemoryError::Storage`] if the capacity overflows or the
+    /// allocator reports a failure.
     ///
-    /// # Panics
+    /// # Example
     ///
-    /// Panics if the provided `limit` is `0`.
-    pub fn find_relevant(
-        &mut self,
-        query_vector: &[f32],
-        limit: usize,
-    ) -> Result<Vec<(f32, Memory)>> {
-        let now = Utc::now();
-
-        #[cfg(feature = "faiss")]
-        let mut scored: Vec<_> = if let Some(index) = &self.faiss_index {
-            index
-                .search(query_vector, limit)?
-                .into_iter()
-                .filter_map(|(dist, id)| {
-                    self.memories.get(&id).map(|mem| {
-                        let retention = mem.calculate_retention(now, &self.agent_state, &self.agent_profile);
-                        (id, (1.0 / (1.0 + dist)) * retention)
-                    })
-                })
-                .collect()
-        } else {
-            self
-                .memories
-                .iter()
-                .map(|(id, mem)| {
-                    let similarity = cosine_similarity(query_vector, &mem.semantic_vector);
-                    let retention = mem.calculate_retention(now, &self.agent_state, &self.agent_profile);
-                    (*id, similarity * retention)
-                })
-                .collect()
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// assert!(store.try_reserve(1_000).is_ok());
+    /// assert!(store.try_reserve(usize::MAX).is_err());
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<()> {
+        let to_error = |e: std::collections::TryReserveError| {
+            MemoryError::Storage(format!("Failed to reserve space for {} memories: {}", additional, e))
         };
+        self.memories.try_reserve(additional).map_err(to_error)?;
+        self.norm_cache.try_reserve(additional).map_err(to_error)
+    }
 
-        #[cfg(not(feature = "faiss"))]
-        let mut scored: Vec<_> = self
-            .memories
-            .iter()
-            .map(|(id, mem)| {
-                let similarity = cosine_similarity(query_vector, &mem.semantic_vector);
-                let retention = mem.calculate_retention(now, &self.agent_state, &self.agent_profile);
-                (*id, similarity * retention)
+    /// Inserts memories whose capacity has already been reserved.
+    fn insert_reserved(&mut self, mut memories: Vec<Memory>) -> Vec<Uuid> {
+        if self.normalize_on_insert {
+            for memory in &mut memories {
+                simd::normalize(&mut memory.semantic_vector);
+            }
+        }
+        let ids: Vec<Uuid> = memories
+            .into_iter()
+            .map(|memory| {
+                if !self.memories.contains_key(&memory.id) {
+                    self.make_room();
+                }
+                self.insert_memory(memory)
             })
             .collect();
 
-        // Sort by score in descending order
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        // Index only what survived eviction, once each.
+        let mut seen = HashSet::new();
+        let batch: Vec<(Uuid, &[f32])> = ids
+            .iter()
+            .filter(|id| seen.insert(**id))
+            .filter_map(|id| self.memories.get(id))
+            .map(|m| (m.id, m.semantic_vector.as_slice()))
+            .collect();
+        if let Some((_, first)) = batch.first() {
+            if self.vector_index.is_none() {
+                self.vector_index = default_vector_index(first.len()).ok().flatten();
+            }
+            if let Some(index) = &mut self.vector_index {
+                let _ = index.add_vectors(&batch);
+            }
+        }
+        ids
+    }
 
-        // Take top N and update their retrieval history
-        let top_n = scored.into_iter().take(limit).collect::<Vec<_>>();
-        
-        // Update retrieval history for top memories
-        for (id, _) in &top_n {
-            if let Some(mem) = self.memories.get_mut(id) {
-                mem.record_retrieval(self.agent_profile.rho);
+    /// Sets the maximum number of memories the store may hold, or removes
+    /// the limit with `None`.
+    ///
+    /// If the store currently holds more memories than the new limit, the
+    /// excess is evicted immediately according to the [`EvictionPolicy`] and
+    /// the IDs of the evicted memories are returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `max_memories` is `Some(0)`.
+    pub fn set_max_memories(&mut self, max_memories: Option<usize>) -> Result<Vec<Uuid>> {
+        if max_memories == Some(0) {
+            return Err(MemoryError::invalid_param("max_memories", 0));
+        }
+        self.max_memories = max_memories;
+
+        let mut evicted = Vec::new();
+        while let Some(max) = self.max_memories {
+            if self.memories.len() <= max {
+                break;
+            }
+            match self.evict_one() {
+                Some(id) => evicted.push(id),
+                None => break,
             }
         }
+        Ok(evicted)
+    }
 
-        // Return copies of the top memories with their scores
-        let result = top_n.into_iter()
-            .filter_map(|(id, score)| {
-                self.memories.get(&id).map(|mem| (score, mem.clone()))
-            })
-            .collect();
-            
-        Ok(result)
+    /// Returns the maximum number of memories the store may hold.
+    pub fn max_memories(&self) -> Option<usize> {
+        self.max_memories
     }
 
-    /// Finds relevant memories for multiple query vectors in a single call.
-    ///
-    /// This is a convenience wrapper that iterates over each query vector and
-    /// returns a vector of results per query.
-    pub fn find_relevant_batch(
-        &mut self,
-        query_vectors: &[Vec<f32>],
-        limit: usize,
-    ) -> Result<Vec<Vec<(f32, Memory)>>> {
-        query_vectors
-            .iter()
-            .map(|q| self.find_relevant(q, limit))
-            .collect()
+    /// Sets the [`EvictionPolicy`] used when the store is at capacity.
+    pub fn set_eviction_policy(&mut self, policy: EvictionPolicy) {
+        self.eviction_policy = policy;
     }
 
-    /// Performs maintenance operations like pruning old memories.
+    /// Returns the [`EvictionPolicy`] used when the store is at capacity.
+    pub fn eviction_policy(&self) -> EvictionPolicy {
+        self.eviction_policy
+    }
+
+    /// Sets the [`NoMatchPolicy`] deciding what queries return when no memory
+    /// scores above zero.
     ///
-    /// Returns the number of memories that were pruned.
+    /// # Example
     ///
-    /// # Panics
+    /// ```
+    /// use memory_module::prelude::*;
     ///
-    /// Panics if `retention_threshold` is not within `0.0..=1.0`.
-    pub fn maintain(&mut self, retention_threshold: f32) -> usize {
-        assert!(
-            (0.0..=1.0).contains(&retention_threshold),
-            "retention_threshold must be between 0.0 and 1.0"
-        );
-        let now = Utc::now();
-        let before = self.memories.len();
-        
-        self.memories.retain(|_id, mem| {
-            let retention = mem.calculate_retention(now, &self.agent_state, &self.agent_profile);
-            retention >= retention_threshold
-        });
-        
-        before - self.memories.len()
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+    /// store.set_no_match_policy(NoMatchPolicy::ReturnEmpty);
+    /// assert!(store.find_relevant(&[0.0, 1.0], 1).unwrap().is_empty());
+    /// ```
+    pub fn set_no_match_policy(&mut self, policy: NoMatchPolicy) {
+        self.no_match_policy = policy;
     }
 
-    /// Updates the agent's state
-    pub fn update_agent_state(&mut self, state: AgentState) {
-        self.agent_state = state;
+    /// Returns the [`NoMatchPolicy`] used by queries.
+    pub fn no_match_policy(&self) -> NoMatchPolicy {
+        self.no_match_policy
     }
 
-    /// Gets the current agent profile
-    pub fn agent_profile(&self) -> &AgentProfile {
-        &self.agent_profile
+    /// Sets whether each memory's `semantic_vector` is scaled to unit length
+    /// when it is added.
+    ///
+    /// This changes the stored vectors: the original magnitudes are lost.
+    /// Memories already in the store are left as they are. Once every vector
+    /// is normalized, [`SimilarityMetric::DotProduct`] ranks memories like
+    /// [`SimilarityMetric::Cosine`] without dividing by the vector norms.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.set_normalize_on_insert(true);
+    /// let id = store.add_memory(Memory::new(vec![3.0, 4.0], 0.0, 25.0, 1.0));
+    /// assert_eq!(store.get_memory(&id).unwrap().semantic_vector, vec![0.6, 0.8]);
+    /// ```
+    pub fn set_normalize_on_insert(&mut self, normalize: bool) {
+        self.normalize_on_insert = normalize;
     }
 
-    /// Gets the current agent state
-    pub fn agent_state(&self) -> &AgentState {
-        &self.agent_state
+    /// Returns whether vectors are normalized when memories are added.
+    pub fn normalize_on_insert(&self) -> bool {
+        self.normalize_on_insert
     }
-}
 
-#[cfg(feature = "serde")]
-impl Serialize for MemoryStore {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let data = MemoryStoreData {
-            version: DATA_FORMAT_VERSION,
-            memories: self.memories.clone(),
-            agent_profile: self.agent_profile.clone(),
-            agent_state: self.agent_state.clone(),
-        };
-        data.serialize(serializer)
+    /// Sets the [`MetadataPolicy`] used when a memory is added with the ID of
+    /// one already in the store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.set_metadata_policy(MetadataPolicy::Merge);
+    ///
+    /// let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// memory.metadata = serde_json::json!({ "a": 1 });
+    /// let id = store.add_memory(memory.clone());
+    /// memory.metadata = serde_json::json!({ "b": 2 });
+    /// store.add_memory(memory);
+    ///
+    /// assert_eq!(store.get_memory(&id).unwrap().metadata, serde_json::json!({ "a": 1, "b": 2 }));
+    /// ```
+    pub fn set_metadata_policy(&mut self, policy: MetadataPolicy) {
+        self.metadata_policy = policy;
     }
-}
 
-#[cfg(feature = "serde")]
-impl<'de> Deserialize<'de> for MemoryStore {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    /// Returns the [`MetadataPolicy`] used when a memory is added with the ID
+    /// of one already in the store.
+    pub fn metadata_policy(&self) -> MetadataPolicy {
+        self.metadata_policy
+    }
+
+    /// Evicts a memory if inserting one more would exceed the capacity limit.
+    fn make_room(&mut self) -> Option<Uuid> {
+        match self.max_memories {
+            Some(max) if self.memories.len() >= max => self.evict_one(),
+            _ => None,
+        }
+    }
+
+    /// Removes the memory selected by the eviction policy and returns its ID.
+    fn evict_one(&mut self) -> Option<Uuid> {
+        let candidates = self.memories.values();
+        let victim = match self.eviction_policy {
+            EvictionPolicy::LowestRetention => {
+                // Capacity always counts towards eviction, even when the
+                // profile keeps it out of retention.
+                let profile = AgentProfile {
+                    use_capacity_in_retention: true,
+                    ..self.agent_profile.clone()
+                };
+                let now = Utc::now();
+                candidates
+                    .map(|m| (m.id, self.retention_model.retention(m, now, &self.agent_state, &profile)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(id, _)| id)
+            }
+            EvictionPolicy::Oldest => candidates.min_by_key(|m| m.timestamp).map(|m| m.id),
+            EvictionPolicy::LeastRecentlyRetrieved => {
+                candidates.min_by_key(|m| m.last_retrieved).map(|m| m.id)
+            }
+        }?;
+        self.remove_memory(&victim).ok()?;
+        Some(victim)
+    }
+
+    /// Inserts a memory and updates every cache except the search index.
+    fn insert_memory(&mut self, mut memory: Memory) -> Uuid {
+        let id = memory.id;
+        if self.metadata_policy == MetadataPolicy::Merge {
+            if let Some(existing) = self.memories.get(&id) {
+                let mut metadata = existing.metadata.clone();
+                merge_json(&mut metadata, std::mem::take(&mut memory.metadata));
+                memory.metadata = metadata;
+            }
+        }
+        #[cfg(feature = "pq")]
+        if let Some(pq) = &mut self.pq_index {
+            pq.insert(id, &memory.semantic_vector);
+        }
+        self.norm_cache.insert(id, simd::norm(&memory.semantic_vector));
+        for observer in &self.observers {
+            observer.on_add(&memory);
+        }
+        self.memories.insert(id, memory);
+        id
+    }
+
+    /// Runs `f` against the store as a single all-or-nothing operation.
+    ///
+    /// If `f` returns `Err`, or panics, every change it made to the store's
+    /// memories, agent profile and state, and configuration is rolled back.
+    /// A panic is re-raised after the rollback. Observer notifications that
+    /// were already sent are not undone. A rolled-back store rebuilds its
+    /// search index from the restored memories.
+    ///
+    /// This clones all memories up front, so each call costs time and memory
+    /// proportional to the size of the store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    /// use memory_module::error::MemoryError;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let result: Result<()> = store.transaction(|store| {
+    ///     store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    ///     Err(MemoryError::InvalidParameter("abort".to_string()))
+    /// });
+    /// assert!(result.is_err());
+    /// assert_eq!(store.embedding_dim(), None);
+    /// ```
+    pub fn transaction<T, F>(&mut self, f: F) -> Result<T>
     where
-        D: serde::Deserializer<'de>,
+        F: FnOnce(&mut MemoryStore) -> Result<T>,
     {
-        let data = MemoryStoreData::deserialize(deserializer)?;
-        if data.version != DATA_FORMAT_VERSION {
-            return Err(serde::de::Error::custom(format!(
-                "Incompatible data format version: expected {}, found {}",
-                DATA_FORMAT_VERSION, data.version
-            )));
+        let snapshot = self.snapshot();
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(self))) {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(err)) => {
+                self.restore(snapshot);
+                Err(err)
+            }
+            Err(panic) => {
+                self.restore(snapshot);
+                std::panic::resume_unwind(panic)
+            }
+        }
+    }
+
+    /// Copies the data that [`transaction`](MemoryStore::transaction) rolls back.
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            memories: self.memories.clone(),
+            agent_profile: self.agent_profile.clone(),
+            agent_state: self.agent_state.clone(),
+            metric: self.metric,
+            similarity_transform: self.similarity_transform,
+            scoring_policy: self.scoring_policy,
+            recency_half_life: self.recency_half_life,
+            capacity_exponent: self.capacity_exponent,
+            use_index: self.use_index,
+            norm_cache: self.norm_cache.clone(),
+            #[cfg(feature = "pq")]
+            pq_index: self.pq_index.clone(),
+            max_memories: self.max_memories,
+            eviction_policy: self.eviction_policy,
+            metadata_policy: self.metadata_policy,
+            no_match_policy: self.no_match_policy,
+            normalize_on_insert: self.normalize_on_insert,
+            maintain_pending: self.maintain_pending.clone(),
+            state_history: self.state_history.clone(),
+            state_history_limit: self.state_history_limit,
+            retention_model: self.retention_model.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.memories = snapshot.memories;
+        self.agent_profile = snapshot.agent_profile;
+        self.agent_state = snapshot.agent_state;
+        self.metric = snapshot.metric;
+        self.similarity_transform = snapshot.similarity_transform;
+        self.scoring_policy = snapshot.scoring_policy;
+        self.recency_half_life = snapshot.recency_half_life;
+        self.capacity_exponent = snapshot.capacity_exponent;
+        self.use_index = snapshot.use_index;
+        self.norm_cache = snapshot.norm_cache;
+        #[cfg(feature = "pq")]
+        {
+            self.pq_index = snapshot.pq_index;
+        }
+        let _ = self.rebuild_index();
+        self.max_memories = snapshot.max_memories;
+        self.eviction_policy = snapshot.eviction_policy;
+        self.metadata_policy = snapshot.metadata_policy;
+        self.no_match_policy = snapshot.no_match_policy;
+        self.normalize_on_insert = snapshot.normalize_on_insert;
+        self.maintain_pending = snapshot.maintain_pending;
+        self.state_history = snapshot.state_history;
+        self.state_history_limit = snapshot.state_history_limit;
+        self.retention_model = snapshot.retention_model;
+    }
+
+    /// Creates a new store holding deep copies of the memories that match
+    /// `f`.
+    ///
+    /// The new store shares this store's profile, agent state, similarity
+    /// settings and capacity limit, but starts without observers, a
+    /// rehydrator or product-quantization codes; norms are computed on
+    /// demand. The features' default search index is built for the copies,
+    /// while an index set with
+    /// [`set_vector_index`](MemoryStore::set_vector_index) is not carried
+    /// over. Changes to either store do not affect the other.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![0.1, 0.2], 0.9, 25.0, 1.0));
+    /// store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0));
+    ///
+    /// let emotional = store.clone_filtered(|m| m.emotion > 0.5);
+    /// assert_eq!(emotional.embedding_dim(), Some(2));
+    /// ```
+    pub fn clone_filtered<F: Fn(&Memory) -> bool>(&self, f: F) -> MemoryStore {
+        let mut store = self.empty_like();
+        store.memories = self
+            .memories
+            .iter()
+            .filter(|(_, mem)| f(mem))
+            .map(|(id, mem)| (*id, mem.clone()))
+            .collect();
+        let _ = store.rebuild_index();
+        store
+    }
+
+    /// Consumes the store, transforming each memory with `f` into a new
+    /// store and dropping memories mapped to `None`.
+    ///
+    /// Useful for pipeline stages such as moving to a smaller embedding
+    /// model or stripping metadata. The new store keeps this store's
+    /// profile, agent state and settings like
+    /// [`clone_filtered`](MemoryStore::clone_filtered), and memories are keyed
+    /// by the id `f` returns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![0.1, 0.2, 0.3], 0.0, 25.0, 1.0));
+    ///
+    /// let truncated = store.map_into(|mut m| {
+    ///     m.semantic_vector.truncate(2);
+    ///     Some(m)
+    /// });
+    /// assert_eq!(truncated.embedding_dim(), Some(2));
+    /// ```
+    pub fn map_into<F: Fn(Memory) -> Option<Memory>>(self, f: F) -> MemoryStore {
+        let mut store = self.empty_like();
+        store.memories = self
+            .memories
+            .into_values()
+            .filter_map(f)
+            .map(|mem| (mem.id, mem))
+            .collect();
+        let _ = store.rebuild_index();
+        store
+    }
+
+    /// Creates an empty store with this store's profile, agent state and
+    /// settings.
+    pub(crate) fn empty_like(&self) -> MemoryStore {
+        let mut store = Self::new(self.agent_profile.clone(), self.agent_state.clone());
+        store.metric = self.metric;
+        store.similarity_transform = self.similarity_transform;
+        store.scoring_policy = self.scoring_policy;
+        store.recency_half_life = self.recency_half_life;
+        store.capacity_exponent = self.capacity_exponent;
+        store.use_index = self.use_index;
+        #[cfg(feature = "parallel")]
+        {
+            store.thread_pool = self.thread_pool.clone();
+        }
+        store.max_memories = self.max_memories;
+        store.eviction_policy = self.eviction_policy;
+        store.metadata_policy = self.metadata_policy;
+        store.no_match_policy = self.no_match_policy;
+        store.normalize_on_insert = self.normalize_on_insert;
+        store.state_history = self.state_history.clone();
+        store.state_history_limit = self.state_history_limit;
+        store.retention_model = self.retention_model.clone();
+        store
+    }
+
+    /// Retrieves a memory by ID
+    pub fn get_memory(&self, id: &Uuid) -> Option<&Memory> {
+        self.memories.get(id)
+    }
+
+    /// Returns the dimension of the memory vectors in the store.
+    ///
+    /// The dimension is inferred from the stored memories, skipping archived
+    /// memories without a vector. Returns `None` if the store holds no
+    /// memory with a vector.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// assert_eq!(store.embedding_dim(), None);
+    /// store.add_memory(Memory::new(vec![0.1, 0.2, 0.3], 0.0, 25.0, 1.0));
+    /// assert_eq!(store.embedding_dim(), Some(3));
+    /// ```
+    pub fn embedding_dim(&self) -> Option<usize> {
+        self.memories
+            .values()
+            .map(|m| m.semantic_vector.len())
+            .find(|&len| len > 0)
+    }
+
+    /// Returns the sum of the `capacity_weight` of all memories.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 0.5));
+    /// store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 0.25));
+    /// assert_eq!(store.total_capacity_used(), 0.75);
+    /// ```
+    pub fn total_capacity_used(&self) -> f32 {
+        self.memories.values().map(|m| m.capacity_weight).sum()
+    }
+
+    /// Retrieves a mutable reference to a memory by ID
+    ///
+    /// Since the caller may change the memory's vector, its cached norm is
+    /// discarded; call [`warm_up`](MemoryStore::warm_up) to recompute it.
+    pub fn get_memory_mut(&mut self, id: &Uuid) -> Option<&mut Memory> {
+        self.norm_cache.remove(id);
+        self.memories.get_mut(id)
+    }
+
+    /// Marks a memory as seen now without strengthening it.
+    ///
+    /// See [`Memory::touch`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::NotFound`] if the requested memory does not exist.
+    pub fn touch(&mut self, id: &Uuid) -> Result<()> {
+        self.memories
+            .get_mut(id)
+            .map(|mem| mem.touch(Utc::now()))
+            .ok_or_else(|| MemoryError::not_found(id))
+    }
+
+    /// Blends a memory's emotion toward `new_emotion`, modelling how recalling
+    /// an event in a new context can change how it feels.
+    ///
+    /// The stored emotion becomes `emotion + blend * (new_emotion - emotion)`,
+    /// clamped to `-1.0..=1.0`. `blend` is clamped to `0.0..=1.0`: 0.0 leaves
+    /// the emotion unchanged and 1.0 replaces it. Retention is recomputed from
+    /// the new emotion on the next query; `memory_strength` is unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `new_emotion` or `blend`
+    /// is NaN or infinite, and [`MemoryError::NotFound`] if the requested
+    /// memory does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let id = store.add_memory(Memory::new(vec![0.1, 0.2], -0.8, 25.0, 1.0));
+    /// store.reconsolidate(&id, 0.4, 0.5).unwrap();
+    /// assert!((store.get_memory(&id).unwrap().emotion - -0.2).abs() < 1e-6);
+    /// ```
+    pub fn reconsolidate(&mut self, id: &Uuid, new_emotion: f32, blend: f32) -> Result<()> {
+        if !new_emotion.is_finite() {
+            return Err(MemoryError::invalid_param("new_emotion", new_emotion));
+        }
+        if !blend.is_finite() {
+            return Err(MemoryError::invalid_param("blend", blend));
+        }
+        let mem = self
+            .memories
+            .get_mut(id)
+            .ok_or_else(|| MemoryError::not_found(id))?;
+        let blend = blend.clamp(0.0, 1.0);
+        mem.emotion = (mem.emotion + blend * (new_emotion - mem.emotion)).clamp(-1.0, 1.0);
+        Ok(())
+    }
+
+    /// Changes a memory's `capacity_weight`, clamped to `0.0..=1.0`.
+    ///
+    /// The new weight is reflected by
+    /// [`total_capacity_used`](MemoryStore::total_capacity_used) and, when
+    /// [`AgentProfile::use_capacity_in_retention`] is set, by the memory's
+    /// retention on the next query. Retention is always computed on demand,
+    /// so there is no cached value to go stale.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `weight` is NaN or
+    /// infinite, and [`MemoryError::NotFound`] if the requested memory does
+    /// not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    /// store.set_capacity_weight(&id, 0.4).unwrap();
+    /// assert_eq!(store.get_memory(&id).unwrap().capacity_weight, 0.4);
+    /// store.set_capacity_weight(&id, 2.0).unwrap();
+    /// assert_eq!(store.get_memory(&id).unwrap().capacity_weight, 1.0);
+    /// ```
+    pub fn set_capacity_weight(&mut self, id: &Uuid, weight: f32) -> Result<()> {
+        if !weight.is_finite() {
+            return Err(MemoryError::invalid_param("capacity_weight", weight));
+        }
+        let mem = self
+            .memories
+            .get_mut(id)
+            .ok_or_else(|| MemoryError::not_found(id))?;
+        mem.capacity_weight = weight.clamp(0.0, 1.0);
+        Ok(())
+    }
+
+    /// Drops the semantic vector of a memory to save space, keeping the rest
+    /// of the memory in the store.
+    ///
+    /// An archived memory has an empty `semantic_vector` and scores no
+    /// similarity against queries until its vector is restored, either
+    /// directly or by a rehydrator (see
+    /// [`set_rehydrator`](MemoryStore::set_rehydrator)).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::NotFound`] if the requested memory does not exist.
+    pub fn archive_vector(&mut self, id: &Uuid) -> Result<()> {
+        let mem = self
+            .memories
+            .get_mut(id)
+            .ok_or_else(|| MemoryError::not_found(id))?;
+        mem.semantic_vector = Vec::new();
+        self.norm_cache.remove(id);
+        #[cfg(feature = "pq")]
+        if let Some(pq) = &mut self.pq_index {
+            pq.remove(id);
+        }
+        if let Some(index) = &mut self.vector_index {
+            let _ = index.remove_vector(*id);
+        }
+        Ok(())
+    }
+
+    /// Sets a callback that recomputes the vectors of archived memories.
+    ///
+    /// Before every [`find_relevant`](MemoryStore::find_relevant) query, the
+    /// callback is invoked for each memory with an empty `semantic_vector`,
+    /// for example to re-embed text kept in its `metadata`. Returned vectors
+    /// are stored on the memory, so each memory is rehydrated at most once;
+    /// memories for which the callback returns `None` stay archived.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let id = store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+    /// store.archive_vector(&id).unwrap();
+    ///
+    /// store.set_rehydrator(|_memory| Some(vec![1.0, 0.0]));
+    /// let results = store.find_relevant(&[1.0, 0.0], 1).unwrap();
+    /// assert_eq!(results[0].memory.semantic_vector, vec![1.0, 0.0]);
+    /// ```
+    pub fn set_rehydrator(&mut self, f: impl Fn(&Memory) -> Option<Vec<f32>> + Send + Sync + 'static) {
+        self.rehydrator = Some(Box::new(f));
+    }
+
+    /// Restores the vectors of archived memories using the rehydrator set by
+    /// [`set_rehydrator`](MemoryStore::set_rehydrator).
+    ///
+    /// Returns the number of memories that were rehydrated, which is always
+    /// `0` when no rehydrator is set. Rehydrated vectors are added back to
+    /// the search index, if one is built.
+    pub fn rehydrate_vectors(&mut self) -> usize {
+        let Some(rehydrator) = &self.rehydrator else {
+            return 0;
+        };
+        let mut restored = 0;
+        for (id, mem) in self.memories.iter_mut() {
+            if !mem.semantic_vector.is_empty() {
+                continue;
+            }
+            if let Some(vector) = rehydrator(mem) {
+                #[cfg(feature = "pq")]
+                if let Some(pq) = &mut self.pq_index {
+                    pq.insert(*id, &vector);
+                }
+                if let Some(index) = &mut self.vector_index {
+                    let _ = index.add_vector(*id, &vector);
+                }
+                self.norm_cache.insert(*id, simd::norm(&vector));
+                mem.semantic_vector = vector;
+                restored += 1;
+            }
+        }
+        restored
+    }
+
+    /// Removes a memory by ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::NotFound`] if the requested memory does not exist.
+    pub fn remove_memory(&mut self, id: &Uuid) -> Result<()> {
+        self.take_memory(id)
+            .ok_or_else(|| MemoryError::not_found(id))?;
+        for observer in &self.observers {
+            observer.on_remove(id);
+        }
+        Ok(())
+    }
+
+    /// Removes every memory from the store.
+    ///
+    /// Caches and indexes are emptied as well; a trained product quantizer is
+    /// kept so new memories are still encoded. Observers are notified of each
+    /// removal.
+    pub fn clear(&mut self) {
+        if !self.observers.is_empty() {
+            for id in self.memories.keys() {
+                for observer in &self.observers {
+                    observer.on_remove(id);
+                }
+            }
+        }
+        self.memories.clear();
+        self.norm_cache.clear();
+        self.maintain_pending.clear();
+        #[cfg(feature = "pq")]
+        if let Some(pq) = &mut self.pq_index {
+            pq.retain(|_| false);
+        }
+        if let Some(index) = &mut self.vector_index {
+            let _ = index.rebuild(&[]);
+        }
+    }
+
+    /// Removes every memory for which `predicate` returns `true` and returns
+    /// the number removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let mut tagged = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// tagged.metadata = serde_json::json!({ "session": "123" });
+    /// store.add_memory(tagged);
+    /// let kept = store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0));
+    ///
+    /// assert_eq!(store.remove_matching(|m| m.metadata["session"] == "123"), 1);
+    /// assert!(store.get_memory(&kept).is_some());
+    /// ```
+    pub fn remove_matching(&mut self, predicate: impl Fn(&Memory) -> bool) -> usize {
+        let matching: Vec<Uuid> = self
+            .memories
+            .values()
+            .filter(|mem| predicate(mem))
+            .map(|mem| mem.id)
+            .collect();
+        for id in &matching {
+            self.take_memory(id);
+            for observer in &self.observers {
+                observer.on_remove(id);
+            }
+        }
+        matching.len()
+    }
+
+    /// Moves the memories of `other` into this store and returns how many
+    /// were added or replaced.
+    ///
+    /// Memories whose ID is already present are resolved by `on_conflict`; a
+    /// replaced memory is swapped out whole, without applying the
+    /// [`MetadataPolicy`]. Incoming memories go through
+    /// [`add_memory`](MemoryStore::add_memory), so they are indexed, reported
+    /// to observers and subject to the capacity limit. This store's agent
+    /// profile, state and settings are left unchanged; those of `other` are
+    /// dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut base = MemoryStore::default();
+    /// base.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    /// let mut user = MemoryStore::default();
+    /// let id = user.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0));
+    ///
+    /// assert_eq!(base.merge(user, ConflictPolicy::KeepExisting), 1);
+    /// assert!(base.get_memory(&id).is_some());
+    /// ```
+    pub fn merge(&mut self, other: MemoryStore, on_conflict: ConflictPolicy) -> usize {
+        let mut merged = 0;
+        for memory in other.memories.into_values() {
+            if let Some(existing) = self.memories.get(&memory.id) {
+                let replace = match on_conflict {
+                    ConflictPolicy::KeepExisting => false,
+                    ConflictPolicy::KeepIncoming => true,
+                    ConflictPolicy::KeepStronger => memory.memory_strength > existing.memory_strength,
+                };
+                if !replace {
+                    continue;
+                }
+                self.take_memory(&memory.id);
+            }
+            self.add_memory_capped(memory);
+            merged += 1;
+        }
+        merged
+    }
+
+    /// Iterates over the stored memories in unspecified order.
+    pub(crate) fn memories(&self) -> impl Iterator<Item = &Memory> {
+        self.memories.values()
+    }
+
+    /// Creates a store holding `memories` and builds its search index.
+    pub(crate) fn from_memories(
+        agent_profile: AgentProfile,
+        agent_state: AgentState,
+        memories: HashMap<Uuid, Memory>,
+    ) -> Result<Self> {
+        let mut store = Self::new(agent_profile, agent_state);
+        store.memories = memories;
+        store.rebuild_index()?;
+        Ok(store)
+    }
+
+    /// Removes a memory and its cache entries without notifying observers.
+    pub(crate) fn take_memory(&mut self, id: &Uuid) -> Option<Memory> {
+        self.norm_cache.remove(id);
+        #[cfg(feature = "pq")]
+        if let Some(pq) = &mut self.pq_index {
+            pq.remove(id);
+        }
+        if let Some(index) = &mut self.vector_index {
+            let _ = index.remove_vector(*id);
+        }
+        self.memories.remove(id)
+    }
+
+    /// Validates every memory in the store, collecting all violations.
+    ///
+    /// Unlike calling [`Memory::validate`] in a loop with `?`, this does not
+    /// stop at the first invalid memory. The returned list is empty when all
+    /// memories are valid; its order is unspecified.
+    pub fn validate_all(&self) -> Vec<(Uuid, MemoryError)> {
+        self.memories
+            .iter()
+            .filter_map(|(id, mem)| mem.validate().err().map(|err| (*id, err)))
+            .collect()
+    }
+
+    /// Counts memories grouped by a key extracted from each memory.
+    ///
+    /// Memories for which `key_fn` returns `None` are not counted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![0.1], 0.8, 25.0, 1.0));
+    /// store.add_memory(Memory::new(vec![0.2], -0.4, 25.0, 1.0));
+    /// store.add_memory(Memory::new(vec![0.3], 0.5, 25.0, 1.0));
+    ///
+    /// let by_valence = store.count_by(|m| Some(m.emotion >= 0.0));
+    /// assert_eq!(by_valence[&true], 2);
+    /// assert_eq!(by_valence[&false], 1);
+    /// ```
+    pub fn count_by<K, F>(&self, key_fn: F) -> HashMap<K, usize>
+    where
+        K: Eq + std::hash::Hash,
+        F: Fn(&Memory) -> Option<K>,
+    {
+        let mut counts = HashMap::new();
+        for key in self.memories.values().filter_map(key_fn) {
+            *counts.entry(key).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Summarizes the store's contents, with retention evaluated now.
+    ///
+    /// Nothing is modified; in particular no retrieval is recorded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![0.1, 0.2, 0.3], 0.0, 25.0, 1.0));
+    /// store.add_memory(Memory::new(vec![0.4, 0.5, 0.6], 0.5, 25.0, 1.0));
+    ///
+    /// let summary = store.summary();
+    /// assert_eq!(summary.memory_count, 2);
+    /// assert_eq!(summary.embedding_dim, Some(3));
+    /// assert!(summary.min_retention <= summary.mean_retention);
+    /// ```
+    pub fn summary(&self) -> StoreSummary {
+        self.summary_at(Utc::now())
+    }
+
+    /// Like [`summary`](MemoryStore::summary), but evaluates retention at
+    /// `at` under the current agent state and profile.
+    pub fn summary_at(&self, at: DateTime<Utc>) -> StoreSummary {
+        if self.memories.is_empty() {
+            return StoreSummary::default();
+        }
+        let count = self.memories.len() as f32;
+        let mut summary = StoreSummary {
+            memory_count: self.memories.len(),
+            min_retention: f32::INFINITY,
+            max_retention: f32::NEG_INFINITY,
+            embedding_dim: self.embedding_dim(),
+            ..StoreSummary::default()
+        };
+        for mem in self.memories.values() {
+            let retention = self.retention_model.retention(mem, at, &self.agent_state, &self.agent_profile);
+            summary.mean_retention += retention / count;
+            summary.min_retention = summary.min_retention.min(retention);
+            summary.max_retention = summary.max_retention.max(retention);
+            summary.mean_retrieval_count += mem.retrieval_count as f32 / count;
+            summary.oldest = Some(summary.oldest.map_or(mem.timestamp, |t| t.min(mem.timestamp)));
+            summary.newest = Some(summary.newest.map_or(mem.timestamp, |t| t.max(mem.timestamp)));
+        }
+        summary
+    }
+
+    /// Returns a hash of the store's contents for change detection.
+    ///
+    /// Memories are hashed in id order with [`Memory::content_hash`]'s field
+    /// coverage, followed by the agent profile and state, so two stores with
+    /// the same contents hash equally regardless of insertion order. Search
+    /// configuration such as the metric is not included. Like
+    /// [`Memory::content_hash`], the value should not be persisted across
+    /// builds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    /// let before = store.snapshot_hash();
+    ///
+    /// store.get_memory_mut(&id).unwrap().emotion = 0.5;
+    /// assert_ne!(store.snapshot_hash(), before);
+    /// ```
+    pub fn snapshot_hash(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        let mut ids: Vec<&Uuid> = self.memories.keys().collect();
+        ids.sort();
+        ids.len().hash(&mut hasher);
+        for id in ids {
+            self.memories[id].hash_content(&mut hasher);
+        }
+
+        let profile = &self.agent_profile;
+        profile.k.to_bits().hash(&mut hasher);
+        profile.a_mid.to_bits().hash(&mut hasher);
+        for value in [
+            profile.epsilon,
+            profile.theta_shock,
+            profile.gamma,
+            profile.eta,
+            profile.shock_blend_width,
+            profile.c_base,
+            profile.rho,
+            profile.kappa,
+            profile.beta_cortisol,
+            profile.beta_sleep,
+            profile.beta_fatigue,
+        ] {
+            value.to_bits().hash(&mut hasher);
+        }
+        profile.use_capacity_in_retention.hash(&mut hasher);
+        profile.version.hash(&mut hasher);
+
+        let state = &self.agent_state;
+        state.current_age.to_bits().hash(&mut hasher);
+        for value in [state.sleep_debt, state.cortisol_level, state.fatigue, state.training_factor] {
+            value.to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Finds memories matching a query vector, ordered by relevance.
+    ///
+    /// Each memory is scored by the store's [`SimilarityMetric`], adjusted by
+    /// its [`SimilarityTransform`], multiplied by its current retention.
+    ///
+    /// When the `faiss` feature is enabled, candidates are selected by the
+    /// FAISS index, which always uses L2 distance, and then re-scored with the
+    /// configured metric. With a metric other than
+    /// [`SimilarityMetric::NegativeL2`] the L2 ranking can disagree with the
+    /// metric's, so [`INDEX_OVERSAMPLE`] times `limit` candidates are
+    /// re-scored. This makes it unlikely, though not impossible, that a
+    /// memory the metric ranks in the top `limit` is missed; use
+    /// [`set_use_index`](MemoryStore::set_use_index) for an exact scan.
+    /// Without `faiss`, the `hnsw` feature selects
+    /// candidates the same way from a pure-Rust [`HnswIndex`], whose search
+    /// is approximate even under L2. An index set with
+    /// [`set_vector_index`](MemoryStore::set_vector_index) is used the same
+    /// way.
+    ///
+    /// [`HnswIndex`]: crate::hnsw_index::HnswIndex
+    ///
+    /// When the `pq` feature is enabled and `train_pq` has been called, the
+    /// brute-force scan approximates similarity from product-quantization
+    /// codes instead of the full vectors.
+    ///
+    /// Results are ordered by descending score. Equal scores are ordered by
+    /// most recent `timestamp` and then by ID, so the order is reproducible.
+    /// A `limit` of `0` returns no results and records no retrieval, and an
+    /// empty store returns no results rather than an error.
+    ///
+    /// If no memory scores above zero, the [`NoMatchPolicy`] decides whether
+    /// the top `limit` memories or no results are returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error the search index reports.
+    pub fn find_relevant(
+        &mut self,
+        query_vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<RetrievalResult>> {
+        self.find_relevant_filtered(query_vector, limit, |_| true)
+    }
+
+    /// Finds memories matching a query vector, returning bare
+    /// `(score, memory)` pairs.
+    #[deprecated(note = "use `find_relevant`, which returns `RetrievalResult`s")]
+    pub fn find_relevant_tuples(
+        &mut self,
+        query_vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<(f32, Memory)>> {
+        Ok(self
+            .find_relevant(query_vector, limit)?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Like [`find_relevant`](MemoryStore::find_relevant), but only considers
+    /// memories for which `predicate` returns `true`.
+    ///
+    /// The predicate is evaluated before scoring, so filtered-out memories are
+    /// never returned or strengthened, and up to `limit` matching memories are
+    /// returned even if better-scoring non-matching memories exist. When a
+    /// search index is set, it is searched for more candidates until `limit`
+    /// of them match, which costs extra searches when few memories match.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let mut chat = Memory::new(vec![0.9, 0.1], 0.0, 25.0, 1.0);
+    /// chat.metadata = serde_json::json!({ "source": "conversation" });
+    /// let chat_id = store.add_memory(chat);
+    /// store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+    ///
+    /// let results = store
+    ///     .find_relevant_filtered(&[1.0, 0.0], 1, |m| m.metadata["source"] == "conversation")
+    ///     .unwrap();
+    /// assert_eq!(results[0].memory.id, chat_id);
+    /// ```
+    pub fn find_relevant_filtered(
+        &mut self,
+        query_vector: &[f32],
+        limit: usize,
+        predicate: impl Fn(&Memory) -> bool,
+    ) -> Result<Vec<RetrievalResult>> {
+        Ok(self
+            .retrieve(query_vector, limit, &predicate, None)?
+            .into_iter()
+            .filter_map(|(id, scored)| self.retrieval_result(&id, scored))
+            .collect())
+    }
+
+    /// Like [`find_relevant`](MemoryStore::find_relevant), but returns only
+    /// the scores and IDs of the matching memories.
+    ///
+    /// Retrieval is recorded exactly as with `find_relevant`, but no memory is
+    /// cloned, which avoids copying every returned vector in hot retrieval
+    /// loops. Use [`get_memory`](MemoryStore::get_memory) to look up the
+    /// memories that are actually needed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let id = store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+    ///
+    /// let results = store.find_relevant_ids(&[1.0, 0.0], 1).unwrap();
+    /// assert_eq!(results[0].1, id);
+    /// assert_eq!(store.get_memory(&id).unwrap().retrieval_count, 1);
+    /// ```
+    pub fn find_relevant_ids(&mut self, query_vector: &[f32], limit: usize) -> Result<Vec<(f32, Uuid)>> {
+        Ok(self
+            .retrieve(query_vector, limit, &|_| true, None)?
+            .into_iter()
+            .map(|(id, scored)| (scored.score, id))
+            .collect())
+    }
+
+    /// Like [`find_relevant`](MemoryStore::find_relevant), but discards every
+    /// memory whose raw similarity to the query is below `min_similarity`
+    /// before ranking.
+    ///
+    /// Similarity is measured with the store's [`SimilarityMetric`], before
+    /// the [`SimilarityTransform`] is applied. Fewer than `limit` results, or
+    /// none, are returned when few memories are related to the query, and
+    /// only the returned memories have a retrieval recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `min_similarity` is NaN.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let related = store.add_memory(Memory::new(vec![0.9, 0.1], 0.0, 25.0, 1.0));
+    /// store.add_memory(Memory::new(vec![0.1, 0.9], 0.0, 25.0, 1.0));
+    ///
+    /// let results = store.find_relevant_above(&[1.0, 0.0], 5, 0.5).unwrap();
+    /// assert_eq!(results.len(), 1);
+    /// assert_eq!(results[0].memory.id, related);
+    /// ```
+    pub fn find_relevant_above(
+        &mut self,
+        query_vector: &[f32],
+        limit: usize,
+        min_similarity: f32,
+    ) -> Result<Vec<RetrievalResult>> {
+        if min_similarity.is_nan() {
+            return Err(MemoryError::invalid_param("min_similarity", min_similarity));
+        }
+        Ok(self
+            .retrieve(query_vector, limit, &|_| true, Some(min_similarity))?
+            .into_iter()
+            .filter_map(|(id, scored)| self.retrieval_result(&id, scored))
+            .collect())
+    }
+
+    /// Like [`find_relevant`](MemoryStore::find_relevant), but replaces each
+    /// result's `score` with its share of a distribution over the returned
+    /// results, so the scores sum to one.
+    ///
+    /// Normalization is monotonic, so results keep their ranking order. The
+    /// `similarity` and `retention` fields are left unnormalized.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if a softmax temperature is
+    /// not finite and positive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+    /// store.add_memory(Memory::new(vec![0.6, 0.8], 0.0, 25.0, 1.0));
+    ///
+    /// let results = store
+    ///     .find_relevant_normalized(&[1.0, 0.0], 2, ScoreNormalization::Softmax(0.5))
+    ///     .unwrap();
+    /// let total: f32 = results.iter().map(|r| r.score).sum();
+    /// assert!((total - 1.0).abs() < 1e-5);
+    /// ```
+    pub fn find_relevant_normalized(
+        &mut self,
+        query_vector: &[f32],
+        limit: usize,
+        normalization: ScoreNormalization,
+    ) -> Result<Vec<RetrievalResult>> {
+        if let ScoreNormalization::Softmax(temperature) = normalization {
+            if !temperature.is_finite() || temperature <= 0.0 {
+                return Err(MemoryError::invalid_param("temperature", temperature));
+            }
+        }
+        let mut results = self.find_relevant(query_vector, limit)?;
+        let weights: Vec<f32> = match normalization {
+            ScoreNormalization::Sum => results.iter().map(|r| r.score.max(0.0)).collect(),
+            ScoreNormalization::Softmax(temperature) => {
+                let max = results.iter().map(|r| r.score).fold(f32::NEG_INFINITY, f32::max);
+                results
+                    .iter()
+                    .map(|r| ((r.score - max) / temperature).exp())
+                    .collect()
+            }
+        };
+        let total: f32 = weights.iter().sum();
+        let uniform = 1.0 / results.len().max(1) as f32;
+        for (result, weight) in results.iter_mut().zip(weights) {
+            result.score = if total > 0.0 { weight / total } else { uniform };
+        }
+        Ok(results)
+    }
+
+    /// Scores the memories accepted by `predicate`, drops those less similar
+    /// than `min_similarity`, records a retrieval for the top `limit` and
+    /// notifies observers.
+    // Every `find_relevant` variant scores through here, so the span is named
+    // after the public entry point.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "find_relevant",
+            level = "debug",
+            skip_all,
+            fields(store_size = self.memories.len(), limit = limit)
+        )
+    )]
+    fn retrieve(
+        &mut self,
+        query_vector: &[f32],
+        limit: usize,
+        predicate: &dyn Fn(&Memory) -> bool,
+        min_similarity: Option<f32>,
+    ) -> Result<Vec<(Uuid, Scored)>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let started = Instant::now();
+        self.rehydrate_vectors();
+        let now = Utc::now();
+
+        let mut scored: Vec<_> = if let Some(index) = self.vector_index.as_deref().filter(|_| self.use_index) {
+            // The index ranks by L2 distance, so other metrics re-score a
+            // wider candidate set. Filters run on the candidates, so keep
+            // widening the search until enough pass or the index runs out.
+            let wanted = match self.metric {
+                SimilarityMetric::NegativeL2 => limit,
+                _ => limit.saturating_mul(INDEX_OVERSAMPLE),
+            };
+            let mut k = wanted.min(self.memories.len()).max(1);
+            loop {
+                let hits = index.search(query_vector, k)?;
+                let exhausted = hits.len() < k || k >= self.memories.len();
+                let scored: Vec<_> = hits
+                    .into_iter()
+                    .filter_map(|(_dist, id)| {
+                        self.memories.get(&id).filter(|mem| predicate(mem)).map(|mem| {
+                            let similarity = self.metric.similarity(query_vector, &mem.semantic_vector);
+                            let retention =
+                                self.retention_model.retention(mem, now, &self.agent_state, &self.agent_profile);
+                            (id, self.score_memory(self.metric, similarity, retention, mem, now))
+                        })
+                    })
+                    .filter(|(_, s)| min_similarity.is_none_or(|min| s.similarity >= min))
+                    .collect();
+                if scored.len() >= wanted || exhausted {
+                    break scored;
+                }
+                k = k.saturating_mul(2).min(self.memories.len());
+            }
+        } else {
+            self.score_all(query_vector, now, &self.agent_state, &self.agent_profile, predicate)
+        };
+
+        if let Some(min_similarity) = min_similarity {
+            scored.retain(|(_, s)| s.similarity >= min_similarity);
+        }
+        Ok(self.finish_retrieval(scored, limit, started))
+    }
+
+    /// Ranks scored memories, records a retrieval for the top `limit` and
+    /// notifies observers.
+    fn finish_retrieval(
+        &mut self,
+        mut scored: Vec<(Uuid, Scored)>,
+        limit: usize,
+        started: Instant,
+    ) -> Vec<(Uuid, Scored)> {
+        sort_ranked(&mut scored);
+        if self.no_match_policy == NoMatchPolicy::ReturnEmpty
+            && scored.first().and_then(|(_, s)| s.score.partial_cmp(&0.0)) != Some(std::cmp::Ordering::Greater)
+        {
+            scored.clear();
+        }
+
+        // Take top N and update their retrieval history
+        let top_n = scored.into_iter().take(limit).collect::<Vec<_>>();
+        
+        // Update retrieval history for top memories
+        for (id, _) in &top_n {
+            if let Some(mem) = self.memories.get_mut(id) {
+                mem.record_retrieval(self.agent_profile.rho);
+            }
+        }
+
+        if !self.observers.is_empty() {
+            let elapsed = started.elapsed();
+            for observer in &self.observers {
+                for (id, scored) in &top_n {
+                    observer.on_retrieve(id, scored.score);
+                }
+                observer.on_query(top_n.len(), elapsed);
+            }
+        }
+
+        top_n
+    }
+
+    /// Finds relevant memories, weighting each embedding dimension by
+    /// `dim_weights` when comparing vectors.
+    ///
+    /// Similarity is the
+    /// [`weighted_cosine_similarity`](similarity::weighted_cosine_similarity)
+    /// of the query and each memory, whatever the store's
+    /// [`SimilarityMetric`]; it is then transformed and combined with
+    /// retention as in [`find_relevant`](MemoryStore::find_relevant). Raising
+    /// a dimension's weight ranks memories strong in that dimension higher,
+    /// and a weight of 0.0 ignores the dimension. Every memory is scored, so
+    /// the search index is not used.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `dim_weights` is not as
+    /// long as `query_vector` and the store's
+    /// [`embedding_dim`](MemoryStore::embedding_dim), or if any weight is
+    /// negative or not finite.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![1.0, 0.6], 0.0, 25.0, 1.0));
+    /// let topical = store.add_memory(Memory::new(vec![0.6, 1.0], 0.0, 25.0, 1.0));
+    ///
+    /// let results = store.find_relevant_weighted(&[1.0, 1.0], &[1.0, 5.0], 1).unwrap();
+    /// assert_eq!(results[0].memory.id, topical);
+    /// ```
+    pub fn find_relevant_weighted(
+        &mut self,
+        query_vector: &[f32],
+        dim_weights: &[f32],
+        limit: usize,
+    ) -> Result<Vec<RetrievalResult>> {
+        let dim = self.embedding_dim().unwrap_or(query_vector.len());
+        if dim_weights.len() != query_vector.len() || dim_weights.len() != dim {
+            return Err(MemoryError::invalid_param("dim_weights.len()", dim_weights.len()));
+        }
+        if let Some(&weight) = dim_weights.iter().find(|w| !(w.is_finite() && **w >= 0.0)) {
+            return Err(MemoryError::invalid_param("dim_weights", weight));
+        }
+        let started = Instant::now();
+        self.rehydrate_vectors();
+        let now = Utc::now();
+        let scored = self
+            .memories
+            .iter()
+            .map(|(id, mem)| {
+                let similarity =
+                    similarity::weighted_cosine_similarity(query_vector, &mem.semantic_vector, dim_weights);
+                let retention = self.retention_model.retention(mem, now, &self.agent_state, &self.agent_profile);
+                (*id, self.score_memory(SimilarityMetric::Cosine, similarity, retention, mem, now))
+            })
+            .collect();
+
+        Ok(self
+            .finish_retrieval(scored, limit, started)
+            .into_iter()
+            .filter_map(|(id, scored)| self.retrieval_result(&id, scored))
+            .collect())
+    }
+
+    /// Finds relevant memories, letting activation spread from the best
+    /// matches to memories similar to them.
+    ///
+    /// Memories are first scored as by
+    /// [`find_relevant`](MemoryStore::find_relevant). Each hop then takes
+    /// the current top `limit` memories as sources and raises every other
+    /// memory's score to its query score plus
+    /// `spread_factor * max(cosine(memory, source) * source_score)` over the
+    /// sources, ignoring negative similarities. This lets one memory remind
+    /// the agent of another that does not match the query itself.
+    /// `spread_factor` 0.0 or `hops` 0 gives plain `find_relevant` ranking.
+    ///
+    /// `hops` is capped at [`MAX_ASSOCIATIVE_HOPS`]. Each hop compares every
+    /// memory with every source, so the cost is
+    /// `O(hops * n * limit * dim)`; spreading over the full similarity graph
+    /// (`limit` close to `n`) is `O(n²)` per hop. The search index is not
+    /// used. The returned `score` includes the spread activation, while
+    /// `similarity` and `retention` are those of the initial scoring.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `spread_factor` is
+    /// negative or not finite.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![0.7, 0.7, 0.0], 0.0, 25.0, 1.0));
+    /// // Unrelated to the query, but close to the first memory.
+    /// let reminded = store.add_memory(Memory::new(vec![0.0, 1.0, 0.0], 0.0, 25.0, 1.0));
+    /// store.add_memory(Memory::new(vec![0.2, 0.0, 1.0], 0.0, 25.0, 1.0));
+    ///
+    /// let results = store.find_relevant_associative(&[1.0, 0.0, 0.0], 2, 1.0, 1).unwrap();
+    /// assert_eq!(results[1].memory.id, reminded);
+    /// ```
+    pub fn find_relevant_associative(
+        &mut self,
+        query_vector: &[f32],
+        limit: usize,
+        spread_factor: f32,
+        hops: usize,
+    ) -> Result<Vec<RetrievalResult>> {
+        if !(spread_factor.is_finite() && spread_factor >= 0.0) {
+            return Err(MemoryError::invalid_param("spread_factor", spread_factor));
+        }
+        let started = Instant::now();
+        self.rehydrate_vectors();
+        let now = Utc::now();
+        let mut scored = self.score_all(query_vector, now, &self.agent_state, &self.agent_profile, &|_| true);
+        let base: Vec<f32> = scored.iter().map(|(_, s)| s.score).collect();
+
+        for _ in 0..hops.min(MAX_ASSOCIATIVE_HOPS) {
+            let mut order: Vec<usize> = (0..scored.len()).collect();
+            order.sort_by(|&a, &b| scored[b].1.score.total_cmp(&scored[a].1.score));
+            order.truncate(limit);
+            let sources: Vec<(usize, &[f32], f32)> = order
+                .into_iter()
+                .map(|i| (i, self.memories[&scored[i].0].semantic_vector.as_slice(), scored[i].1.score))
+                .collect();
+
+            let activation: Vec<f32> = scored
+                .iter()
+                .enumerate()
+                .map(|(i, (id, _))| {
+                    let vector = &self.memories[id].semantic_vector;
+                    let spread = sources
+                        .iter()
+                        .filter(|(source, ..)| *source != i)
+                        .map(|(_, source, score)| simd::cosine_similarity(vector, source).max(0.0) * score)
+                        .fold(0.0, f32::max);
+                    base[i] + spread_factor * spread
+                })
+                .collect();
+            for ((_, s), score) in scored.iter_mut().zip(activation) {
+                s.score = score;
+            }
+        }
+
+        Ok(self
+            .finish_retrieval(scored, limit, started)
+            .into_iter()
+            .filter_map(|(id, scored)| self.retrieval_result(&id, scored))
+            .collect())
+    }
+
+    /// Like [`find_relevant`](MemoryStore::find_relevant), but only considers
+    /// memories formed at or after `since`.
+    ///
+    /// The boundary is inclusive: a memory whose `timestamp` equals `since`
+    /// is considered. See
+    /// [`find_relevant_filtered`](MemoryStore::find_relevant_filtered) for
+    /// how the filter interacts with `limit`.
+    pub fn find_relevant_within(
+        &mut self,
+        query_vector: &[f32],
+        limit: usize,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<RetrievalResult>> {
+        self.find_relevant_filtered(query_vector, limit, |mem| mem.timestamp >= since)
+    }
+
+    /// Finds memories matching a query vector as if the agent were in
+    /// `agent_state` with `profile`, without modifying the store.
+    ///
+    /// Retention is computed from the given state and profile instead of the
+    /// store's own, which allows "what if" queries such as recall under less
+    /// stress. Unlike [`find_relevant`](MemoryStore::find_relevant), no
+    /// retrieval is recorded, observers are not notified, archived memories
+    /// are not rehydrated, and the search index is not used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+    ///
+    /// let calm = AgentState::default();
+    /// let results = store
+    ///     .find_relevant_with_state(&[1.0, 0.0], 1, &calm, store.agent_profile())
+    ///     .unwrap();
+    /// assert_eq!(results.len(), 1);
+    /// ```
+    pub fn find_relevant_with_state(
+        &self,
+        query_vector: &[f32],
+        limit: usize,
+        agent_state: &AgentState,
+        profile: &AgentProfile,
+    ) -> Result<Vec<RetrievalResult>> {
+        let mut scored = self.score_all(query_vector, Utc::now(), agent_state, profile, &|_| true);
+        sort_ranked(&mut scored);
+
+        Ok(scored
+            .into_iter()
+            .take(limit)
+            .filter_map(|(id, scored)| self.retrieval_result(&id, scored))
+            .collect())
+    }
+
+    /// Pairs a score with a copy of the memory it belongs to.
+    fn retrieval_result(&self, id: &Uuid, scored: Scored) -> Option<RetrievalResult> {
+        self.memories.get(id).map(|mem| RetrievalResult {
+            score: scored.score,
+            similarity: scored.similarity,
+            retention: scored.retention,
+            last_retrieved: mem.last_retrieved,
+            retrieval_count: mem.retrieval_count,
+            memory: mem.clone(),
+        })
+    }
+
+    /// Scores every memory accepted by `predicate` against the query by
+    /// similarity multiplied by retention under the given agent state and
+    /// profile.
+    fn score_all(
+        &self,
+        query_vector: &[f32],
+        now: DateTime<Utc>,
+        agent_state: &AgentState,
+        profile: &AgentProfile,
+        predicate: &dyn Fn(&Memory) -> bool,
+    ) -> Vec<(Uuid, Scored)> {
+        #[cfg(feature = "pq")]
+        let pq_table = self
+            .pq_index
+            .as_ref()
+            .and_then(|pq| pq.quantizer().query_table(query_vector).map(|table| (pq, table)));
+
+        let query_norm = simd::norm(query_vector);
+        let exact_similarity = |id: &Uuid, mem: &Memory| match self.norm_cache.get(id) {
+            Some(&norm) => self
+                .metric
+                .similarity_with_norms(query_vector, query_norm, &mem.semantic_vector, norm),
+            None => self.metric.similarity(query_vector, &mem.semantic_vector),
+        };
+
+        let score = |(id, mem): (&Uuid, &Memory)| {
+            #[cfg(feature = "pq")]
+            let similarity = pq_table
+                .as_ref()
+                .and_then(|(pq, table)| pq.codes(id).map(|codes| table.similarity(codes, self.metric)))
+                .unwrap_or_else(|| exact_similarity(id, mem));
+            #[cfg(not(feature = "pq"))]
+            let similarity = exact_similarity(id, mem);
+            let retention = self.retention_model.retention(mem, now, agent_state, profile);
+            (*id, self.score_memory(self.metric, similarity, retention, mem, now))
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            // The predicate need not be `Sync`, so filtering stays serial.
+            let candidates: Vec<_> = self.memories.iter().filter(|(_, mem)| predicate(mem)).collect();
+            let run = || candidates.into_par_iter().map(score).collect();
+            match &self.thread_pool {
+                Some(pool) => pool.install(run),
+                None => run(),
+            }
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        self.memories
+            .iter()
+            .filter(|(_, mem)| predicate(mem))
+            .map(score)
+            .collect()
+    }
+
+    /// Combines a raw similarity and a retention value into a ranking score
+    /// under the [`ScoringPolicy`], applying the recency boost if one is
+    /// configured.
+    fn score_memory(
+        &self,
+        metric: SimilarityMetric,
+        similarity: f32,
+        retention: f32,
+        mem: &Memory,
+        now: DateTime<Utc>,
+    ) -> Scored {
+        let mut score = self
+            .scoring_policy
+            .combine(self.similarity_transform.apply(metric.scoring_similarity(similarity)), retention);
+        if let Some(half_life) = self.recency_half_life {
+            let age = (now - mem.timestamp).num_milliseconds().max(0) as f64;
+            let half_lives = age / half_life.num_milliseconds() as f64;
+            score *= 0.5f64.powf(half_lives) as f32;
+        }
+        if let Some(exponent) = self.capacity_exponent {
+            score *= mem.capacity_weight.powf(exponent);
+        }
+        Scored { score, similarity, retention, formed: mem.timestamp }
+    }
+
+    /// Computes the current retention of every memory without retrieving it.
+    ///
+    /// Unlike [`find_relevant`](MemoryStore::find_relevant), this leaves
+    /// `retrieval_count`, `memory_strength` and `recall_history` untouched,
+    /// which makes it suitable for debug views and decay plots. The order of
+    /// the returned pairs is unspecified; see
+    /// [`retention_ranked`](MemoryStore::retention_ranked) for a sorted view.
+    pub fn retention_snapshot(&self, now: DateTime<Utc>) -> Vec<(Uuid, f32)> {
+        self.memories
+            .iter()
+            .map(|(id, mem)| {
+                (*id, self.retention_model.retention(mem, now, &self.agent_state, &self.agent_profile))
+            })
+            .collect()
+    }
+
+    /// Like [`retention_snapshot`](MemoryStore::retention_snapshot), but
+    /// sorted by retention in descending order.
+    pub fn retention_ranked(&self, now: DateTime<Utc>) -> Vec<(Uuid, f32)> {
+        let mut snapshot = self.retention_snapshot(now);
+        snapshot.sort_by(|a, b| b.1.total_cmp(&a.1));
+        snapshot
+    }
+
+    /// Returns one page of memories sorted by current retention, strongest
+    /// first, together with the total number of memories.
+    ///
+    /// Ties are broken by ID, so pages are stable across calls as long as
+    /// the store and `now` stay the same: concatenating consecutive pages
+    /// yields every memory exactly once. Each call computes and sorts the
+    /// retention of all memories.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    /// use memory_module::chrono::Utc;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// for i in 0..5 {
+    ///     store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 0.2 * i as f32));
+    /// }
+    ///
+    /// let (page, total) = store.top_by_retention_paged(4, 2, Utc::now());
+    /// assert_eq!((page.len(), total), (1, 5));
+    /// ```
+    pub fn top_by_retention_paged(&self, offset: usize, limit: usize, now: DateTime<Utc>) -> (Vec<(f32, Uuid)>, usize) {
+        let mut ranked: Vec<(f32, Uuid)> = self
+            .retention_snapshot(now)
+            .into_iter()
+            .map(|(id, retention)| (retention, id))
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        let total = ranked.len();
+        let page = ranked.into_iter().skip(offset).take(limit).collect();
+        (page, total)
+    }
+
+    /// Calculates `memory`'s retention including interference from similar
+    /// memories in this store.
+    ///
+    /// Every other memory whose cosine similarity to `memory` is at least
+    /// [`INTERFERENCE_SIMILARITY_THRESHOLD`] counts as interfering; see
+    /// [`Memory::calculate_retention_with_interference`] for how the count
+    /// enters the formula; the store's [`RetentionModel`] gives the retention
+    /// the interference scales. `memory` need not be in the store; if it is,
+    /// it does not interfere with itself. Counting neighbours compares against
+    /// every memory, so this is `O(n·d)` per call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    /// use memory_module::chrono::Duration;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let memory = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+    /// let later = memory.timestamp + Duration::days(7);
+    /// let alone = store.calculate_retention_with_interference(&memory, later);
+    ///
+    /// store.add_memory(Memory::new(vec![1.0, 0.01], 0.0, 25.0, 1.0));
+    /// assert!(store.calculate_retention_with_interference(&memory, later) < alone);
+    /// ```
+    pub fn calculate_retention_with_interference(&self, memory: &Memory, now: DateTime<Utc>) -> f32 {
+        let similar = self
+            .memories
+            .values()
+            .filter(|other| other.id != memory.id)
+            .filter(|other| {
+                simd::cosine_similarity(&memory.semantic_vector, &other.semantic_vector)
+                    >= INTERFERENCE_SIMILARITY_THRESHOLD
+            })
+            .count();
+        self.retention_model.retention(memory, now, &self.agent_state, &self.agent_profile)
+            * memory.interference_factor(now, &self.agent_profile, similar)
+    }
+
+    /// Counts the memories whose retention will be below `threshold` at
+    /// `at`, i.e. those [`maintain`](MemoryStore::maintain) would prune then.
+    ///
+    /// Retention is evaluated under the current agent state and profile, as
+    /// if they stay the same until `at`. Nothing is modified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    /// use memory_module::chrono::{Duration, Utc};
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    ///
+    /// let now = Utc::now();
+    /// assert!(store.count_forgotten_at(0.1, now) <= store.count_forgotten_at(0.1, now + Duration::days(3650)));
+    /// ```
+    pub fn count_forgotten_at(&self, threshold: f32, at: DateTime<Utc>) -> usize {
+        self.forgotten_at(threshold, at).count()
+    }
+
+    /// Like [`count_forgotten_at`](MemoryStore::count_forgotten_at), but
+    /// returns the IDs of the memories, in unspecified order.
+    pub fn forgotten_ids_at(&self, threshold: f32, at: DateTime<Utc>) -> Vec<Uuid> {
+        self.forgotten_at(threshold, at).collect()
+    }
+
+    fn forgotten_at(&self, threshold: f32, at: DateTime<Utc>) -> impl Iterator<Item = Uuid> + '_ {
+        self.memories
+            .values()
+            .filter(move |mem| self.retention_model.retention(mem, at, &self.agent_state, &self.agent_profile) < threshold)
+            .map(|mem| mem.id)
+    }
+
+    /// Finds relevant memories for multiple query vectors in a single call.
+    ///
+    /// This is a convenience wrapper that iterates over each query vector and
+    /// returns a vector of results per query.
+    pub fn find_relevant_batch(
+        &mut self,
+        query_vectors: &[Vec<f32>],
+        limit: usize,
+    ) -> Result<Vec<Vec<RetrievalResult>>> {
+        query_vectors
+            .iter()
+            .map(|q| self.find_relevant(q, limit))
+            .collect()
+    }
+
+    /// Merges near-duplicate memories, modelling memory consolidation.
+    ///
+    /// Memories are visited from oldest to newest. Each one absorbs every
+    /// later memory whose vector has a cosine similarity above
+    /// `similarity_threshold` with its own (regardless of the store's
+    /// configured metric). A merged memory keeps the earliest `timestamp` and
+    /// the id of the oldest memory. Its other fields are combined as follows:
+    ///
+    /// - `semantic_vector`: average weighted by `memory_strength`
+    /// - `retrieval_count`: sum
+    /// - `emotion`: the value with the largest magnitude
+    /// - `memory_strength`: the larger of the two
+    /// - `recall_history` and `last_retrieved`: union and latest, respectively
+    ///
+    /// Absorbed memories are removed from the store. Returns the number of
+    /// merges performed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let a = store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+    /// let b = store.add_memory(Memory::new(vec![1.0, 0.01], 0.0, 25.0, 1.0));
+    /// assert_eq!(store.consolidate(0.95), 1);
+    /// assert!(store.get_memory(&a).is_some());
+    /// assert!(store.get_memory(&b).is_none());
+    /// ```
+    pub fn consolidate(&mut self, similarity_threshold: f32) -> usize {
+        let mut order: Vec<(DateTime<Utc>, Uuid)> =
+            self.memories.values().map(|m| (m.timestamp, m.id)).collect();
+        order.sort();
+
+        let mut absorbed = Vec::new();
+        let mut merged_away = HashSet::new();
+        for (i, (_, keep_id)) in order.iter().enumerate() {
+            if merged_away.contains(keep_id) {
+                continue;
+            }
+            let Some(mut keep) = self.memories.remove(keep_id) else {
+                continue;
+            };
+            let mut changed = false;
+            for (_, other_id) in &order[i + 1..] {
+                if merged_away.contains(other_id) {
+                    continue;
+                }
+                let Some(other) = self.memories.get(other_id) else {
+                    continue;
+                };
+                if keep.semantic_vector.is_empty()
+                    || keep.semantic_vector.len() != other.semantic_vector.len()
+                {
+                    continue;
+                }
+                let similarity = simd::cosine_similarity(&keep.semantic_vector, &other.semantic_vector);
+                if similarity > similarity_threshold {
+                    merge_memory(&mut keep, other);
+                    merged_away.insert(*other_id);
+                    absorbed.push(*other_id);
+                    changed = true;
+                }
+            }
+            if changed {
+                #[cfg(feature = "pq")]
+                if let Some(pq) = &mut self.pq_index {
+                    pq.insert(*keep_id, &keep.semantic_vector);
+                }
+                if let Some(index) = &mut self.vector_index {
+                    let _ = index.remove_vector(*keep_id);
+                    let _ = index.add_vector(*keep_id, &keep.semantic_vector);
+                }
+                self.norm_cache.insert(*keep_id, simd::norm(&keep.semantic_vector));
+            }
+            self.memories.insert(*keep_id, keep);
+        }
+
+        for id in &absorbed {
+            let _ = self.remove_memory(id);
+        }
+        absorbed.len()
+    }
+
+    /// Performs maintenance operations like pruning old memories.
+    ///
+    /// Returns the number of memories that were pruned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `retention_threshold` is not within `0.0..=1.0`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(store_size = self.memories.len(), pruned = tracing::field::Empty)
+        )
+    )]
+    pub fn maintain(&mut self, retention_threshold: f32) -> usize {
+        let pruned = self.maintain_collect(retention_threshold).len();
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("pruned", pruned);
+        pruned
+    }
+
+    /// Like [`maintain`](MemoryStore::maintain), but returns the pruned
+    /// memories, e.g. to archive or log them.
+    ///
+    /// The memories are returned in unspecified order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `retention_threshold` is not within `0.0..=1.0`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let mut faded = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// faded.memory_strength = 0.0;
+    /// let faded_id = store.add_memory(faded);
+    /// store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0));
+    ///
+    /// let pruned = store.maintain_collect(1e-6);
+    /// assert_eq!(pruned.len(), 1);
+    /// assert_eq!(pruned[0].id, faded_id);
+    /// assert!(store.get_memory(&faded_id).is_none());
+    /// assert_eq!(store.maintain(1e-6), 0);
+    /// ```
+    pub fn maintain_collect(&mut self, retention_threshold: f32) -> Vec<Memory> {
+        self.prune_where(retention_threshold, Utc::now(), |_| true)
+    }
+
+    /// Like [`maintain`](MemoryStore::maintain), but only considers pruning
+    /// memories formed at least `age` ago.
+    ///
+    /// The boundary is inclusive: a memory whose age is exactly `age` is
+    /// eligible for pruning. Younger memories are kept regardless of their
+    /// retention. Returns the number of memories that were pruned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `retention_threshold` is not within `0.0..=1.0`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    /// use chrono::{Duration, Utc};
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let mut old = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// old.timestamp = Utc::now() - Duration::days(400);
+    /// store.add_memory(old);
+    /// store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0));
+    ///
+    /// assert_eq!(store.maintain_older_than(Duration::days(365), 1.0), 1);
+    /// ```
+    pub fn maintain_older_than(&mut self, age: chrono::Duration, retention_threshold: f32) -> usize {
+        let now = Utc::now();
+        // No memory can be older than an age that reaches past the earliest
+        // representable time.
+        let cutoff = now.checked_sub_signed(age);
+        self.prune_where(retention_threshold, now, |mem| cutoff.is_some_and(|cutoff| mem.timestamp <= cutoff))
+            .len()
+    }
+
+    /// Prunes memories accepted by `eligible` whose retention at `now` is
+    /// below `retention_threshold`, returning them.
+    fn prune_where(
+        &mut self,
+        retention_threshold: f32,
+        now: DateTime<Utc>,
+        eligible: impl Fn(&Memory) -> bool,
+    ) -> Vec<Memory> {
+        assert!(
+            (0.0..=1.0).contains(&retention_threshold),
+            "retention_threshold must be between 0.0 and 1.0"
+        );
+
+        let expired: Vec<Uuid> = self
+            .memories
+            .iter()
+            .filter(|(_, mem)| {
+                eligible(mem)
+                    && self.retention_model.retention(mem, now, &self.agent_state, &self.agent_profile) < retention_threshold
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        self.remove_pruned(&expired)
+    }
+
+    /// Like [`maintain`](MemoryStore::maintain), but computes retention on
+    /// the rayon pool.
+    ///
+    /// Retention is computed for every memory in parallel, on the pool set
+    /// by [`set_thread_count`](MemoryStore::set_thread_count) if there is
+    /// one, and the expired memories are then removed serially. The result
+    /// is the same as `maintain`; on large stores it is faster. Returns the
+    /// number of memories that were pruned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `retention_threshold` is not within `0.0..=1.0`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let mut faded = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    /// faded.memory_strength = 0.0;
+    /// store.add_memory(faded);
+    /// store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0));
+    ///
+    /// assert_eq!(store.maintain_parallel(1e-6), 1);
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn maintain_parallel(&mut self, retention_threshold: f32) -> usize {
+        use rayon::prelude::*;
+
+        assert!(
+            (0.0..=1.0).contains(&retention_threshold),
+            "retention_threshold must be between 0.0 and 1.0"
+        );
+        let now = Utc::now();
+        let (state, profile) = (&self.agent_state, &self.agent_profile);
+        let run = || {
+            self.memories
+                .par_iter()
+                .filter(|(_, mem)| self.retention_model.retention(mem, now, state, profile) < retention_threshold)
+                .map(|(id, _)| *id)
+                .collect::<Vec<Uuid>>()
+        };
+        let expired = match &self.thread_pool {
+            Some(pool) => pool.install(run),
+            None => run(),
+        };
+        self.remove_pruned(&expired).len()
+    }
+
+    /// Removes the `expired` memories and their cached data, notifying
+    /// observers, and returns the removed memories.
+    fn remove_pruned(&mut self, expired: &[Uuid]) -> Vec<Memory> {
+        let pruned: Vec<Memory> = expired.iter().filter_map(|id| self.memories.remove(id)).collect();
+        for mem in &pruned {
+            if let Some(index) = &mut self.vector_index {
+                let _ = index.remove_vector(mem.id);
+            }
+            for observer in &self.observers {
+                observer.on_prune(&mem.id);
+            }
+        }
+
+        let memories = &self.memories;
+        self.norm_cache.retain(|id, _| memories.contains_key(id));
+        #[cfg(feature = "pq")]
+        if let Some(pq) = &mut self.pq_index {
+            pq.retain(|id| memories.contains_key(id));
+        }
+        
+        pruned
+    }
+
+    /// Performs a slice of [`maintain`](MemoryStore::maintain), examining at
+    /// most `max_items` memories.
+    ///
+    /// The store remembers where the previous call stopped, so repeated calls
+    /// walk through all memories across several frames. A pass covers the
+    /// memories present when it started; memories added during a pass are
+    /// examined in the next one. Each call prunes using its own
+    /// `retention_threshold` and the current agent state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `retention_threshold` is not within `0.0..=1.0`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// for i in 0..10 {
+    ///     store.add_memory(Memory::new(vec![i as f32, 1.0], 0.0, 25.0, 1.0));
+    /// }
+    ///
+    /// let mut frames = 0;
+    /// loop {
+    ///     frames += 1;
+    ///     if store.maintain_budgeted(0.0, 4).finished {
+    ///         break;
+    ///     }
+    /// }
+    /// assert_eq!(frames, 3);
+    /// ```
+    pub fn maintain_budgeted(&mut self, retention_threshold: f32, max_items: usize) -> MaintainProgress {
+        assert!(
+            (0.0..=1.0).contains(&retention_threshold),
+            "retention_threshold must be between 0.0 and 1.0"
+        );
+        if self.maintain_pending.is_empty() {
+            self.maintain_pending = self.memories.keys().copied().collect();
+        }
+
+        let now = Utc::now();
+        let mut progress = MaintainProgress::default();
+        while progress.processed < max_items {
+            let Some(id) = self.maintain_pending.pop() else {
+                break;
+            };
+            let Some(mem) = self.memories.get(&id) else {
+                continue;
+            };
+            progress.processed += 1;
+            let retention = self.retention_model.retention(mem, now, &self.agent_state, &self.agent_profile);
+            if retention < retention_threshold {
+                self.take_memory(&id);
+                for observer in &self.observers {
+                    observer.on_prune(&id);
+                }
+                progress.pruned += 1;
+            }
+        }
+
+        progress.remaining = self.maintain_pending.len();
+        progress.finished = self.maintain_pending.is_empty();
+        progress
+    }
+
+    /// Registers an observer that is notified of adds, retrievals, removals
+    /// and prunes.
+    pub fn add_observer(&mut self, observer: Arc<dyn StoreObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Uses `model` to compute retention; see
+    /// [`set_retention_model`](MemoryStore::set_retention_model).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let store = MemoryStore::default().with_retention_model(ExponentialRetentionModel::default());
+    /// assert_eq!(store.summary().memory_count, 0);
+    /// ```
+    pub fn with_retention_model(mut self, model: impl RetentionModel + 'static) -> Self {
+        self.set_retention_model(model);
+        self
+    }
+
+    /// Sets the [`RetentionModel`] used wherever the store computes
+    /// retention: scoring, pruning, eviction, summaries and forecasts.
+    ///
+    /// Defaults to [`DefaultRetentionModel`], i.e.
+    /// [`Memory::calculate_retention`]. Stores made by
+    /// [`clone_filtered`](MemoryStore::clone_filtered) and
+    /// [`map_into`](MemoryStore::map_into) share the model; a deserialized
+    /// store uses the default.
+    pub fn set_retention_model(&mut self, model: impl RetentionModel + 'static) {
+        self.retention_model = Arc::new(model);
+    }
+
+    /// Gets the [`RetentionModel`] used to compute retention
+    pub fn retention_model(&self) -> &dyn RetentionModel {
+        &*self.retention_model
+    }
+
+    /// Sets the [`SimilarityMetric`] used to score memories in [`find_relevant`].
+    ///
+    /// [`find_relevant`]: MemoryStore::find_relevant
+    pub fn set_metric(&mut self, metric: SimilarityMetric) {
+        self.metric = metric;
+    }
+
+    /// Gets the [`SimilarityMetric`] used to score memories
+    pub fn metric(&self) -> SimilarityMetric {
+        self.metric
+    }
+
+    /// Sets the [`SimilarityTransform`] applied to similarities before they
+    /// are combined with retention.
+    pub fn set_similarity_transform(&mut self, transform: SimilarityTransform) {
+        self.similarity_transform = transform;
+    }
+
+    /// Gets the [`SimilarityTransform`] applied to similarities
+    pub fn similarity_transform(&self) -> SimilarityTransform {
+        self.similarity_transform
+    }
+
+    /// Sets the [`ScoringPolicy`] that weights similarity against retention
+    /// when ranking memories.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if either weight is negative
+    /// or not finite.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store
+    ///     .set_scoring_policy(ScoringPolicy { similarity_weight: 1.0, retention_weight: 2.0 })
+    ///     .unwrap();
+    /// assert_eq!(store.scoring_policy().retention_weight, 2.0);
+    /// ```
+    pub fn set_scoring_policy(&mut self, policy: ScoringPolicy) -> Result<()> {
+        if !(policy.similarity_weight.is_finite() && policy.similarity_weight >= 0.0) {
+            return Err(MemoryError::invalid_param("similarity_weight", policy.similarity_weight));
+        }
+        if !(policy.retention_weight.is_finite() && policy.retention_weight >= 0.0) {
+            return Err(MemoryError::invalid_param("retention_weight", policy.retention_weight));
+        }
+        self.scoring_policy = policy;
+        Ok(())
+    }
+
+    /// Gets the [`ScoringPolicy`] used to rank memories
+    pub fn scoring_policy(&self) -> ScoringPolicy {
+        self.scoring_policy
+    }
+
+    /// Sets a recency boost applied to retrieval scores.
+    ///
+    /// With `Some(half_life)`, every score is multiplied by
+    /// `0.5^(age / half_life)`, where `age` is the time since the memory was
+    /// formed, so a memory's score halves with every `half_life` of age. This
+    /// is layered on top of retention and leaves the decay model untouched.
+    /// `None` (the default) disables the boost.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `half_life` is shorter
+    /// than a millisecond, the resolution ages are measured in.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.set_recency_half_life(Some(chrono::Duration::hours(6))).unwrap();
+    /// assert_eq!(store.recency_half_life(), Some(chrono::Duration::hours(6)));
+    /// assert!(store.set_recency_half_life(Some(chrono::Duration::zero())).is_err());
+    /// ```
+    pub fn set_recency_half_life(&mut self, half_life: Option<chrono::Duration>) -> Result<()> {
+        if let Some(half_life) = half_life {
+            if half_life.num_milliseconds() <= 0 {
+                return Err(MemoryError::invalid_param("recency_half_life", half_life));
+            }
+        }
+        self.recency_half_life = half_life;
+        Ok(())
+    }
+
+    /// Gets the recency half-life applied to retrieval scores, if any.
+    pub fn recency_half_life(&self) -> Option<chrono::Duration> {
+        self.recency_half_life
+    }
+
+    /// Sets the exponent used to weight retrieval scores by capacity weight.
+    ///
+    /// With `Some(gamma)`, every score is multiplied by
+    /// `capacity_weight^gamma`, favoring richer, more detailed memories.
+    /// `None` (the default) disables the weighting.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `exponent` is negative or
+    /// not finite.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.set_capacity_exponent(Some(2.0)).unwrap();
+    /// assert_eq!(store.capacity_exponent(), Some(2.0));
+    /// assert!(store.set_capacity_exponent(Some(-1.0)).is_err());
+    /// ```
+    pub fn set_capacity_exponent(&mut self, exponent: Option<f32>) -> Result<()> {
+        if let Some(exponent) = exponent {
+            if !exponent.is_finite() || exponent < 0.0 {
+                return Err(MemoryError::invalid_param("capacity_exponent", exponent));
+            }
+        }
+        self.capacity_exponent = exponent;
+        Ok(())
+    }
+
+    /// Gets the exponent applied to capacity weight in retrieval scores, if any.
+    pub fn capacity_exponent(&self) -> Option<f32> {
+        self.capacity_exponent
+    }
+
+    /// Runs parallel scoring on a dedicated pool of `threads` threads.
+    ///
+    /// Brute-force retrieval scores memories in parallel when the `parallel`
+    /// feature is enabled. By default this uses rayon's global pool; a
+    /// dedicated pool caps thread usage without reconfiguring the global one.
+    /// `None` switches back to the global pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `threads` is zero, or
+    /// [`MemoryError::Storage`] if the pool cannot be created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.set_thread_count(Some(2)).unwrap();
+    /// assert_eq!(store.thread_count(), Some(2));
+    /// assert!(store.set_thread_count(Some(0)).is_err());
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn set_thread_count(&mut self, threads: Option<usize>) -> Result<()> {
+        self.thread_pool = match threads {
+            Some(0) => return Err(MemoryError::invalid_param("threads", 0)),
+            Some(threads) => Some(Arc::new(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map_err(|e| MemoryError::Storage(e.to_string()))?,
+            )),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Gets the size of the dedicated scoring pool, if one is set.
+    #[cfg(feature = "parallel")]
+    pub fn thread_count(&self) -> Option<usize> {
+        self.thread_pool.as_ref().map(|pool| pool.current_num_threads())
+    }
+
+    /// Enables or disables the search index for retrieval.
+    ///
+    /// With the index disabled, queries score every memory exactly, as in a
+    /// build without the `faiss` and `hnsw` features. This is useful when an index gives
+    /// approximate results and exact ones are needed for comparison. The
+    /// index is still kept up to date while disabled. Enabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.set_use_index(false);
+    /// assert!(!store.use_index());
+    /// ```
+    pub fn set_use_index(&mut self, use_index: bool) {
+        self.use_index = use_index;
+    }
+
+    /// Gets whether retrieval may use the search index.
+    pub fn use_index(&self) -> bool {
+        self.use_index
+    }
+
+    /// Precomputes search structures so the first query does not pay for them.
+    ///
+    /// This caches the norm of every memory vector and, when the `faiss` or
+    /// `hnsw` feature is enabled, builds the search index if none is set.
+    /// Loading a store already builds the index. Retention is
+    /// time-dependent and is always computed at query time, so there is no
+    /// retention cache to prime.
+    ///
+    /// Calling `warm_up` on an already warm store is cheap and idempotent.
+    pub fn warm_up(&mut self) {
+        for (id, mem) in &self.memories {
+            self.norm_cache
+                .entry(*id)
+                .or_insert_with(|| simd::norm(&mem.semantic_vector));
+        }
+
+        if self.vector_index.is_none() {
+            let _ = self.rebuild_index();
+        }
+    }
+
+    /// Replaces the FAISS index with one built from the current memories.
+    #[cfg(feature = "faiss")]
+    #[deprecated(note = "use `rebuild_index`")]
+    pub fn rebuild_faiss_index(&mut self) -> Result<()> {
+        self.rebuild_index()
+    }
+
+    /// Rebuilds the search index from the current memories.
+    ///
+    /// An index set with [`set_vector_index`](MemoryStore::set_vector_index)
+    /// is rebuilt in place. Otherwise a new one is built: a FAISS index with
+    /// the `faiss` feature, an HNSW graph with the `hnsw` feature, and none
+    /// without either. The index's dimension is taken from the first
+    /// non-empty vector; archived memories and vectors of any other
+    /// dimension are left out and are only found by the brute-force path.
+    ///
+    /// Rebuilding copies every vector into the index, so it takes time and
+    /// memory linear in the number of memories times their dimension (times
+    /// `log n` for HNSW); for stores with millions of memories expect it to
+    /// take seconds. It runs automatically after [`load`](MemoryStore::load).
+    /// Rebuilding also clears the tombstones HNSW keeps for removed memories.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error the index reports, such as
+    /// `MemoryError::FaissError` if FAISS fails to build it, in which case
+    /// the store is left without an index.
+    pub fn rebuild_index(&mut self) -> Result<()> {
+        let dim = self
+            .memories
+            .values()
+            .map(|m| m.semantic_vector.len())
+            .find(|&len| len > 0);
+        let entries: Vec<(Uuid, &[f32])> = self
+            .memories
+            .iter()
+            .filter(|(_, mem)| Some(mem.semantic_vector.len()) == dim)
+            .map(|(id, mem)| (*id, mem.semantic_vector.as_slice()))
+            .collect();
+        let result = match (self.vector_index.as_mut(), dim) {
+            (Some(index), _) => index.rebuild(&entries),
+            (None, Some(dim)) => default_vector_index(dim).and_then(|index| {
+                if let Some(mut index) = index {
+                    index.add_vectors(&entries)?;
+                    self.vector_index = Some(index);
+                }
+                Ok(())
+            }),
+            (None, None) => Ok(()),
+        };
+        if result.is_err() {
+            self.vector_index = None;
+        }
+        result
+    }
+
+    /// Replaces the search index with `index`, built from the current
+    /// memories, and returns any error building it reports.
+    ///
+    /// Any [`VectorIndex`] implementation can be plugged in, in place of
+    /// the FAISS or HNSW index the `faiss` and `hnsw` features build. The
+    /// store keeps the index in sync as memories are added, removed,
+    /// archived and rehydrated, and uses it to select retrieval candidates
+    /// while [`use_index`](MemoryStore::use_index) is set. Passing `None`
+    /// drops the index; with the `faiss` or `hnsw` feature a default one is
+    /// built again by the next insert or [`warm_up`](MemoryStore::warm_up),
+    /// so use [`set_use_index`](MemoryStore::set_use_index) to query
+    /// exactly.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error [`VectorIndex::rebuild`] reports, in which case the
+    /// store is left without an index.
+    pub fn set_vector_index(&mut self, index: Option<Box<dyn VectorIndex>>) -> Result<()> {
+        self.vector_index = index;
+        if self.vector_index.is_some() {
+            self.rebuild_index()?;
+        }
+        Ok(())
+    }
+
+    /// Gets whether a search index is set
+    pub fn has_vector_index(&self) -> bool {
+        self.vector_index.is_some()
+    }
+
+    /// Trains product-quantization codebooks on the current memories and
+    /// switches brute-force retrieval to the compressed approximation.
+    ///
+    /// Vectors are split into `m` subspaces with `2^bits` centroids each, so
+    /// every memory is scored from `m` bytes of codes. The full vectors are
+    /// kept on each [`Memory`], so the codebooks can be retrained at any time.
+    /// Memories added later are encoded on insertion; vectors modified through
+    /// [`get_memory_mut`] keep their old codes until `train_pq` is called again.
+    ///
+    /// [`get_memory_mut`]: MemoryStore::get_memory_mut
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if the store is empty, vectors
+    /// differ in dimension, `m` does not divide the dimension, or `bits` is
+    /// outside `1..=8`.
+    #[cfg(feature = "pq")]
+    pub fn train_pq(&mut self, m: usize, bits: usize) -> Result<()> {
+        let vectors: Vec<&[f32]> = self
+            .memories
+            .values()
+            .map(|mem| mem.semantic_vector.as_slice())
+            .collect();
+        let mut index = PqIndex::new(ProductQuantizer::train(&vectors, m, bits)?);
+        for (id, mem) in &self.memories {
+            index.insert(*id, &mem.semantic_vector);
+        }
+        self.pq_index = Some(index);
+        Ok(())
+    }
+
+    /// Gets the product-quantization index, if [`train_pq`] has been called.
+    ///
+    /// [`train_pq`]: MemoryStore::train_pq
+    #[cfg(feature = "pq")]
+    pub fn pq_index(&self) -> Option<&PqIndex> {
+        self.pq_index.as_ref()
+    }
+
+    /// Updates the agent's state
+    ///
+    /// If state history is enabled, the new state is recorded with the
+    /// current time; see [`set_state_history_limit`].
+    ///
+    /// [`set_state_history_limit`]: MemoryStore::set_state_history_limit
+    pub fn update_agent_state(&mut self, state: AgentState) {
+        self.update_agent_state_at(state, Utc::now());
+    }
+
+    /// Updates the agent's state, recording it in the state history as
+    /// taking effect at `at`.
+    ///
+    /// Useful when the agent runs on a simulated clock. The history stays
+    /// ordered by time even if `at` precedes earlier updates.
+    pub fn update_agent_state_at(&mut self, state: AgentState, at: DateTime<Utc>) {
+        if self.state_history_limit > 0 {
+            let index = self.state_history.partition_point(|(time, _)| *time <= at);
+            self.state_history.insert(index, (at, state.clone()));
+            while self.state_history.len() > self.state_history_limit {
+                self.state_history.pop_front();
+            }
+        }
+        self.agent_state = state;
+    }
+
+    /// Sets how many past agent states the store keeps, dropping the oldest
+    /// entries beyond the new limit.
+    ///
+    /// The default of `0` keeps no history. The history is not persisted by
+    /// [`save`](MemoryStore::save).
+    pub fn set_state_history_limit(&mut self, limit: usize) {
+        self.state_history_limit = limit;
+        let excess = self.state_history.len().saturating_sub(limit);
+        self.state_history.drain(..excess);
+    }
+
+    /// Returns how many past agent states the store keeps.
+    pub fn state_history_limit(&self) -> usize {
+        self.state_history_limit
+    }
+
+    /// Returns the recorded agent states with the time each took effect,
+    /// oldest first.
+    pub fn state_history(&self) -> &VecDeque<(DateTime<Utc>, AgentState)> {
+        &self.state_history
+    }
+
+    /// Returns the agent state in effect at `time`: the latest recorded
+    /// state that took effect at or before it.
+    ///
+    /// Returns `None` if `time` precedes every recorded state, including
+    /// when state history is disabled. Passing the result to
+    /// [`Memory::calculate_retention`] shows how well a memory was retained
+    /// when, for example, it was last recalled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    /// use chrono::{Duration, Utc};
+    ///
+    /// let mut store = MemoryStore::default();
+    /// store.set_state_history_limit(16);
+    /// let start = Utc::now();
+    /// let tired = AgentState { fatigue: 0.9, ..AgentState::default() };
+    /// store.update_agent_state_at(tired.clone(), start);
+    /// store.update_agent_state_at(AgentState::default(), start + Duration::hours(8));
+    ///
+    /// assert_eq!(store.state_at(start + Duration::hours(1)), Some(&tired));
+    /// assert_eq!(store.state_at(start - Duration::hours(1)), None);
+    /// ```
+    pub fn state_at(&self, time: DateTime<Utc>) -> Option<&AgentState> {
+        let index = self.state_history.partition_point(|(at, _)| *at <= time);
+        index.checked_sub(1).map(|i| &self.state_history[i].1)
+    }
+
+    /// Lets the agent sleep for `hours`, paying off sleep debt and
+    /// consolidating the day's memories.
+    ///
+    /// Every [`SLEEP_HOURS_PER_NIGHT`] hours of sleep remove 1.0 of
+    /// `sleep_debt`, down to zero. Memories formed within the last 24 hours
+    /// are then consolidated in proportion to the nights slept: their
+    /// `memory_strength` is multiplied by
+    /// `1 + 0.5 * nights * (|emotion| - SLEEP_SALIENCE_THRESHOLD)`, floored
+    /// at zero. Emotionally salient memories (`|emotion|` above
+    /// [`SLEEP_SALIENCE_THRESHOLD`]) are strengthened, while weakly encoded,
+    /// near-neutral ones lose strength and fade faster. Older memories are
+    /// left unchanged. The reduced sleep debt goes through
+    /// [`update_agent_state`](MemoryStore::update_agent_state), so it is
+    /// recorded in the state history.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryError::InvalidParameter`] if `hours` is negative or
+    /// not finite.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memory_module::prelude::*;
+    ///
+    /// let mut store = MemoryStore::default();
+    /// let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.9, 25.0, 1.0));
+    /// store.sleep(8.0).unwrap();
+    /// assert!(store.get_memory(&id).unwrap().memory_strength > 1.0);
+    /// ```
+    pub fn sleep(&mut self, hours: f32) -> Result<()> {
+        if !(hours.is_finite() && hours >= 0.0) {
+            return Err(MemoryError::invalid_param("hours", hours));
+        }
+        let now = Utc::now();
+        let nights = hours / SLEEP_HOURS_PER_NIGHT;
+        let rested = AgentState {
+            sleep_debt: (self.agent_state.sleep_debt - nights).max(0.0),
+            ..self.agent_state.clone()
+        };
+        self.update_agent_state_at(rested, now);
+
+        let formed_after = now - chrono::Duration::hours(24);
+        for mem in self.memories.values_mut().filter(|m| m.timestamp >= formed_after) {
+            let salience = mem.emotion.abs() - SLEEP_SALIENCE_THRESHOLD;
+            mem.memory_strength *= (1.0 + 0.5 * nights * salience).max(0.0);
+        }
+        Ok(())
+    }
+
+    /// Gets the current agent profile
+    pub fn agent_profile(&self) -> &AgentProfile {
+        &self.agent_profile
+    }
+
+    /// Gets the current agent state
+    pub fn agent_state(&self) -> &AgentState {
+        &self.agent_state
+    }
+}
+
+/// Folds `other` into `target`; see [`MemoryStore::consolidate`].
+fn merge_memory(target: &mut Memory, other: &Memory) {
+    let (wa, wb) = match (target.memory_strength.max(0.0), other.memory_strength.max(0.0)) {
+        (a, b) if a + b > 0.0 => (a, b),
+        _ => (1.0, 1.0),
+    };
+    for (a, b) in target.semantic_vector.iter_mut().zip(&other.semantic_vector) {
+        *a = (*a * wa + b * wb) / (wa + wb);
+    }
+
+    target.retrieval_count = target.retrieval_count.saturating_add(other.retrieval_count);
+    if other.emotion.abs() > target.emotion.abs() {
+        target.emotion = other.emotion;
+    }
+    target.memory_strength = target.memory_strength.max(other.memory_strength);
+    target.timestamp = target.timestamp.min(other.timestamp);
+    target.last_retrieved = target.last_retrieved.max(other.last_retrieved);
+    target.recall_history.extend(other.recall_history.iter().copied());
+    target.recall_history.make_contiguous().sort();
+    target.trim_recall_history();
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for MemoryStore {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let data = MemoryStoreData {
+            version: DATA_FORMAT_VERSION,
+            memories: self.memories.clone(),
+            agent_profile: self.agent_profile.clone(),
+            agent_state: self.agent_state.clone(),
+            metric: self.metric,
+            similarity_transform: self.similarity_transform,
+        };
+        data.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for MemoryStore {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let data = if deserializer.is_human_readable() {
+            // Self-describing formats can be upgraded from older versions.
+            let raw = serde_json::Value::deserialize(deserializer)?;
+            let raw = crate::storage::upgrade_to_current(raw).map_err(D::Error::custom)?;
+            MemoryStoreData::deserialize(raw).map_err(D::Error::custom)?
+        } else {
+            MemoryStoreData::deserialize(deserializer)?
+        };
+        if data.version != DATA_FORMAT_VERSION {
+            return Err(D::Error::custom(format!(
+                "Incompatible data format version: expected {}, found {}",
+                DATA_FORMAT_VERSION, data.version
+            )));
+        }
+        let mut store = Self::from_memories(data.agent_profile, data.agent_state, data.memories)
+            .map_err(D::Error::custom)?;
+        store.metric = data.metric;
+        store.similarity_transform = data.similarity_transform;
+        Ok(store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    #[cfg(feature = "serde")]
+    use serde_json;
+
+    fn create_test_memory(emotion: f32, days_old: i64) -> Memory {
+        let mut memory = Memory::new(
+            vec![0.1, 0.2, 0.3], 
+            emotion, 
+            25.0, 
+            1.0
+        );
+        memory.timestamp = Utc::now() - Duration::days(days_old);
+        memory
+    }
+
+    #[test]
+    fn test_add_and_retrieve_memory() {
+        let mut store = MemoryStore::new(AgentProfile::default(), AgentState {
+            current_age: 30.0,
+            sleep_debt: 0.0,
+            cortisol_level: 0.0,
+            fatigue: 0.0,
+            training_factor: 0.0,
+        });
+
+        let memory = create_test_memory(0.5, 1);
+        let id = memory.id;
+        
+        store.add_memory(memory);
+        assert!(store.get_memory(&id).is_some());
+        
+        store.remove_memory(&id).unwrap();
+        assert!(store.get_memory(&id).is_none());
+    }
+
+    #[test]
+    fn test_add_memories() {
+        let mut store = MemoryStore::default();
+        let memories: Vec<Memory> = (0..10)
+            .map(|i| Memory::new(vec![i as f32, 1.0, 0.0], 0.0, 25.0, 1.0))
+            .collect();
+        let expected: Vec<Uuid> = memories.iter().map(|m| m.id).collect();
+
+        let ids = store.add_memories(memories);
+
+        assert_eq!(ids, expected);
+        assert!(ids.iter().all(|id| store.get_memory(id).is_some()));
+        assert_eq!(store.norm_cache.len(), 10);
+    }
+
+    #[test]
+    fn test_reconsolidate_blends_emotion() {
+        let mut store = MemoryStore::default();
+        let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.6, 25.0, 1.0));
+
+        store.reconsolidate(&id, -0.3, 0.0).unwrap();
+        assert_eq!(store.get_memory(&id).unwrap().emotion, 0.6);
+        store.reconsolidate(&id, -0.3, 1.0).unwrap();
+        assert_eq!(store.get_memory(&id).unwrap().emotion, -0.3);
+        // Out-of-range inputs are clamped.
+        store.reconsolidate(&id, -4.0, 2.0).unwrap();
+        assert_eq!(store.get_memory(&id).unwrap().emotion, -1.0);
+
+        assert!(store.reconsolidate(&id, f32::NAN, 0.5).unwrap_err().is_invalid_parameter());
+        assert!(store.reconsolidate(&Uuid::new_v4(), 0.0, 0.5).unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn test_forgotten_at_grows_over_time() {
+        let mut store = MemoryStore::default();
+        let now = Utc::now();
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let mut memory = Memory::new(vec![1.0, i as f32], 0.0, 25.0, 1.0);
+            memory.timestamp = now;
+            ids.push(store.add_memory(memory));
+        }
+        let threshold = store.retention_snapshot(now).iter().map(|(_, r)| *r).fold(f32::MAX, f32::min) * 0.5;
+        assert_eq!(store.count_forgotten_at(threshold, now), 0);
+        let far_future = now + Duration::days(365 * 50);
+        let forgotten = store.forgotten_ids_at(threshold, far_future);
+        assert_eq!(store.count_forgotten_at(threshold, far_future), forgotten.len());
+        assert_eq!(forgotten.len(), ids.len());
+        assert!(ids.iter().all(|id| forgotten.contains(id)));
+    }
+
+    #[test]
+    fn test_find_relevant_associative_spreads_to_similar_memories() {
+        let mut store = MemoryStore::default();
+        let now = Utc::now();
+        let mut add = |vector: Vec<f32>| {
+            let mut memory = Memory::new(vector, 0.0, 25.0, 1.0);
+            memory.timestamp = now;
+            store.add_memory(memory)
+        };
+        let anchor = add(vec![0.7, 0.7, 0.0]);
+        let associated = add(vec![0.0, 1.0, 0.0]);
+        let unrelated = add(vec![0.2, 0.0, 1.0]);
+        let mut twin = store.clone_filtered(|_| true);
+        let query = [1.0, 0.0, 0.0];
+
+        let ids = |results: Vec<RetrievalResult>| results.into_iter().map(|r| r.memory.id).collect::<Vec<_>>();
+        assert_eq!(ids(twin.find_relevant_associative(&query, 2, 1.0, 0).unwrap()), vec![anchor, unrelated]);
+        let results = store.find_relevant_associative(&query, 2, 1.0, 1).unwrap();
+        assert_eq!(results[1].similarity, 0.0);
+        assert!(results[1].score > 0.0);
+        assert_eq!(ids(results), vec![anchor, associated]);
+        assert_eq!(store.get_memory(&associated).unwrap().retrieval_count, 1);
+
+        assert!(store.find_relevant_associative(&query, 2, -1.0, 1).unwrap_err().is_invalid_parameter());
+        assert_eq!(store.find_relevant_associative(&query, 3, 0.5, 100).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_similar_memories_lower_retention_with_interference() {
+        let mut store = MemoryStore::default();
+        let formed = Utc::now() - Duration::days(10);
+        let add = |store: &mut MemoryStore, vector: Vec<f32>| {
+            let mut memory = Memory::new(vector, 0.0, 25.0, 1.0);
+            memory.timestamp = formed;
+            store.add_memory(memory)
+        };
+        let id = add(&mut store, vec![1.0, 0.0, 0.0]);
+        let now = Utc::now();
+        let retention = |store: &MemoryStore| {
+            store.calculate_retention_with_interference(store.get_memory(&id).unwrap(), now)
+        };
+        let alone = retention(&store);
+        assert_eq!(
+            alone,
+            store.get_memory(&id).unwrap().calculate_retention(now, &AgentState::default(), &AgentProfile::default())
+        );
+
+        // Dissimilar memories do not interfere.
+        add(&mut store, vec![0.0, 1.0, 0.0]);
+        assert_eq!(retention(&store), alone);
+
+        let mut previous = alone;
+        let mut similar = Vec::new();
+        for i in 1..=5 {
+            similar.push(add(&mut store, vec![1.0, 0.01 * i as f32, 0.0]));
+            let crowded = retention(&store);
+            assert!(crowded < previous);
+            previous = crowded;
+        }
+        for other in similar {
+            let memory = store.get_memory(&other).unwrap();
+            let plain = memory.calculate_retention(now, store.agent_state(), store.agent_profile());
+            assert!(store.calculate_retention_with_interference(memory, now) < plain);
+        }
+    }
+
+    #[test]
+    fn test_set_capacity_weight_updates_capacity_and_retention() {
+        use approx::assert_relative_eq;
+
+        let mut store = MemoryStore::default();
+        let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+        store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 0.5));
+        let now = Utc::now();
+        let retention = |store: &MemoryStore| {
+            store.get_memory(&id).unwrap().calculate_retention(now, store.agent_state(), store.agent_profile())
+        };
+        assert!(store.agent_profile().use_capacity_in_retention);
+        let before = retention(&store);
+        assert_eq!(store.total_capacity_used(), 1.5);
+
+        store.set_capacity_weight(&id, 0.25).unwrap();
+        assert_eq!(store.total_capacity_used(), 0.75);
+        assert_relative_eq!(retention(&store), before * 0.25, max_relative = 1e-5);
+
+        store.set_capacity_weight(&id, -1.0).unwrap();
+        assert_eq!(store.get_memory(&id).unwrap().capacity_weight, 0.0);
+        assert_eq!(retention(&store), 0.0);
+
+        assert!(store.set_capacity_weight(&id, f32::NAN).unwrap_err().is_invalid_parameter());
+        assert!(store.set_capacity_weight(&Uuid::new_v4(), 0.5).unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn test_sleep_consolidates_salient_recent_memories() {
+        let mut store = MemoryStore::default();
+        store.update_agent_state(AgentState {
+            sleep_debt: 1.5,
+            ..AgentState::default()
+        });
+        let salient = store.add_memory(Memory::new(vec![0.1, 0.2], -0.9, 25.0, 1.0));
+        let neutral = store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0));
+        let mut old = Memory::new(vec![0.5, 0.6], 0.9, 25.0, 1.0);
+        old.timestamp = Utc::now() - Duration::days(3);
+        let old = store.add_memory(old);
+
+        let now = Utc::now();
+        let retention = |store: &MemoryStore, id: &Uuid| {
+            store.get_memory(id).unwrap().calculate_retention(now, &AgentState::default(), store.agent_profile())
+        };
+        let before: Vec<f32> = [salient, neutral, old].iter().map(|id| retention(&store, id)).collect();
+
+        store.sleep(8.0).unwrap();
+        assert_eq!(store.agent_state().sleep_debt, 0.5);
+        assert!(retention(&store, &salient) > before[0]);
+        assert!(retention(&store, &neutral) < before[1]);
+        assert_eq!(retention(&store, &old), before[2]);
+
+        store.sleep(16.0).unwrap();
+        assert_eq!(store.agent_state().sleep_debt, 0.0);
+        assert!(store.sleep(-1.0).unwrap_err().is_invalid_parameter());
+        assert!(store.sleep(f32::NAN).unwrap_err().is_invalid_parameter());
+    }
+
+    #[test]
+    fn test_find_relevant_weighted_emphasizes_dimensions() {
+        let mut store = MemoryStore::default();
+        let now = Utc::now();
+        let mut add = |vector: Vec<f32>| {
+            let mut memory = Memory::new(vector, 0.0, 25.0, 1.0);
+            memory.timestamp = now;
+            store.add_memory(memory)
+        };
+        let first = add(vec![1.0, 0.2, 0.0]);
+        let second = add(vec![0.2, 1.0, 0.0]);
+        let third = add(vec![0.0, 0.3, 1.0]);
+        let query = [1.0, 0.8, 0.5];
+
+        let ids = |results: Vec<RetrievalResult>| results.into_iter().map(|r| r.memory.id).collect::<Vec<_>>();
+        let mut twin = store.clone_filtered(|_| true);
+        assert_eq!(ids(twin.find_relevant_weighted(&query, &[1.0, 1.0, 1.0], 3).unwrap()), vec![first, second, third]);
+        assert_eq!(ids(store.find_relevant_weighted(&query, &[1.0, 1.0, 10.0], 3).unwrap()), vec![third, first, second]);
+
+        assert!(store.find_relevant_weighted(&query, &[1.0, 1.0], 3).unwrap_err().is_invalid_parameter());
+        assert!(store.find_relevant_weighted(&[1.0, 0.8], &[1.0, 1.0], 3).unwrap_err().is_invalid_parameter());
+        assert!(store.find_relevant_weighted(&query, &[1.0, -1.0, 1.0], 3).unwrap_err().is_invalid_parameter());
+        assert!(store.find_relevant_weighted(&query, &[1.0, f32::NAN, 1.0], 3).unwrap_err().is_invalid_parameter());
+    }
+
+    #[test]
+    fn test_map_into_truncates_vectors_and_drops_none() {
+        let mut store = MemoryStore::default();
+        store.set_metric(SimilarityMetric::DotProduct);
+        let ids: Vec<Uuid> = (0..6)
+            .map(|i| store.add_memory(Memory::new(vec![i as f32, 1.0, 2.0, 3.0], 0.0, 25.0, 1.0)))
+            .collect();
+        let dropped = ids[0];
+
+        const K: usize = 2;
+        let mapped = store.map_into(|mut mem| {
+            if mem.id == dropped {
+                return None;
+            }
+            mem.semantic_vector.truncate(K);
+            Some(mem)
+        });
+        assert_eq!(mapped.memories.len(), ids.len() - 1);
+        assert!(mapped.get_memory(&dropped).is_none());
+        assert!(mapped.memories.values().all(|m| m.semantic_vector.len() == K));
+        assert_eq!(mapped.metric(), SimilarityMetric::DotProduct);
+        assert_eq!(mapped.agent_profile(), &AgentProfile::default());
+    }
+
+    #[test]
+    fn test_merge_resolves_conflicts() {
+        let shared = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+        let mut stronger = shared.clone();
+        stronger.memory_strength = 2.0;
+        stronger.emotion = 0.5;
+        let mut weaker = Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0);
+        let mut existing_strong = weaker.clone();
+        existing_strong.memory_strength = 3.0;
+        weaker.memory_strength = 0.5;
+
+        let mut store = MemoryStore::new(AgentProfile { rho: 0.3, ..AgentProfile::default() }, AgentState::default());
+        store.add_memory(shared.clone());
+        store.add_memory(existing_strong.clone());
+        let mut other = MemoryStore::default();
+        other.add_memory(stronger.clone());
+        other.add_memory(weaker.clone());
+        let new_id = other.add_memory(Memory::new(vec![0.5, 0.6], 0.0, 25.0, 1.0));
+
+        assert_eq!(store.merge(other.clone_filtered(|_| true), ConflictPolicy::KeepStronger), 2);
+        assert_eq!(store.memories.len(), 3);
+        assert_eq!(store.get_memory(&shared.id), Some(&stronger));
+        assert_eq!(store.get_memory(&existing_strong.id), Some(&existing_strong));
+        assert!(store.get_memory(&new_id).is_some());
+        assert_eq!(store.norm_cache.len(), 3);
+        assert_eq!(store.agent_profile().rho, 0.3);
+
+        assert_eq!(store.merge(other.clone_filtered(|_| true), ConflictPolicy::KeepExisting), 0);
+        assert_eq!(store.merge(other, ConflictPolicy::KeepIncoming), 3);
+        assert_eq!(store.get_memory(&existing_strong.id), Some(&weaker));
+    }
+
+    #[test]
+    fn test_no_match_policy_for_orthogonal_query() {
+        let mut store = MemoryStore::default();
+        let ids: Vec<Uuid> = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+            .iter()
+            .map(|v| store.add_memory(Memory::new(v.to_vec(), 0.0, 25.0, 1.0)))
+            .collect();
+        let orthogonal = [0.0, 0.0, 1.0];
+        assert_eq!(store.no_match_policy(), NoMatchPolicy::ReturnTopN);
+        let mut twin = store.clone_filtered(|_| true);
+        let results = twin.find_relevant(&orthogonal, 2).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.score == 0.0));
+
+        store.set_no_match_policy(NoMatchPolicy::ReturnEmpty);
+        assert!(store.find_relevant(&orthogonal, 2).unwrap().is_empty());
+        assert!(ids.iter().all(|id| store.get_memory(id).unwrap().retrieval_count == 0));
+        let results = store.find_relevant(&[1.0, 0.0, 0.0], 2).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].memory.id, ids[0]);
+    }
+
+    #[test]
+    fn test_retention_pages_cover_every_memory_once() {
+        let mut store = MemoryStore::default();
+        let formed = Utc::now();
+        for i in 0..23 {
+            // Pairs of identical memories exercise the ID tiebreak.
+            let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, (i / 2) as f32 / 12.0);
+            memory.timestamp = formed;
+            store.add_memory(memory);
+        }
+        let now = Utc::now();
+
+        let (full, total) = store.top_by_retention_paged(0, usize::MAX, now);
+        assert_eq!(total, 23);
+        assert!(full.windows(2).all(|w| w[0].0 > w[1].0 || (w[0].0 == w[1].0 && w[0].1 < w[1].1)));
+
+        let mut paged = Vec::new();
+        for offset in (0..total).step_by(5) {
+            let (page, page_total) = store.top_by_retention_paged(offset, 5, now);
+            assert_eq!(page_total, total);
+            paged.extend(page);
+        }
+        assert_eq!(paged, full);
+        assert!(store.top_by_retention_paged(total, 5, now).0.is_empty());
+    }
+
+    #[test]
+    fn test_touch() {
+        let mut store = MemoryStore::default();
+        let mut memory = create_test_memory(0.5, 1);
+        memory.last_retrieved = memory.timestamp;
+        let id = memory.id;
+        let formed = memory.last_retrieved;
+        store.add_memory(memory);
+
+        store.touch(&id).unwrap();
+
+        let memory = store.get_memory(&id).unwrap();
+        assert!(memory.last_retrieved > formed);
+        assert_eq!(memory.memory_strength, 1.0);
+        assert_eq!(memory.retrieval_count, 0);
+        assert!(store.touch(&Uuid::new_v4()).unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn test_find_relevant() {
+        let mut store = MemoryStore::new(AgentProfile::default(), AgentState {
+            current_age: 30.0,
+            sleep_debt: 0.0,
+            cortisol_level: 0.0,
+            fatigue: 0.0,
+            training_factor: 0.0,
+        });
+
+        // Add some test memories
+        store.add_memory(create_test_memory(0.5, 1));
+        store.add_memory(create_test_memory(-0.2, 2));
+        store.add_memory(create_test_memory(0.8, 3));
+
+        // Find relevant memories
+        let results = store.find_relevant(&[0.1, 0.2, 0.3], 2).unwrap();
+        assert_eq!(results.len(), 2);
+        
+        // Should be sorted by relevance
+        assert!(results[0].score >= results[1].score);
+    }
+
+    #[test]
+    fn test_retention_snapshot_does_not_mutate() {
+        let mut store = MemoryStore::default();
+        store.add_memory(create_test_memory(0.5, 1));
+        store.add_memory(create_test_memory(0.0, 30));
+        store.add_memory(create_test_memory(-0.8, 7));
+        let now = Utc::now();
+
+        let snapshot = store.retention_snapshot(now);
+        assert_eq!(snapshot.len(), 3);
+        for (id, retention) in &snapshot {
+            let mem = store.get_memory(id).unwrap();
+            assert_eq!(*retention, mem.calculate_retention(now, store.agent_state(), store.agent_profile()));
+            assert_eq!(mem.retrieval_count, 0);
+            assert_eq!(mem.memory_strength, 1.0);
+            assert!(mem.recall_history.is_empty());
+        }
+
+        let ranked = store.retention_ranked(now);
+        assert_eq!(ranked.len(), 3);
+        assert!(ranked.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn test_maintenance() {
+        let mut store = MemoryStore::new(AgentProfile::default(), AgentState {
+            current_age: 30.0,
+            sleep_debt: 0.0,
+            cortisol_level: 0.0,
+            fatigue: 0.0,
+            training_factor: 0.0,
+        });
+
+        // Add a memory that should be kept (recent)
+        store.add_memory(create_test_memory(0.5, 1));
+        
+        // Add a memory that should be pruned (very old)
+        let mut old_memory = create_test_memory(0.5, 1);
+        old_memory.timestamp = Utc::now() - Duration::days(365);
+        let old_id = old_memory.id;
+        store.add_memory(old_memory);
+
+        // Run maintenance with a threshold that should prune the old memory
+        let pruned = store.maintain(0.1);
+        
+        assert!(pruned > 0);
+        assert!(store.get_memory(&old_id).is_none());
+    }
+
+    #[test]
+    fn test_find_relevant_with_metric() {
+        let mut store = MemoryStore::new(AgentProfile::default(), AgentState {
+            current_age: 30.0,
+            sleep_debt: 0.0,
+            cortisol_level: 0.0,
+            fatigue: 0.0,
+            training_factor: 0.0,
+        });
+        assert_eq!(store.metric(), SimilarityMetric::Cosine);
+
+        let aligned = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+        let aligned_id = aligned.id;
+        let large = Memory::new(vec![10.0, 10.0], 0.0, 25.0, 1.0);
+        let large_id = large.id;
+        store.add_memory(aligned);
+        store.add_memory(large);
+
+        let results = store.find_relevant(&[1.0, 0.0], 2).unwrap();
+        assert_eq!(results[0].memory.id, aligned_id);
+
+        store.set_metric(SimilarityMetric::DotProduct);
+        let results = store.find_relevant(&[1.0, 0.0], 2).unwrap();
+        assert_eq!(results[0].memory.id, large_id);
+
+        store.set_metric(SimilarityMetric::NegativeL2);
+        let results = store.find_relevant(&[1.0, 0.0], 2).unwrap();
+        assert_eq!(results[0].memory.id, aligned_id);
+    }
+
+    #[test]
+    fn test_negative_l2_ranks_better_retained_memories_first() {
+        let mut store = MemoryStore::default();
+        store.set_metric(SimilarityMetric::NegativeL2);
+        store.set_no_match_policy(NoMatchPolicy::ReturnEmpty);
+        let strong = store.add_memory(Memory::new(vec![1.0, 1.0], 0.0, 25.0, 1.0));
+        let mut faded = Memory::new(vec![1.0, -1.0], 0.0, 25.0, 1.0);
+        faded.memory_strength = 0.5;
+        let faded = store.add_memory(faded);
+
+        // Both memories are at distance 1 from the query.
+        let results = store.find_relevant(&[1.0, 0.0], 2).unwrap();
+        assert_eq!(results.iter().map(|r| r.memory.id).collect::<Vec<_>>(), vec![strong, faded]);
+        assert_eq!(results[0].similarity, -1.0);
+        assert!(results.iter().all(|r| r.score > 0.0));
+        assert!((results[0].score - 0.5 * results[0].retention).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_retrieval_result_exposes_score_components() {
+        let mut store = MemoryStore::default();
+        store.set_similarity_transform(SimilarityTransform::Rescale01);
+        let id = store.add_memory(Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0));
+
+        let result = store.find_relevant(&[1.0, 0.0], 1).unwrap().remove(0);
+        assert_eq!(result.memory.id, id);
+        assert!(result.similarity.abs() < 1e-6);
+        assert!(result.retention > 0.0);
+        assert!((result.score - 0.5 * result.retention).abs() < 1e-6);
+
+        #[allow(deprecated)]
+        let tuples = store.find_relevant_tuples(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(tuples[0].1.id, id);
+    }
+
+    #[test]
+    fn test_retrieval_result_reports_updated_retrieval_history() {
+        let mut store = MemoryStore::default();
+        let id = store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+        let added_at = store.get_memory(&id).unwrap().last_retrieved;
+
+        let first = store.find_relevant(&[1.0, 0.0], 1).unwrap().remove(0);
+        assert_eq!(first.retrieval_count, 1);
+        assert!(first.last_retrieved >= added_at);
+        let second = store.find_relevant(&[1.0, 0.0], 1).unwrap().remove(0);
+        assert_eq!(second.retrieval_count, 2);
+        assert!(second.last_retrieved >= first.last_retrieved);
+        assert_eq!(second.last_retrieved, store.get_memory(&id).unwrap().last_retrieved);
+    }
+
+    #[test]
+    fn test_find_relevant_ids_matches_find_relevant() {
+        let mut store = MemoryStore::default();
+        for i in 0..5 {
+            store.add_memory(Memory::new(vec![1.0, i as f32], 0.0, 25.0, 1.0));
+        }
+        let mut twin = store.clone_filtered(|_| true);
+
+        let ids = store.find_relevant_ids(&[1.0, 0.5], 3).unwrap();
+        let full = twin.find_relevant(&[1.0, 0.5], 3).unwrap();
+        assert_eq!(ids.len(), 3);
+        for ((score, id), result) in ids.iter().zip(&full) {
+            assert_eq!(*id, result.memory.id);
+            assert_eq!(*score, result.score);
+            assert_eq!(store.get_memory(id).unwrap().retrieval_count, 1);
+        }
+    }
+
+    #[test]
+    fn test_similarity_transform_clamps_negative_scores() {
+        let mut store = MemoryStore::default();
+        let opposite = Memory::new(vec![-1.0, 0.0], 0.0, 25.0, 1.0);
+        let opposite_id = opposite.id;
+        store.add_memory(opposite);
+
+        let results = store.find_relevant(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].memory.id, opposite_id);
+        assert!(results[0].score < 0.0);
+
+        store.set_similarity_transform(SimilarityTransform::ClampNonNegative);
+        let results = store.find_relevant(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].score, 0.0);
+    }
+
+    #[test]
+    fn test_find_relevant_filtered_skips_non_matching_memories() {
+        let mut store = MemoryStore::default();
+        let mut ids = Vec::new();
+        for (i, topic) in ["work", "home", "work", "home"].iter().enumerate() {
+            let mut memory = Memory::new(vec![1.0, 0.1 * i as f32], 0.0, 25.0, 1.0);
+            memory.metadata = serde_json::json!({ "topic": topic });
+            ids.push(store.add_memory(memory));
+        }
+
+        let results = store
+            .find_relevant_filtered(&[1.0, 0.0], 2, |m| m.metadata["topic"] == "home")
+            .unwrap();
+        let mut found: Vec<Uuid> = results.iter().map(|r| r.memory.id).collect();
+        found.sort();
+        let mut expected = vec![ids[1], ids[3]];
+        expected.sort();
+        assert_eq!(found, expected);
+
+        // Filtered-out memories are never strengthened.
+        assert_eq!(store.get_memory(&ids[0]).unwrap().retrieval_count, 0);
+        assert_eq!(store.get_memory(&ids[2]).unwrap().retrieval_count, 0);
+        assert_eq!(store.get_memory(&ids[1]).unwrap().retrieval_count, 1);
+    }
+
+    #[test]
+    fn test_time_window_boundaries_are_inclusive() {
+        let mut store = MemoryStore::default();
+        let boundary = Utc::now() - Duration::days(7);
+        let mut ids = Vec::new();
+        for timestamp in [boundary - Duration::seconds(1), boundary, Utc::now()] {
+            let mut memory = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+            memory.timestamp = timestamp;
+            ids.push(store.add_memory(memory));
+        }
+
+        let results = store.find_relevant_within(&[1.0, 0.0], 3, boundary).unwrap();
+        let found: HashSet<Uuid> = results.iter().map(|r| r.memory.id).collect();
+        assert_eq!(found, [ids[1], ids[2]].into_iter().collect());
+
+        assert_eq!(store.maintain_older_than(Duration::days(30), 1.0), 0);
+        assert_eq!(store.maintain_older_than(Duration::days(7), 1.0), 2);
+        assert!(store.get_memory(&ids[2]).is_some());
+    }
+
+    #[test]
+    fn test_maintain_older_than_max_age_prunes_nothing() {
+        let mut store = MemoryStore::default();
+        let mut old = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+        old.timestamp = Utc::now() - Duration::days(400);
+        store.add_memory(old);
+
+        assert_eq!(store.maintain_older_than(Duration::MAX, 1.0), 0);
+        assert_eq!(store.memories.len(), 1);
+    }
+
+    #[test]
+    fn test_recency_half_life_favors_recent_memories() {
+        let mut store = MemoryStore::default();
+        // The older memory matches the query exactly and is strong enough to
+        // outrank the recent one on similarity and retention alone.
+        let mut old = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+        old.timestamp = Utc::now() - Duration::days(2);
+        old.memory_strength = 5.0;
+        let old_id = old.id;
+        let mut recent = Memory::new(vec![1.0, 0.5], 0.0, 25.0, 1.0);
+        recent.timestamp = Utc::now() - Duration::minutes(10);
+        let recent_id = recent.id;
+        store.add_memory(old);
+        store.add_memory(recent);
+
+        let (state, profile) = (store.agent_state().clone(), store.agent_profile().clone());
+        let results = store.find_relevant_with_state(&[1.0, 0.0], 2, &state, &profile).unwrap();
+        assert_eq!(results[0].memory.id, old_id);
+
+        store.set_recency_half_life(Some(Duration::hours(1))).unwrap();
+        let results = store.find_relevant(&[1.0, 0.0], 2).unwrap();
+        assert_eq!(results[0].memory.id, recent_id);
+    }
+
+    #[test]
+    fn test_recency_half_life_rejects_sub_millisecond_values() {
+        let mut store = MemoryStore::default();
+        store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+
+        assert!(store.set_recency_half_life(Some(Duration::microseconds(500))).is_err());
+        assert!(store.set_recency_half_life(Some(Duration::microseconds(-500))).is_err());
+        assert_eq!(store.recency_half_life(), None);
+
+        store.set_recency_half_life(Some(Duration::milliseconds(1))).unwrap();
+        let results = store.find_relevant(&[1.0, 0.0], 1).unwrap();
+        assert!(results[0].score.is_finite());
+    }
+
+    #[test]
+    fn test_capacity_exponent_favors_high_capacity_memories() {
+        // Keep capacity out of retention so both memories retain equally.
+        let profile = AgentProfile {
+            use_capacity_in_retention: false,
+            ..AgentProfile::default()
+        };
+        let mut store = MemoryStore::new(profile, AgentState::default());
+        let now = Utc::now();
+        let mut low = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 0.2);
+        let mut high = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 0.9);
+        low.timestamp = now;
+        high.timestamp = now;
+        let high_id = high.id;
+        store.add_memory(low);
+        store.add_memory(high);
+
+        store.set_capacity_exponent(Some(1.0)).unwrap();
+        let results = store.find_relevant(&[1.0, 0.0], 2).unwrap();
+        assert_eq!(results[0].memory.id, high_id);
+        assert_eq!(results[0].similarity, results[1].similarity);
+        assert_eq!(results[0].retention, results[1].retention);
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn test_rehydrator_restores_archived_memories() {
+        let mut store = MemoryStore::default();
+        let mut archived = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+        archived.metadata = serde_json::json!({ "text": "cat" });
+        let archived_id = store.add_memory(archived);
+        let other_id = store.add_memory(Memory::new(vec![0.5, 0.5], 0.0, 25.0, 1.0));
+
+        store.archive_vector(&archived_id).unwrap();
+        assert!(store.get_memory(&archived_id).unwrap().semantic_vector.is_empty());
+        assert_eq!(store.rehydrate_vectors(), 0);
+        let results = store.find_relevant(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].memory.id, other_id);
+
+        store.set_rehydrator(|mem| match mem.metadata["text"].as_str() {
+            Some("cat") => Some(vec![1.0, 0.0]),
+            _ => None,
+        });
+        let results = store.find_relevant(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].memory.id, archived_id);
+        assert_eq!(store.norm_cache[&archived_id], 1.0);
+        assert!(store.archive_vector(&Uuid::new_v4()).unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn test_consolidate_merges_near_duplicates() {
+        let mut store = MemoryStore::default();
+        let mut older = create_test_memory(0.2, 5);
+        older.semantic_vector = vec![1.0, 0.0];
+        older.retrieval_count = 2;
+        older.memory_strength = 3.0;
+        let older_timestamp = older.timestamp;
+        let mut newer = create_test_memory(-0.8, 1);
+        // cos(0.1415 rad) ~= 0.99
+        newer.semantic_vector = vec![0.99, 0.1411];
+        newer.retrieval_count = 1;
+        let older_id = store.add_memory(older);
+        let newer_id = store.add_memory(newer);
+        let distinct_id = store.add_memory(Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0));
+
+        assert_eq!(store.consolidate(0.98), 1);
+        assert_eq!(store.memories.len(), 2);
+        assert!(store.get_memory(&newer_id).is_none());
+        assert!(store.get_memory(&distinct_id).is_some());
+
+        let merged = store.get_memory(&older_id).unwrap();
+        assert_eq!(merged.timestamp, older_timestamp);
+        assert_eq!(merged.retrieval_count, 3);
+        assert_eq!(merged.emotion, -0.8);
+        assert!((merged.semantic_vector[0] - (3.0 + 0.99) / 4.0).abs() < 1e-6);
+        assert!((merged.semantic_vector[1] - 0.1411 / 4.0).abs() < 1e-6);
+        assert_eq!(store.norm_cache[&older_id], simd::norm(&merged.semantic_vector));
+
+        assert_eq!(store.consolidate(0.98), 0);
+    }
+
+    #[test]
+    fn test_capped_insert_evicts_lowest_retention() {
+        let mut store = MemoryStore::default();
+        store.set_max_memories(Some(3)).unwrap();
+        let weakest = store.add_memory(create_test_memory(0.0, 400));
+        store.add_memory(create_test_memory(0.0, 1));
+        store.add_memory(create_test_memory(0.0, 2));
+
+        let evicted = store.add_memory_capped(create_test_memory(0.0, 0));
+        assert_eq!(evicted, Some(weakest));
+        assert_eq!(store.memories.len(), 3);
+        assert!(store.get_memory(&weakest).is_none());
+        assert!(!store.norm_cache.contains_key(&weakest));
+    }
+
+    #[test]
+    fn test_capped_upsert_does_not_evict() {
+        let mut store = MemoryStore::default();
+        store.set_metadata_policy(MetadataPolicy::Merge);
+        store.set_max_memories(Some(2)).unwrap();
+        let weakest = create_test_memory(0.0, 400).with_metadata("kind", "event");
+        let weakest_id = store.add_memory(weakest.clone());
+        let other = store.add_memory(create_test_memory(0.0, 1));
+
+        let mut update = weakest.clone();
+        update.metadata = serde_json::json!({ "source": "chat" });
+        assert_eq!(store.add_memory_capped(update.clone()), None);
+        assert!(store.get_memory(&other).is_some());
+        let metadata = &store.get_memory(&weakest_id).unwrap().metadata;
+        assert_eq!(metadata["kind"], "event");
+        assert_eq!(metadata["source"], "chat");
+
+        assert_eq!(store.add_memories(vec![update, weakest]), vec![weakest_id, weakest_id]);
+        assert_eq!(store.memories.len(), 2);
+        assert!(store.get_memory(&other).is_some());
+    }
+
+    #[test]
+    fn test_eviction_uses_capacity_when_retention_ignores_it() {
+        let profile = AgentProfile {
+            use_capacity_in_retention: false,
+            ..AgentProfile::default()
+        };
+        let mut store = MemoryStore::new(profile, AgentState::default());
+        store.set_max_memories(Some(2)).unwrap();
+        let formed = Utc::now() - Duration::days(1);
+        let (mut light, mut heavy) = (
+            Memory::new(vec![1.0, 0.0], 0.0, 25.0, 0.1),
+            Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0),
+        );
+        light.timestamp = formed;
+        heavy.timestamp = formed;
+        let light = store.add_memory(light);
+        let heavy = store.add_memory(heavy);
+
+        let now = Utc::now();
+        let state = store.agent_state().clone();
+        let retention = |id| store.get_memory(&id).unwrap().calculate_retention(now, &state, store.agent_profile());
+        assert_eq!(retention(light), retention(heavy));
+
+        store.add_memory(Memory::new(vec![1.0, 1.0], 0.0, 25.0, 1.0));
+        assert!(store.get_memory(&light).is_none());
+        assert!(store.get_memory(&heavy).is_some());
+    }
+
+    #[test]
+    fn test_eviction_policies() {
+        let mut store = MemoryStore::default();
+        let mut oldest = create_test_memory(0.0, 10);
+        oldest.last_retrieved = Utc::now();
+        let oldest_id = store.add_memory(oldest);
+        let mut stale = create_test_memory(0.0, 5);
+        stale.last_retrieved = stale.timestamp;
+        let stale_id = store.add_memory(stale);
+        store.add_memory(create_test_memory(0.0, 1));
+
+        store.set_eviction_policy(EvictionPolicy::LeastRecentlyRetrieved);
+        assert_eq!(store.set_max_memories(Some(2)).unwrap(), vec![stale_id]);
+
+        store.set_eviction_policy(EvictionPolicy::Oldest);
+        assert_eq!(store.add_memory_capped(create_test_memory(0.0, 0)), Some(oldest_id));
+
+        store.set_max_memories(None).unwrap();
+        assert_eq!(store.add_memory_capped(create_test_memory(0.0, 0)), None);
+        assert_eq!(store.memories.len(), 3);
+        assert!(store.set_max_memories(Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_find_relevant_with_state_override() {
+        let mut store = MemoryStore::default();
+        let mut old_match = create_test_memory(0.0, 60);
+        old_match.semantic_vector = vec![1.0, 0.0];
+        let old_id = store.add_memory(old_match);
+        let mut fresh_partial = create_test_memory(0.0, 0);
+        fresh_partial.semantic_vector = vec![0.6, 0.8];
+        let fresh_id = store.add_memory(fresh_partial);
+
+        let calm = AgentState::default();
+        let stressed = AgentState {
+            cortisol_level: 5.0,
+            ..AgentState::default()
+        };
+        let profile = store.agent_profile().clone();
+
+        let results = store.find_relevant_with_state(&[1.0, 0.0], 1, &calm, &profile).unwrap();
+        assert_eq!(results[0].memory.id, old_id);
+        let results = store.find_relevant_with_state(&[1.0, 0.0], 1, &stressed, &profile).unwrap();
+        assert_eq!(results[0].memory.id, fresh_id);
+
+        // The store itself is left untouched.
+        assert_eq!(store.get_memory(&old_id).unwrap().retrieval_count, 0);
+        assert_eq!(store.get_memory(&fresh_id).unwrap().retrieval_count, 0);
+    }
+
+    #[test]
+    fn test_maintain_budgeted_resumes_across_calls() {
+        let mut store = MemoryStore::default();
+        for _ in 0..5 {
+            store.add_memory(create_test_memory(0.0, 1));
+        }
+
+        let first = store.maintain_budgeted(0.0, 2);
+        assert_eq!(first, MaintainProgress { processed: 2, pruned: 0, remaining: 3, finished: false });
+
+        // Memories added mid-pass wait for the next pass.
+        store.add_memory(create_test_memory(0.0, 1));
+        let second = store.maintain_budgeted(1.0, 2);
+        assert_eq!(second, MaintainProgress { processed: 2, pruned: 2, remaining: 1, finished: false });
+        let third = store.maintain_budgeted(1.0, 2);
+        assert_eq!(third, MaintainProgress { processed: 1, pruned: 1, remaining: 0, finished: true });
+        assert_eq!(store.memories.len(), 3);
+        assert_eq!(store.norm_cache.len(), 3);
+
+        let next_pass = store.maintain_budgeted(1.0, 10);
+        assert_eq!(next_pass, MaintainProgress { processed: 3, pruned: 3, remaining: 0, finished: true });
+        assert!(store.memories.is_empty());
+    }
+
+    #[test]
+    fn test_validate_all_reports_every_invalid_memory() {
+        let mut store = MemoryStore::default();
+        store.add_memory(create_test_memory(0.0, 1));
+        let mut nan_vector = create_test_memory(0.0, 1);
+        nan_vector.semantic_vector[2] = f32::NAN;
+        let nan_id = store.add_memory(nan_vector);
+        let mut emotional = create_test_memory(0.0, 1);
+        emotional.emotion = 2.0;
+        let too_emotional = store.add_memory(emotional);
+        let mut overweight = create_test_memory(0.0, 1);
+        overweight.capacity_weight = 3.0;
+        let overweight_id = store.add_memory(overweight);
+
+        let mut report = store.validate_all();
+        report.sort_by_key(|(id, _)| *id);
+        let mut expected = vec![
+            (nan_id, MemoryError::invalid_param("semantic_vector[2]", f32::NAN)),
+            (too_emotional, MemoryError::invalid_param("emotion", 2.0)),
+            (overweight_id, MemoryError::invalid_param("capacity_weight", 3.0)),
+        ];
+        expected.sort_by_key(|(id, _)| *id);
+        assert_eq!(report, expected);
+    }
+
+    #[test]
+    fn test_disabled_index_matches_brute_force() {
+        let memories: Vec<Memory> = (0..20)
+            .map(|i| Memory::new(vec![(i as f32).sin(), (i as f32).cos(), 0.5], 0.0, 25.0, 1.0))
+            .collect();
+        let mut indexed = MemoryStore::default();
+        let mut brute = MemoryStore::default();
+        indexed.add_memories(memories.clone());
+        brute.add_memories(memories);
+        indexed.warm_up();
+        indexed.set_use_index(false);
+
+        let query = [0.3, 0.9, 0.1];
+        let scores = |results: Vec<RetrievalResult>| {
+            results.into_iter().map(|r| (r.memory.id, r.score)).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            scores(indexed.find_relevant(&query, 5).unwrap()),
+            scores(brute.find_relevant(&query, 5).unwrap())
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_dedicated_thread_pool_matches_serial_scoring() {
+        use approx::assert_relative_eq;
+
+        let memories: Vec<Memory> = (0..200)
+            .map(|i| Memory::new(vec![(i as f32).sin(), (i as f32).cos(), 0.5], 0.0, 25.0, 1.0))
+            .collect();
+        let mut store = MemoryStore::default();
+        store.add_memories(memories.clone());
+        store.set_thread_count(Some(2)).unwrap();
+        assert_eq!(store.thread_count(), Some(2));
+
+        let query = [0.3, 0.9, 0.1];
+        let now = Utc::now();
+        let (state, profile) = (store.agent_state().clone(), store.agent_profile().clone());
+        let parallel = store.score_all(&query, now, &state, &profile, &|_| true);
+
+        let mut serial: Vec<(Uuid, f32)> = memories
+            .iter()
+            .map(|mem| {
+                let similarity = store.metric.similarity(&query, &mem.semantic_vector);
+                let retention = mem.calculate_retention(now, &state, &profile);
+                (mem.id, store.score_memory(store.metric, similarity, retention, mem, now).score)
+            })
+            .collect();
+        let mut parallel: Vec<(Uuid, f32)> = parallel.into_iter().map(|(id, s)| (id, s.score)).collect();
+        serial.sort_by_key(|(id, _)| *id);
+        parallel.sort_by_key(|(id, _)| *id);
+        assert_eq!(parallel.len(), serial.len());
+        for ((id_a, a), (id_b, b)) in parallel.iter().zip(&serial) {
+            assert_eq!(id_a, id_b);
+            assert_relative_eq!(*a, *b, epsilon = 1e-6);
         }
-        Ok(Self {
-            memories: data.memories,
-            agent_profile: data.agent_profile,
-            agent_state: data.agent_state,
-            #[cfg(feature = "faiss")]
-            faiss_index: None,
-        })
+
+        assert_eq!(store.find_relevant(&query, 5).unwrap().len(), 5);
     }
-}
 
-/// Calculates cosine similarity between two vectors.
-///
-/// Returns `0.0` if the vectors are empty or their lengths differ.
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    simd_utils::cosine_similarity(a, b)
-}
+    #[test]
+    fn test_metadata_policy_merge_on_upsert() {
+        let mut store = MemoryStore::default();
+        let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+        memory.metadata = serde_json::json!({ "a": 1, "nested": { "x": 1 }, "tags": ["old"] });
+        let id = store.add_memory(memory.clone());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::Duration;
-    #[cfg(feature = "serde")]
-    use serde_json;
+        memory.metadata = serde_json::json!({ "b": 2 });
+        store.add_memory(memory.clone());
+        assert_eq!(store.get_memory(&id).unwrap().metadata, serde_json::json!({ "b": 2 }));
 
-    fn create_test_memory(emotion: f32, days_old: i64) -> Memory {
-        let mut memory = Memory::new(
-            vec![0.1, 0.2, 0.3], 
-            emotion, 
-            25.0, 
-            1.0
+        store.remove_memory(&id).unwrap();
+        memory.metadata = serde_json::json!({ "a": 1, "nested": { "x": 1 }, "tags": ["old"] });
+        store.add_memory(memory.clone());
+        store.set_metadata_policy(MetadataPolicy::Merge);
+        memory.metadata = serde_json::json!({ "b": 2, "nested": { "y": 2 }, "tags": ["new"] });
+        store.add_memory(memory);
+        assert_eq!(
+            store.get_memory(&id).unwrap().metadata,
+            serde_json::json!({ "a": 1, "b": 2, "nested": { "x": 1, "y": 2 }, "tags": ["new"] })
         );
-        memory.timestamp = Utc::now() - Duration::days(days_old);
-        memory
     }
 
     #[test]
-    fn test_add_and_retrieve_memory() {
-        let mut store = MemoryStore::new(AgentProfile::default(), AgentState {
-            current_age: 30.0,
-            sleep_debt: 0.0,
-            cortisol_level: 0.0,
-            fatigue: 0.0,
-            training_factor: 0.0,
-        });
+    fn test_clear_and_remove_matching() {
+        let mut store = MemoryStore::default();
+        let mut ids = Vec::new();
+        for i in 0..6 {
+            let mut memory = Memory::new(vec![1.0, i as f32], 0.0, 25.0, 1.0);
+            memory.metadata = serde_json::json!({ "session": if i % 2 == 0 { "123" } else { "456" } });
+            ids.push(store.add_memory(memory));
+        }
+        store.warm_up();
 
-        let memory = create_test_memory(0.5, 1);
-        let id = memory.id;
-        
-        store.add_memory(memory);
-        assert!(store.get_memory(&id).is_some());
-        
-        store.remove_memory(&id).unwrap();
-        assert!(store.get_memory(&id).is_none());
+        assert_eq!(store.remove_matching(|m| m.metadata["session"] == "123"), 3);
+        assert_eq!(store.memories.len(), 3);
+        assert_eq!(store.norm_cache.len(), 3);
+        assert!(store.get_memory(&ids[0]).is_none());
+        let results = store.find_relevant(&[1.0, 0.0], 6).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.memory.metadata["session"] == "456"));
+        assert_eq!(store.remove_matching(|_| false), 0);
+
+        store.clear();
+        assert!(store.memories.is_empty());
+        assert!(store.norm_cache.is_empty());
+        assert_eq!(store.embedding_dim(), None);
     }
 
     #[test]
-    fn test_find_relevant() {
-        let mut store = MemoryStore::new(AgentProfile::default(), AgentState {
-            current_age: 30.0,
-            sleep_debt: 0.0,
-            cortisol_level: 0.0,
-            fatigue: 0.0,
-            training_factor: 0.0,
-        });
+    fn test_count_by_metadata_source() {
+        let mut store = MemoryStore::default();
+        for source in ["conversation", "document", "conversation", "conversation"] {
+            let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+            memory.metadata = serde_json::json!({ "source": source });
+            store.add_memory(memory);
+        }
+        store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0));
 
-        // Add some test memories
-        store.add_memory(create_test_memory(0.5, 1));
-        store.add_memory(create_test_memory(-0.2, 2));
-        store.add_memory(create_test_memory(0.8, 3));
+        let counts = store.count_by(|m| m.metadata.get("source").and_then(|s| s.as_str()).map(str::to_owned));
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["conversation"], 3);
+        assert_eq!(counts["document"], 1);
+    }
 
-        // Find relevant memories
-        let results = store.find_relevant(&[0.1, 0.2, 0.3], 2).unwrap();
-        assert_eq!(results.len(), 2);
-        
-        // Should be sorted by relevance
-        assert!(results[0].0 >= results[1].0);
+    #[test]
+    fn test_snapshot_hash_ignores_insertion_order() {
+        let memories: Vec<Memory> = (0..5)
+            .map(|i| Memory::new(vec![i as f32, 1.0], 0.1 * i as f32, 25.0, 1.0))
+            .collect();
+        let mut forward = MemoryStore::default();
+        let mut reverse = MemoryStore::default();
+        for memory in &memories {
+            forward.add_memory(memory.clone());
+        }
+        for memory in memories.iter().rev() {
+            reverse.add_memory(memory.clone());
+        }
+        assert_eq!(forward.snapshot_hash(), reverse.snapshot_hash());
+
+        let before = forward.snapshot_hash();
+        forward.get_memory_mut(&memories[2].id).unwrap().semantic_vector[0] += 0.5;
+        assert_ne!(forward.snapshot_hash(), before);
+        forward.get_memory_mut(&memories[2].id).unwrap().semantic_vector[0] -= 0.5;
+        assert_eq!(forward.snapshot_hash(), before);
+
+        forward.get_memory_mut(&memories[4].id).unwrap().retrieval_count += 1;
+        assert_ne!(forward.snapshot_hash(), before);
+
+        let mut state = reverse.agent_state().clone();
+        state.fatigue = 0.5;
+        reverse.update_agent_state(state);
+        assert_ne!(reverse.snapshot_hash(), before);
     }
 
     #[test]
-    fn test_maintenance() {
-        let mut store = MemoryStore::new(AgentProfile::default(), AgentState {
-            current_age: 30.0,
-            sleep_debt: 0.0,
-            cortisol_level: 0.0,
-            fatigue: 0.0,
-            training_factor: 0.0,
+    fn test_try_reserve_reports_absurd_requests() {
+        let mut store = MemoryStore::default();
+        store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+
+        // Capacity overflow is detected before anything is allocated.
+        assert!(matches!(store.try_reserve(usize::MAX), Err(MemoryError::Storage(_))));
+        assert_eq!(store.memories.len(), 1);
+
+        let ids = store
+            .try_add_memories((0..3).map(|i| Memory::new(vec![i as f32, 1.0], 0.0, 25.0, 1.0)))
+            .unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(store.memories.len(), 4);
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_error_and_panic() {
+        let mut store = MemoryStore::default();
+        let a = store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+        let b = store.add_memory(Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0));
+        let before = store.snapshot_hash();
+
+        let result: Result<()> = store.transaction(|store| {
+            store.remove_memory(&a)?;
+            store.get_memory_mut(&b).unwrap().emotion = 0.9;
+            store.remove_memory(&Uuid::new_v4())?;
+            unreachable!("removing an unknown memory fails");
         });
+        assert!(result.unwrap_err().is_not_found());
+        assert_eq!(store.snapshot_hash(), before);
 
-        // Add a memory that should be kept (recent)
-        store.add_memory(create_test_memory(0.5, 1));
-        
-        // Add a memory that should be pruned (very old)
-        let mut old_memory = create_test_memory(0.5, 1);
-        old_memory.timestamp = Utc::now() - Duration::days(365);
-        let old_id = old_memory.id;
-        store.add_memory(old_memory);
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _ = store.transaction(|store| -> Result<()> {
+                store.remove_memory(&b)?;
+                panic!("midway");
+            });
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(store.snapshot_hash(), before);
 
-        // Run maintenance with a threshold that should prune the old memory
-        let pruned = store.maintain(0.1);
-        
-        assert!(pruned > 0);
-        assert!(store.get_memory(&old_id).is_none());
+        let removed = store.transaction(|store| store.remove_memory(&a));
+        assert!(removed.is_ok());
+        assert!(store.get_memory(&a).is_none());
+    }
+
+    #[test]
+    fn test_embedding_dim() {
+        let mut store = MemoryStore::default();
+        assert_eq!(store.embedding_dim(), None);
+
+        let first = store.add_memory(create_test_memory(0.0, 1));
+        store.add_memory(create_test_memory(0.0, 2));
+        assert_eq!(store.embedding_dim(), Some(3));
+
+        store.archive_vector(&first).unwrap();
+        assert_eq!(store.embedding_dim(), Some(3));
+
+        store.maintain(1.0);
+        assert_eq!(store.embedding_dim(), None);
+    }
+
+    #[test]
+    fn test_clone_filtered() {
+        let mut store = MemoryStore::default();
+        store.set_metric(SimilarityMetric::DotProduct);
+        let mut ids = Vec::new();
+        for i in 0..6 {
+            let mut memory = Memory::new(vec![i as f32, 1.0], 0.0, 25.0, 1.0);
+            memory.metadata = serde_json::json!({ "topic": if i % 2 == 0 { "work" } else { "home" } });
+            ids.push(store.add_memory(memory));
+        }
+
+        let mut work = store.clone_filtered(|m| m.metadata["topic"] == "work");
+        assert_eq!(work.memories.len(), 3);
+        for (i, id) in ids.iter().enumerate() {
+            assert_eq!(work.get_memory(id).is_some(), i % 2 == 0);
+        }
+        assert_eq!(work.metric(), SimilarityMetric::DotProduct);
+
+        let results = work.find_relevant(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].memory.id, ids[4]);
+        assert_eq!(work.get_memory(&ids[4]).unwrap().retrieval_count, 1);
+        assert_eq!(store.get_memory(&ids[4]).unwrap().retrieval_count, 0);
+    }
+
+    #[test]
+    fn test_warm_up_caches_norms() {
+        let mut store = MemoryStore::default();
+        let memory = Memory::new(vec![3.0, 4.0], 0.0, 25.0, 1.0);
+        let id = memory.id;
+        store.add_memory(memory);
+        store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+
+        // Mutable access invalidates the cached norm.
+        store.get_memory_mut(&id).unwrap().semantic_vector = vec![6.0, 8.0];
+        assert!(!store.norm_cache.contains_key(&id));
+
+        store.warm_up();
+        assert_eq!(store.norm_cache.len(), store.memories.len());
+        assert_eq!(store.norm_cache[&id], 10.0);
+
+        let results = store.find_relevant(&[3.0, 4.0], 1).unwrap();
+        assert_eq!(results[0].memory.id, id);
+    }
+
+    #[cfg(feature = "pq")]
+    #[test]
+    fn test_train_pq() {
+        let mut store = MemoryStore::default();
+        assert!(store.train_pq(2, 4).unwrap_err().is_invalid_parameter());
+
+        for i in 0..20 {
+            let x = i as f32 / 20.0;
+            store.add_memory(Memory::new(vec![x, 1.0 - x, x * x, 1.0], 0.0, 25.0, 1.0));
+        }
+        store.train_pq(2, 4).unwrap();
+        assert_eq!(store.pq_index().unwrap().len(), 20);
+
+        let late = Memory::new(vec![1.0, 0.0, 1.0, 1.0], 0.0, 25.0, 1.0);
+        let late_id = late.id;
+        store.add_memory(late);
+        assert!(store.pq_index().unwrap().codes(&late_id).is_some());
+
+        let results = store.find_relevant(&[1.0, 0.0, 1.0, 1.0], 5).unwrap();
+        assert_eq!(results.len(), 5);
+
+        store.remove_memory(&late_id).unwrap();
+        assert!(store.pq_index().unwrap().codes(&late_id).is_none());
     }
 
     #[cfg(feature = "serde")]
@@ -385,6 +4407,40 @@ mod tests {
         assert!(deserialized.get_memory(&id).is_some());
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_upgrades_v1_data() {
+        let memory = Memory::new(vec![0.1, 0.2], 0.0, 0.0, 1.0);
+        let id = memory.id;
+        let v1 = serde_json::json!({
+            "version": 1,
+            "memories": { id.to_string(): memory },
+            "agent_profile": AgentProfile::default(),
+            "agent_state": AgentState::default(),
+        });
+
+        let store: MemoryStore = serde_json::from_value(v1).expect("upgrade v1");
+        assert!(store.get_memory(&id).is_some());
+        assert_eq!(store.metric(), SimilarityMetric::Cosine);
+        assert_eq!(store.similarity_transform(), SimilarityTransform::Raw);
+
+        let future = serde_json::json!({ "version": DATA_FORMAT_VERSION + 1 });
+        assert!(serde_json::from_value::<MemoryStore>(future).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialization_preserves_metric() {
+        let mut store = MemoryStore::default();
+        store.set_metric(SimilarityMetric::DotProduct);
+        store.set_similarity_transform(SimilarityTransform::Rescale01);
+
+        let json = serde_json::to_string(&store).expect("serialize");
+        let restored: MemoryStore = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(restored.metric(), SimilarityMetric::DotProduct);
+        assert_eq!(restored.similarity_transform(), SimilarityTransform::Rescale01);
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_serialization_includes_version() {
@@ -393,4 +4449,465 @@ mod tests {
         let v: serde_json::Value = serde_json::from_str(&json).unwrap();
         assert_eq!(v["version"], DATA_FORMAT_VERSION);
     }
+
+    #[test]
+    fn test_find_relevant_normalized_sums_to_one() {
+        let mut store = MemoryStore::default();
+        for v in [[1.0, 0.0], [0.8, 0.6], [0.6, 0.8], [0.0, 1.0]] {
+            store.add_memory(Memory::new(v.to_vec(), 0.0, 25.0, 1.0));
+        }
+        let query = [1.0, 0.0];
+        let raw = store.clone_filtered(|_| true).find_relevant(&query, 3).unwrap();
+        let raw_ids: Vec<Uuid> = raw.iter().map(|r| r.memory.id).collect();
+
+        for normalization in [
+            ScoreNormalization::Sum,
+            ScoreNormalization::Softmax(1.0),
+            ScoreNormalization::Softmax(0.01),
+        ] {
+            let mut twin = store.clone_filtered(|_| true);
+            let results = twin.find_relevant_normalized(&query, 3, normalization).unwrap();
+            let total: f32 = results.iter().map(|r| r.score).sum();
+            assert!((total - 1.0).abs() < 1e-5, "{normalization:?} sums to {total}");
+            let ids: Vec<Uuid> = results.iter().map(|r| r.memory.id).collect();
+            assert_eq!(ids, raw_ids);
+            assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+        }
+
+        assert!(matches!(
+            store.find_relevant_normalized(&query, 3, ScoreNormalization::Softmax(0.0)),
+            Err(MemoryError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn test_retention_weight_favors_stronger_memories() {
+        let mut store = MemoryStore::default();
+        let formed = Utc::now();
+        let mut close = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+        close.timestamp = formed;
+        let mut strong = Memory::new(vec![0.8, 0.6], 0.0, 25.0, 1.0);
+        strong.timestamp = formed;
+        strong.memory_strength = 1.2;
+        let close_id = store.add_memory(close);
+        let strong_id = store.add_memory(strong);
+        let query = [1.0, 0.0];
+
+        assert_eq!(store.scoring_policy(), ScoringPolicy::default());
+        let results = store.clone_filtered(|_| true).find_relevant(&query, 2).unwrap();
+        assert_eq!(results[0].memory.id, close_id);
+
+        store
+            .set_scoring_policy(ScoringPolicy { similarity_weight: 1.0, retention_weight: 2.0 })
+            .unwrap();
+        let results = store.clone_filtered(|_| true).find_relevant(&query, 2).unwrap();
+        assert_eq!(results[0].memory.id, strong_id);
+
+        assert!(store
+            .set_scoring_policy(ScoringPolicy { similarity_weight: -1.0, retention_weight: 1.0 })
+            .is_err());
+        assert_eq!(store.scoring_policy().retention_weight, 2.0);
+    }
+
+    #[test]
+    fn test_find_relevant_above_drops_unrelated_memories() {
+        let mut store = MemoryStore::default();
+        let close = store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+        let near = store.add_memory(Memory::new(vec![0.8, 0.6], 0.0, 25.0, 1.0));
+        let far = store.add_memory(Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0));
+
+        let results = store.find_relevant_above(&[1.0, 0.0], 3, 0.7).unwrap();
+        let ids: Vec<Uuid> = results.iter().map(|r| r.memory.id).collect();
+        assert_eq!(ids, vec![close, near]);
+        assert!(results.iter().all(|r| r.similarity >= 0.7));
+        assert_eq!(store.get_memory(&far).unwrap().retrieval_count, 0);
+
+        assert!(store.find_relevant_above(&[1.0, 0.0], 3, 1.5).unwrap().is_empty());
+        assert_eq!(store.get_memory(&close).unwrap().retrieval_count, 1);
+        assert!(store.find_relevant_above(&[1.0, 0.0], 3, f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_equal_scores_rank_deterministically() {
+        let mut store = MemoryStore::default();
+        let formed = Utc::now();
+        let mut ids: Vec<Uuid> = (0..8)
+            .map(|_| {
+                let mut memory = Memory::new(vec![0.6, 0.8], 0.0, 25.0, 1.0);
+                memory.timestamp = formed;
+                store.add_memory(memory)
+            })
+            .collect();
+        ids.sort();
+
+        for _ in 0..3 {
+            let mut twin = store.clone_filtered(|_| true);
+            let results = twin.find_relevant(&[0.6, 0.8], ids.len()).unwrap();
+            let order: Vec<Uuid> = results.iter().map(|r| r.memory.id).collect();
+            assert_eq!(order, ids);
+        }
+
+        // Among equal scores the more recently formed memory ranks first.
+        let (low, high) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let earlier = formed - Duration::seconds(1);
+        assert_eq!(rank_order((0.5, formed, &high), (0.5, earlier, &low)), std::cmp::Ordering::Less);
+        assert_eq!(rank_order((0.5, formed, &low), (0.5, formed, &high)), std::cmp::Ordering::Less);
+        assert_eq!(rank_order((0.4, formed, &low), (0.5, earlier, &high)), std::cmp::Ordering::Greater);
+    }
+
+    #[test]
+    fn test_non_finite_vectors_are_rejected_and_do_not_poison_ranking() {
+        let mut store = MemoryStore::default();
+        let good = store.add_memory(Memory::new(vec![0.6, 0.8], 0.0, 25.0, 1.0));
+
+        let err = store.try_add_memory(Memory::new(vec![f32::NAN, 1.0], 0.0, 25.0, 1.0)).unwrap_err();
+        assert!(matches!(err, MemoryError::InvalidParameter(_)));
+        let batch = vec![
+            Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0),
+            Memory::new(vec![f32::INFINITY, 1.0], 0.0, 25.0, 1.0),
+        ];
+        assert!(store.try_add_memories(batch).is_err());
+        assert_eq!(store.memories.len(), 1);
+
+        // A non-finite vector added without validation scores zero.
+        let poisoned = store.add_memory(Memory::new(vec![f32::NAN, 1.0], 0.0, 25.0, 1.0));
+        let results = store.find_relevant(&[0.6, 0.8], 2).unwrap();
+        assert_eq!(results[0].memory.id, good);
+        assert_eq!(results[1].memory.id, poisoned);
+        assert!(results.iter().all(|r| r.score.is_finite()));
+        assert_eq!(results[1].similarity, 0.0);
+    }
+
+    #[test]
+    fn test_normalize_on_insert_lets_dot_product_rank_like_cosine() {
+        let mut store = MemoryStore::default();
+        store.set_normalize_on_insert(true);
+        let formed = Utc::now();
+        let vectors = [vec![10.0, 1.0], vec![0.3, 0.4], vec![-2.0, 5.0], vec![0.05, 0.0]];
+        let first = store.add_memory(Memory::new(vectors[0].clone(), 0.0, 25.0, 1.0));
+        store.add_memories(vectors[1..].iter().map(|v| Memory::new(v.clone(), 0.0, 25.0, 1.0)));
+        for memory in store.memories.values_mut() {
+            memory.timestamp = formed;
+        }
+        assert!(store.memories.values().all(|m| (simd::norm(&m.semantic_vector) - 1.0).abs() < 1e-6));
+        assert!((store.get_memory(&first).unwrap().semantic_vector[0] - 10.0 / 101f32.sqrt()).abs() < 1e-6);
+
+        let query = [0.7, 0.2];
+        let ranking = |metric| {
+            let mut twin = store.clone_filtered(|_| true);
+            twin.set_metric(metric);
+            twin.find_relevant(&query, vectors.len())
+                .unwrap()
+                .into_iter()
+                .map(|r| r.memory.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ranking(SimilarityMetric::DotProduct), ranking(SimilarityMetric::Cosine));
+    }
+
+    #[test]
+    fn test_state_at_returns_nearest_prior_snapshot() {
+        let mut store = MemoryStore::default();
+        let start = Utc::now();
+        let state = |fatigue: f32| AgentState {
+            fatigue,
+            ..AgentState::default()
+        };
+        store.update_agent_state_at(state(0.1), start);
+        assert!(store.state_history().is_empty());
+
+        store.set_state_history_limit(3);
+        for hour in 0..4 {
+            store.update_agent_state_at(state(hour as f32 / 10.0), start + Duration::hours(hour));
+        }
+        assert_eq!(store.state_history().len(), 3);
+        assert_eq!(store.agent_state(), &state(0.3));
+        assert_eq!(store.state_at(start + Duration::minutes(150)), Some(&state(0.2)));
+        assert_eq!(store.state_at(start + Duration::hours(1)), Some(&state(0.1)));
+        assert_eq!(store.state_at(start + Duration::days(1)), Some(&state(0.3)));
+        // The oldest entry was dropped to honour the limit.
+        assert_eq!(store.state_at(start + Duration::minutes(30)), None);
+
+        store.set_state_history_limit(1);
+        assert_eq!(store.state_at(start + Duration::minutes(150)), None);
+        assert_eq!(store.state_at(start + Duration::hours(3)), Some(&state(0.3)));
+    }
+
+    #[test]
+    fn test_sleep_records_state_history() {
+        let mut store = MemoryStore::default();
+        store.set_state_history_limit(4);
+        store.update_agent_state(AgentState {
+            sleep_debt: 2.0,
+            ..AgentState::default()
+        });
+
+        store.sleep(SLEEP_HOURS_PER_NIGHT).unwrap();
+        assert_eq!(store.state_history().len(), 2);
+        assert_eq!(store.agent_state().sleep_debt, 1.0);
+        assert_eq!(store.state_at(Utc::now()), Some(store.agent_state()));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_maintain_parallel_matches_serial_maintain() {
+        let mut serial = MemoryStore::default();
+        for i in 0..200 {
+            let mut memory = Memory::new(vec![i as f32, 1.0], 0.0, 25.0, 1.0);
+            memory.memory_strength = (i % 5) as f32 * 0.25;
+            serial.add_memory(memory);
+        }
+        let mut parallel = serial.clone_filtered(|_| true);
+        parallel.set_thread_count(Some(2)).unwrap();
+
+        // Keep the two strongest of the five strength levels.
+        let full = Memory::new(vec![1.0, 1.0], 0.0, 25.0, 1.0);
+        let threshold = 0.6 * full.calculate_retention(Utc::now(), serial.agent_state(), serial.agent_profile());
+        assert_eq!(parallel.maintain_parallel(threshold), serial.maintain(threshold));
+        let mut kept: Vec<_> = parallel.memories.keys().copied().collect();
+        let mut expected: Vec<_> = serial.memories.keys().copied().collect();
+        kept.sort();
+        expected.sort();
+        assert_eq!(kept, expected);
+        assert!(!kept.is_empty());
+        assert!(parallel.norm_cache.keys().all(|id| parallel.memories.contains_key(id)));
+    }
+
+    #[test]
+    fn test_summary_of_hand_built_store() {
+        let mut store = MemoryStore::default();
+        assert_eq!(store.summary(), StoreSummary::default());
+
+        let start = Utc::now() - Duration::days(10);
+        let mut memories = Vec::new();
+        for (i, strength) in [0.5, 1.0, 1.5].into_iter().enumerate() {
+            let mut memory = Memory::new(vec![1.0, i as f32], 0.0, 25.0, 1.0);
+            memory.timestamp = start + Duration::days(i as i64);
+            memory.memory_strength = strength;
+            memory.retrieval_count = 2 * i as u32;
+            memories.push(memory.clone());
+            store.add_memory(memory);
+        }
+
+        let at = start + Duration::days(20);
+        let retention: Vec<f32> = memories
+            .iter()
+            .map(|m| m.calculate_retention(at, store.agent_state(), store.agent_profile()))
+            .collect();
+        let summary = store.summary_at(at);
+        assert_eq!(summary.memory_count, 3);
+        assert!((summary.mean_retention - retention.iter().sum::<f32>() / 3.0).abs() < 1e-6);
+        assert_eq!(summary.min_retention, retention[0]);
+        assert_eq!(summary.max_retention, retention[2]);
+        assert!((summary.mean_retrieval_count - 2.0).abs() < 1e-6);
+        assert_eq!(summary.oldest, Some(start));
+        assert_eq!(summary.newest, Some(start + Duration::days(2)));
+        assert_eq!(summary.embedding_dim, Some(2));
+        // Summarizing records no retrieval.
+        assert!(memories.iter().all(|m| store.get_memory(&m.id).unwrap().retrieval_count == m.retrieval_count));
+    }
+
+    /// Exact index that records how the store drives it.
+    #[derive(Default)]
+    struct MockIndex {
+        vectors: Arc<std::sync::Mutex<HashMap<Uuid, Vec<f32>>>>,
+        searches: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl VectorIndex for MockIndex {
+        fn add_vector(&mut self, id: Uuid, vector: &[f32]) -> Result<()> {
+            self.vectors.lock().unwrap().insert(id, vector.to_vec());
+            Ok(())
+        }
+
+        fn remove_vector(&mut self, id: Uuid) -> Result<()> {
+            self.vectors.lock().unwrap().remove(&id);
+            Ok(())
+        }
+
+        fn search(&self, query: &[f32], k: usize) -> Result<Vec<(f32, Uuid)>> {
+            self.searches.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let mut hits: Vec<(f32, Uuid)> = self
+                .vectors
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(id, v)| (simd::dot(query, query) - 2.0 * simd::dot(query, v) + simd::dot(v, v), *id))
+                .collect();
+            hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+            hits.truncate(k);
+            Ok(hits)
+        }
+
+        fn rebuild(&mut self, entries: &[(Uuid, &[f32])]) -> Result<()> {
+            *self.vectors.lock().unwrap() = entries.iter().map(|(id, v)| (*id, v.to_vec())).collect();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_vector_index_selects_candidates() {
+        let mut store = MemoryStore::default();
+        let near = store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+        let far = store.add_memory(Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0));
+
+        let searches = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let index = MockIndex { searches: searches.clone(), ..MockIndex::default() };
+        store.set_vector_index(Some(Box::new(index))).unwrap();
+        assert!(store.has_vector_index());
+
+        let results = store.find_relevant(&[1.0, 0.1], 1).unwrap();
+        assert_eq!(results.iter().map(|r| r.memory.id).collect::<Vec<_>>(), vec![near]);
+        assert_eq!(searches.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Removed and archived memories leave the index; new ones join it.
+        store.remove_memory(&near).unwrap();
+        assert_eq!(store.find_relevant(&[1.0, 0.1], 5).unwrap()[0].memory.id, far);
+        store.archive_vector(&far).unwrap();
+        assert!(store.find_relevant(&[1.0, 0.1], 5).unwrap().is_empty());
+        let added = store.add_memory(Memory::new(vec![0.9, 0.1], 0.0, 25.0, 1.0));
+        assert_eq!(store.find_relevant(&[1.0, 0.1], 5).unwrap()[0].memory.id, added);
+
+        // With the index disabled, every memory is scored.
+        store.set_use_index(false);
+        assert_eq!(store.find_relevant(&[1.0, 0.1], 5).unwrap().len(), 2);
+        assert_eq!(searches.load(std::sync::atomic::Ordering::SeqCst), 4);
+
+        store.set_vector_index(None).unwrap();
+        assert!(!store.has_vector_index());
+    }
+
+    #[test]
+    fn test_indexed_candidates_are_rescored_by_the_metric() {
+        let mut store = MemoryStore::default();
+        store.set_metric(SimilarityMetric::DotProduct);
+        // By L2 distance from the query, `near` is closest and `long` is
+        // farthest, but `long` has the largest dot product.
+        let near = store.add_memory(Memory::new(vec![1.0, 1.0], 0.0, 25.0, 1.0));
+        store.add_memory(Memory::new(vec![2.0, 1.0], 0.0, 25.0, 1.0));
+        let long = store.add_memory(Memory::new(vec![4.0, 1.0], 0.0, 25.0, 1.0));
+        store.set_vector_index(Some(Box::new(MockIndex::default()))).unwrap();
+
+        let results = store.find_relevant(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].memory.id, long);
+
+        store.set_metric(SimilarityMetric::NegativeL2);
+        let results = store.find_relevant(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(results[0].memory.id, near);
+    }
+
+    #[test]
+    fn test_indexed_filters_apply_before_limit() {
+        let mut store = MemoryStore::default();
+        let mut ids = Vec::new();
+        for i in 0..8 {
+            let mut memory = Memory::new(vec![1.0, 0.2 * i as f32], 0.0, 25.0, 1.0);
+            memory.metadata = serde_json::json!({ "topic": if i == 7 { "home" } else { "work" } });
+            ids.push(store.add_memory(memory));
+        }
+        let searches = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let index = MockIndex { searches: searches.clone(), ..MockIndex::default() };
+        store.set_vector_index(Some(Box::new(index))).unwrap();
+
+        // The only match is the index's last candidate.
+        let results = store
+            .find_relevant_filtered(&[1.0, 0.0], 1, |m| m.metadata["topic"] == "home")
+            .unwrap();
+        assert_eq!(results.iter().map(|r| r.memory.id).collect::<Vec<_>>(), vec![ids[7]]);
+        assert!(searches.load(std::sync::atomic::Ordering::SeqCst) > 1);
+
+        // A similarity floor is applied before the limit too; the two
+        // memories above it are not the query's nearest neighbours.
+        let floor = SimilarityMetric::Cosine.similarity(&[0.0, 1.0], &[1.0, 1.2]);
+        let results = store.find_relevant_above(&[0.0, 1.0], 2, floor).unwrap();
+        let mut found: Vec<_> = results.iter().map(|r| r.memory.id).collect();
+        found.sort();
+        let mut expected = vec![ids[6], ids[7]];
+        expected.sort();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_retention_model_changes_retention_and_pruning() {
+        use crate::retention::ExponentialRetentionModel;
+
+        let mut memory = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+        memory.timestamp = Utc::now() - Duration::days(10);
+        let id = memory.id;
+        let (state, profile) = (AgentState::default(), AgentProfile::default());
+        let now = Utc::now();
+
+        let mut store = MemoryStore::default();
+        store.add_memory(memory.clone());
+        let default = store.retention_snapshot(now)[0].1;
+        assert_eq!(default, DefaultRetentionModel.retention(&memory, now, &state, &profile));
+
+        let exponential = ExponentialRetentionModel::new(1.0).unwrap();
+        let mut store = store.with_retention_model(exponential);
+        let swapped = store.retention_snapshot(now)[0].1;
+        assert_eq!(swapped, exponential.retention(&memory, now, &state, &profile));
+        assert!((swapped - (-10.0f32).exp()).abs() < 1e-6);
+        assert!(swapped < default);
+
+        // A threshold between the two prunes only under the exponential model.
+        let threshold = (swapped * default).sqrt();
+        assert_eq!(store.count_forgotten_at(threshold, now), 1);
+        store.set_retention_model(DefaultRetentionModel);
+        assert_eq!(store.maintain(threshold), 0);
+        store.set_retention_model(exponential);
+        assert_eq!(store.clone_filtered(|_| true).retention_snapshot(now)[0].1, swapped);
+        assert_eq!(store.maintain(threshold), 1);
+        assert!(store.get_memory(&id).is_none());
+        assert!(ExponentialRetentionModel::new(0.0).is_err());
+    }
+
+    #[test]
+    fn test_maintain_removes_pruned_memories_from_index() {
+        let mut store = MemoryStore::default();
+        let mut faded = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+        faded.memory_strength = 0.0;
+        store.add_memory(faded);
+        let kept = store.add_memory(Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0));
+        store.set_vector_index(Some(Box::new(MockIndex::default()))).unwrap();
+
+        assert_eq!(store.maintain(1e-6), 1);
+        // A stale entry for the pruned memory would be the only candidate.
+        let results = store.find_relevant(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(results.iter().map(|r| r.memory.id).collect::<Vec<_>>(), vec![kept]);
+    }
+
+    #[test]
+    fn test_consolidate_updates_search_index() {
+        let mut store = MemoryStore::default();
+        let mut older = Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0);
+        older.timestamp = Utc::now() - Duration::days(1);
+        let older_id = store.add_memory(older);
+        let newer_id = store.add_memory(Memory::new(vec![1.0, 0.01], 0.0, 25.0, 1.0));
+        let index = MockIndex::default();
+        let vectors = index.vectors.clone();
+        store.set_vector_index(Some(Box::new(index))).unwrap();
+
+        assert_eq!(store.consolidate(0.99), 1);
+        let vectors = vectors.lock().unwrap();
+        assert!(!vectors.contains_key(&newer_id));
+        assert_eq!(vectors[&older_id], store.get_memory(&older_id).unwrap().semantic_vector);
+        assert_ne!(vectors[&older_id], vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_copied_stores_index_every_memory() {
+        let mut store = MemoryStore::default();
+        for i in 0..3 {
+            store.add_memory(Memory::new(vec![1.0, 0.1 * i as f32], 0.0, 25.0, 1.0));
+        }
+
+        // An index built lazily from the first insert would hide the copies.
+        let mut copy = store.clone_filtered(|_| true);
+        copy.add_memory(Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0));
+        assert_eq!(copy.find_relevant(&[1.0, 0.0], 4).unwrap().len(), 4);
+
+        let mut mapped = store.map_into(Some);
+        mapped.add_memory(Memory::new(vec![0.0, 1.0], 0.0, 25.0, 1.0));
+        assert_eq!(mapped.find_relevant(&[1.0, 0.0], 4).unwrap().len(), 4);
+    }
 }
@@ -0,0 +1,111 @@
+//! Pluggable nearest-neighbour indexes for candidate selection.
+//!
+//! A [`MemoryStore`](crate::store::MemoryStore) keeps an optional
+//! [`VectorIndex`] in sync with its memories and, when one is set, asks it
+//! for the candidates a query re-scores instead of scanning every memory.
+//! The `faiss` and `hnsw` features provide implementations; any other
+//! backend can be plugged in with
+//! [`set_vector_index`](crate::store::MemoryStore::set_vector_index).
+
+use crate::error::Result;
+use uuid::Uuid;
+
+/// An approximate or exact nearest-neighbour index over memory vectors.
+///
+/// Implementations rank by Euclidean distance; the store re-scores the
+/// returned candidates with its own [`SimilarityMetric`](crate::similarity::SimilarityMetric).
+///
+/// # Example
+///
+/// ```
+/// use memory_module::prelude::*;
+/// use memory_module::uuid::Uuid;
+/// use std::collections::HashMap;
+///
+/// /// Exact index that scans every vector.
+/// #[derive(Default)]
+/// struct LinearIndex(HashMap<Uuid, Vec<f32>>);
+///
+/// impl VectorIndex for LinearIndex {
+///     fn add_vector(&mut self, id: Uuid, vector: &[f32]) -> Result<()> {
+///         self.0.insert(id, vector.to_vec());
+///         Ok(())
+///     }
+///
+///     fn remove_vector(&mut self, id: Uuid) -> Result<()> {
+///         self.0.remove(&id);
+///         Ok(())
+///     }
+///
+///     fn search(&self, query: &[f32], k: usize) -> Result<Vec<(f32, Uuid)>> {
+///         let mut hits: Vec<(f32, Uuid)> = self
+///             .0
+///             .iter()
+///             .map(|(id, v)| (v.iter().zip(query).map(|(a, b)| (a - b) * (a - b)).sum(), *id))
+///             .collect();
+///         hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+///         hits.truncate(k);
+///         Ok(hits)
+///     }
+///
+///     fn rebuild(&mut self, entries: &[(Uuid, &[f32])]) -> Result<()> {
+///         self.0 = entries.iter().map(|(id, v)| (*id, v.to_vec())).collect();
+///         Ok(())
+///     }
+/// }
+///
+/// let mut store = MemoryStore::default();
+/// let id = store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+/// store.set_vector_index(Some(Box::new(LinearIndex::default()))).unwrap();
+/// assert_eq!(store.find_relevant(&[1.0, 0.0], 1).unwrap()[0].memory.id, id);
+/// ```
+pub trait VectorIndex: Send + Sync {
+    /// Adds a vector for the memory `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index cannot store the vector, e.g. because
+    /// its dimension does not match the index.
+    fn add_vector(&mut self, id: Uuid, vector: &[f32]) -> Result<()>;
+
+    /// Adds several vectors.
+    ///
+    /// The default implementation calls
+    /// [`add_vector`](VectorIndex::add_vector) for each entry; backends with
+    /// a cheaper bulk insert should override it.
+    ///
+    /// # Errors
+    ///
+    /// As for [`add_vector`](VectorIndex::add_vector).
+    fn add_vectors(&mut self, entries: &[(Uuid, &[f32])]) -> Result<()> {
+        for (id, vector) in entries {
+            self.add_vector(*id, vector)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the vector of the memory `id`, if present.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend fails to remove the vector.
+    fn remove_vector(&mut self, id: Uuid) -> Result<()>;
+
+    /// Finds up to `k` vectors near `query`, returning
+    /// `(distance, id)` pairs closest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend fails to search.
+    fn search(&self, query: &[f32], k: usize) -> Result<Vec<(f32, Uuid)>>;
+
+    /// Replaces the index's contents with `entries`.
+    ///
+    /// The index takes its dimension from the entries, so a store whose
+    /// vectors changed dimension can reuse it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index cannot be rebuilt.
+    fn rebuild(&mut self, entries: &[(Uuid, &[f32])]) -> Result<()>;
+}
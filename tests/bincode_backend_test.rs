@@ -0,0 +1,58 @@
+#![cfg(feature = "bincode")]
+
+use memory_module::prelude::*;
+use memory_module::storage::{BincodeBackend, FileBackend};
+use std::fs;
+
+#[test]
+fn test_bincode_backend_roundtrip() {
+    let profile = AgentProfile::default();
+    let state = AgentState::default();
+    let mut store = MemoryStore::new(profile, state);
+    let mut memory = Memory::new(vec![0.1, 0.2], 0.0, 0.0, 1.0);
+    memory.metadata = serde_json::json!({ "source": "conversation", "turn": 3 });
+    let id = memory.id;
+    store.add_memory(memory.clone());
+
+    let path = std::env::temp_dir().join(format!("mm_test_{}.bin", uuid::Uuid::new_v4()));
+    let backend = BincodeBackend::new(&path);
+
+    store.save(&backend).expect("save");
+
+    let loaded = MemoryStore::load(&backend).expect("load");
+    assert_eq!(loaded.get_memory(&id), Some(&memory));
+
+    fs::remove_file(&path).expect("cleanup");
+}
+
+#[test]
+fn test_bincode_backend_is_smaller_than_json() {
+    let mut store = MemoryStore::default();
+    for i in 0..50 {
+        store.add_memory(Memory::new(vec![i as f32 * 0.01; 64], 0.0, 25.0, 1.0));
+    }
+
+    let dir = std::env::temp_dir();
+    let bin_path = dir.join(format!("mm_test_{}.bin", uuid::Uuid::new_v4()));
+    let json_path = dir.join(format!("mm_test_{}.json", uuid::Uuid::new_v4()));
+    store.save(&BincodeBackend::new(&bin_path)).expect("save bincode");
+    store.save(&FileBackend::new(&json_path)).expect("save json");
+
+    let bin_len = fs::metadata(&bin_path).unwrap().len();
+    let json_len = fs::metadata(&json_path).unwrap().len();
+    assert!(bin_len < json_len, "bincode {} >= json {}", bin_len, json_len);
+
+    fs::remove_file(&bin_path).expect("cleanup");
+    fs::remove_file(&json_path).expect("cleanup");
+}
+
+#[test]
+fn test_bincode_backend_reports_corrupt_data() {
+    let path = std::env::temp_dir().join(format!("mm_test_{}.bin", uuid::Uuid::new_v4()));
+    fs::write(&path, [0xff; 4]).unwrap();
+
+    let err = MemoryStore::load(&BincodeBackend::new(&path)).err().expect("corrupt data");
+    assert!(matches!(err, MemoryError::Serialization(_)));
+
+    fs::remove_file(&path).expect("cleanup");
+}
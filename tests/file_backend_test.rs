@@ -1,3 +1,5 @@
+#![cfg(feature = "serde")]
+
 use memory_module::prelude::*;
 use memory_module::storage::FileBackend;
 use std::fs;
@@ -22,3 +24,134 @@ fn test_file_backend_roundtrip() {
 
     fs::remove_file(&path).expect("cleanup");
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_file_backend_save_streaming_roundtrip() {
+    let mut store = MemoryStore::default();
+    let memories: Vec<Memory> = (0..5)
+        .map(|i| Memory::new(vec![i as f32, 0.5], 0.1 * i as f32, 25.0, 1.0))
+        .collect();
+    for memory in &memories {
+        store.add_memory(memory.clone());
+    }
+
+    let path = std::env::temp_dir().join(format!("mm_test_{}.json", uuid::Uuid::new_v4()));
+    let backend = FileBackend::new(&path);
+    backend
+        .save_streaming(memories.iter(), store.agent_profile(), store.agent_state())
+        .expect("save");
+
+    let data = backend.load().expect("load");
+    assert_eq!(data.memories.len(), memories.len());
+    for memory in &memories {
+        assert_eq!(data.memories.get(&memory.id), Some(memory));
+    }
+
+    // MemoryStore::save goes through the same streaming path.
+    let streamed = fs::read_to_string(&path).expect("read");
+    store.save(&backend).expect("store save");
+    let loaded = MemoryStore::load(&backend).expect("store load");
+    for memory in &memories {
+        assert_eq!(loaded.get_memory(&memory.id), Some(memory));
+    }
+    assert_eq!(fs::read_to_string(&path).expect("read").len(), streamed.len());
+
+    fs::remove_file(&path).expect("cleanup");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_file_backend_loads_v1_file() {
+    let memory = Memory::new(vec![0.1, 0.2], 0.0, 0.0, 1.0);
+    let id = memory.id;
+    // Version 1 files were written without a `version` field.
+    let v1 = serde_json::json!({
+        "memories": { id.to_string(): memory },
+        "agent_profile": AgentProfile::default(),
+        "agent_state": AgentState::default(),
+    });
+
+    let path = std::env::temp_dir().join(format!("mm_test_{}.json", uuid::Uuid::new_v4()));
+    fs::write(&path, v1.to_string()).expect("write v1");
+    let backend = FileBackend::new(&path);
+
+    let data = backend.load().expect("load v1");
+    assert_eq!(data.version, memory_module::store::DATA_FORMAT_VERSION);
+    assert!(data.memories.contains_key(&id));
+    let loaded = MemoryStore::load(&backend).expect("load store");
+    assert!(loaded.get_memory(&id).is_some());
+
+    fs::remove_file(&path).expect("cleanup");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_file_backend_roundtrips_agent_profile_and_state() {
+    let profile = AgentProfile {
+        k: 0.7,
+        beta_sleep: 0.25,
+        use_capacity_in_retention: false,
+        ..AgentProfile::default()
+    };
+    let state = AgentState {
+        current_age: 41.5,
+        sleep_debt: 0.3,
+        cortisol_level: 0.6,
+        fatigue: 0.2,
+        training_factor: 0.1,
+    };
+    let store = MemoryStore::new(profile.clone(), state.clone());
+
+    let path = std::env::temp_dir().join(format!("mm_test_{}.json", uuid::Uuid::new_v4()));
+    let backend = FileBackend::new(&path);
+    store.save(&backend).expect("save");
+    let loaded = MemoryStore::load(&backend).expect("load");
+    assert_eq!(loaded.agent_profile(), &profile);
+    assert_eq!(loaded.agent_state(), &state);
+
+    // Older files lack fields added later; those fall back to defaults.
+    let old = serde_json::json!({
+        "memories": {},
+        "agent_profile": { "k": 0.7, "a_mid": 22.0 },
+        "agent_state": { "current_age": 41.5, "sleep_debt": 0.3, "cortisol_level": 0.6, "fatigue": 0.2 },
+    });
+    fs::write(&path, old.to_string()).expect("write old file");
+    let loaded = MemoryStore::load(&backend).expect("load old file");
+    assert_eq!(loaded.agent_state().training_factor, 0.0);
+    assert_eq!(loaded.agent_state().current_age, 41.5);
+    assert_eq!(loaded.agent_profile().k, 0.7);
+    assert_eq!(loaded.agent_profile().beta_fatigue, AgentProfile::default().beta_fatigue);
+
+    fs::remove_file(&path).expect("cleanup");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_persistent_store_flushes_to_backend() {
+    let path = std::env::temp_dir().join(format!("mm_test_{}.json", uuid::Uuid::new_v4()));
+    let mut store = PersistentMemoryStore::open(FileBackend::new(&path))
+        .expect("open")
+        .with_flush_interval(None);
+    let memory = Memory::new(vec![0.1, 0.2], 0.4, 25.0, 1.0);
+    let id = store.add_memory(memory.clone()).expect("add");
+    store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0)).expect("add");
+    assert!(store.is_dirty());
+    assert!(!path.exists());
+
+    store.flush().expect("flush");
+    assert!(!store.is_dirty());
+    let reopened = MemoryStore::load(&FileBackend::new(&path)).expect("load");
+    assert_eq!(reopened.get_memory(&id), Some(&memory));
+    assert_eq!(reopened.snapshot_hash(), store.snapshot_hash());
+
+    // Write-through stores save on every mutation.
+    drop(store);
+    let mut store = PersistentMemoryStore::open(FileBackend::new(&path)).expect("reopen");
+    store.remove_memory(&id).expect("remove");
+    assert!(!store.is_dirty());
+    assert!(MemoryStore::load(&FileBackend::new(&path)).expect("load").get_memory(&id).is_none());
+
+    drop(store);
+    fs::remove_file(&path).expect("cleanup");
+}
@@ -37,7 +37,7 @@ fn test_memory_lifecycle() {
     // Search for the memory
     let results = store.find_relevant(&[0.1, 0.2, 0.3], 1).unwrap();
     assert_eq!(results.len(), 1);
-    assert_eq!(results[0].1.id, memory_id);
+    assert_eq!(results[0].memory.id, memory_id);
     
     // Verify retrieval updated the memory
     let memory = store.get_memory(&memory_id).unwrap();
@@ -128,7 +128,7 @@ fn test_save_load_roundtrip() {
     store.add_memory(mem);
 
     let temp_file = tempfile::NamedTempFile::new().unwrap();
-    store.save(temp_file.path()).unwrap();
+    Save::save(&store, temp_file.path()).unwrap();
     let loaded: MemoryStore = Load::load(temp_file.path()).unwrap();
     // Temporary file is automatically deleted when it goes out of scope.
     assert!(loaded.get_memory(&id).is_some());
@@ -174,6 +174,317 @@ fn test_sharded_store_basic() {
     assert_eq!(retrieved.id, id);
 }
 
+#[cfg(feature = "concurrent")]
+#[test]
+fn test_sharded_store_merges_per_shard_rankings() {
+    let formed = Utc::now();
+    let mut store = MemoryStore::default();
+    let sharded = ShardedMemoryStore::new(AgentProfile::default(), AgentState::default(), 5);
+    let mut all_ids = Vec::new();
+    for i in 0..200 {
+        let angle = i as f32 * 0.031;
+        let mut memory = Memory::new(vec![angle.cos(), angle.sin(), 0.2], 0.0, 25.0, 1.0);
+        memory.timestamp = formed;
+        store.add_memory(memory.clone());
+        all_ids.push(sharded.add_memory(memory));
+    }
+
+    let query = [0.3, 0.9, 0.2];
+    let expected: Vec<_> = store.find_relevant(&query, 10).unwrap().into_iter().map(|r| r.memory.id).collect();
+    let results = sharded.find_relevant(&query, 10).unwrap();
+    let ids: Vec<_> = results.iter().map(|r| r.memory.id).collect();
+    assert_eq!(ids, expected);
+    let retrieved = all_ids
+        .iter()
+        .filter(|id| sharded.get_memory(id).unwrap().retrieval_count > 0)
+        .count();
+    assert_eq!(retrieved, ids.len());
+    assert!(ids.iter().all(|id| sharded.get_memory(id).unwrap().retrieval_count == 1));
+}
+
+#[test]
+fn test_find_relevant_on_empty_store_returns_no_results() {
+    let mut store = MemoryStore::default();
+    assert!(store.find_relevant(&[1.0, 0.0], 5).unwrap().is_empty());
+    assert!(store.find_relevant_above(&[1.0, 0.0], 5, 0.0).unwrap().is_empty());
+    assert!(store
+        .find_relevant_normalized(&[1.0, 0.0], 5, ScoreNormalization::Softmax(1.0))
+        .unwrap()
+        .is_empty());
+
+    #[cfg(feature = "concurrent")]
+    {
+        let concurrent = ConcurrentMemoryStore::new(AgentProfile::default(), AgentState::default());
+        assert!(concurrent.find_relevant(&[1.0, 0.0], 5).unwrap().is_empty());
+        let sharded = ShardedMemoryStore::new(AgentProfile::default(), AgentState::default(), 4);
+        assert!(sharded.find_relevant(&[1.0, 0.0], 5).unwrap().is_empty());
+    }
+}
+
+#[test]
+fn test_find_relevant_with_zero_limit_returns_nothing() {
+    let mut store = MemoryStore::default();
+    let id = store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+    assert!(store.find_relevant(&[1.0, 0.0], 0).unwrap().is_empty());
+    assert!(store.find_relevant_above(&[1.0, 0.0], 0, 0.0).unwrap().is_empty());
+    assert_eq!(store.get_memory(&id).unwrap().retrieval_count, 0);
+
+    #[cfg(feature = "concurrent")]
+    {
+        let concurrent = ConcurrentMemoryStore::new(AgentProfile::default(), AgentState::default());
+        let id = concurrent.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+        assert!(concurrent.find_relevant(&[1.0, 0.0], 0).unwrap().is_empty());
+        assert_eq!(concurrent.get_memory(&id).unwrap().retrieval_count, 0);
+
+        let sharded = ShardedMemoryStore::new(AgentProfile::default(), AgentState::default(), 4);
+        let id = sharded.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+        assert!(sharded.find_relevant(&[1.0, 0.0], 0).unwrap().is_empty());
+        assert_eq!(sharded.get_memory(&id).unwrap().retrieval_count, 0);
+    }
+}
+
+#[cfg(feature = "concurrent")]
+#[test]
+fn test_concurrent_stores_update_agent_state_while_querying() {
+    let state = |fatigue: f32| AgentState {
+        current_age: 25.0,
+        sleep_debt: 0.0,
+        cortisol_level: 0.0,
+        fatigue,
+        training_factor: 0.0,
+    };
+
+    let concurrent = ConcurrentMemoryStore::new(AgentProfile::default(), state(0.0));
+    let sharded = ShardedMemoryStore::new(AgentProfile::default(), state(0.0), 4);
+    for i in 0..20 {
+        let vector = vec![i as f32, 1.0, 0.5];
+        concurrent.add_memory(Memory::new(vector.clone(), 0.0, 25.0, 1.0));
+        sharded.add_memory(Memory::new(vector, 0.0, 25.0, 1.0));
+    }
+
+    std::thread::scope(|scope| {
+        for t in 0..4 {
+            let concurrent = &concurrent;
+            let sharded = &sharded;
+            scope.spawn(move || {
+                for i in 0..50 {
+                    let fatigue = ((t * 50 + i) % 10) as f32 / 10.0;
+                    concurrent.update_agent_state(state(fatigue));
+                    sharded.update_agent_state(state(fatigue));
+                }
+            });
+            scope.spawn(move || {
+                for _ in 0..50 {
+                    assert_eq!(concurrent.find_relevant(&[1.0, 1.0, 0.5], 3).unwrap().len(), 3);
+                    assert_eq!(sharded.find_relevant(&[1.0, 1.0, 0.5], 3).unwrap().len(), 3);
+                }
+            });
+        }
+    });
+
+    concurrent.update_agent_state(state(0.7));
+    sharded.update_agent_state(state(0.7));
+    assert_eq!(concurrent.agent_state().fatigue, 0.7);
+    assert_eq!(sharded.agent_state().fatigue, 0.7);
+}
+
+#[cfg(feature = "concurrent")]
+#[test]
+fn test_concurrent_store_omits_memories_removed_mid_query() {
+    let store = ConcurrentMemoryStore::new(AgentProfile::default(), AgentState::default());
+    let query = [1.0, 0.0, 0.0];
+    let top = Memory::new(query.to_vec(), 0.0, 25.0, 1.0);
+    for i in 1..20 {
+        store.add_memory(Memory::new(vec![1.0, i as f32, 0.0], 0.0, 25.0, 1.0));
+    }
+    store.add_memory(top.clone());
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for _ in 0..200 {
+                store.remove_memory(&top.id).unwrap();
+                store.add_memory(top.clone());
+            }
+        });
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    let results = store.find_relevant(&query, 3).unwrap();
+                    // Removed after ranking: omitted, not replaced.
+                    assert!(results.len() == 2 || results.len() == 3);
+                    assert!(results.iter().all(|r| r.retrieval_count > 0));
+                    assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+                }
+            });
+        }
+    });
+    assert!(store.get_memory(&top.id).is_some());
+    assert_eq!(store.find_relevant(&query, 3).unwrap().len(), 3);
+}
+
+#[cfg(feature = "concurrent")]
+#[test]
+fn test_all_stores_share_similarity_scoring() {
+    let profile = AgentProfile::default();
+    let state = AgentState {
+        current_age: 25.0,
+        sleep_debt: 0.0,
+        cortisol_level: 0.0,
+        fatigue: 0.0,
+        training_factor: 0.0,
+    };
+
+    let mut store = MemoryStore::new(profile.clone(), state.clone());
+    let concurrent = ConcurrentMemoryStore::new(profile.clone(), state.clone());
+    let sharded = ShardedMemoryStore::new(profile, state, 3);
+    for vector in [vec![1.0, 0.0, 0.0], vec![0.5, 0.5, 0.0], vec![0.0, 0.2, 1.0]] {
+        let memory = Memory::new(vector, 0.0, 25.0, 1.0);
+        store.add_memory(memory.clone());
+        concurrent.add_memory(memory.clone());
+        sharded.add_memory(memory);
+    }
+
+    let query = [0.9, 0.1, 0.0];
+    let expected = store.find_relevant(&query, 3).unwrap();
+    for results in [
+        concurrent.find_relevant(&query, 3).unwrap(),
+        sharded.find_relevant(&query, 3).unwrap(),
+    ] {
+        assert_eq!(results.len(), expected.len());
+        for (result, expected) in results.iter().zip(&expected) {
+            assert_eq!(result.memory.id, expected.memory.id);
+            assert!((result.score - expected.score).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn test_maintain_collect_returns_exactly_the_pruned_memories() {
+    let memories: Vec<Memory> = (0..6)
+        .map(|i| {
+            let mut memory = Memory::new(vec![i as f32, 1.0], 0.0, 25.0, 1.0);
+            if i % 2 == 0 {
+                memory.memory_strength = 0.0;
+            }
+            memory
+        })
+        .collect();
+    let mut faded: Vec<Memory> = memories.iter().filter(|m| m.memory_strength == 0.0).cloned().collect();
+    faded.sort_by_key(|m| m.id);
+    let threshold = 1e-6;
+
+    let check = |mut pruned: Vec<Memory>, remaining: Vec<Option<Memory>>| {
+        pruned.sort_by_key(|m| m.id);
+        assert_eq!(pruned, faded);
+        for (memory, remaining) in memories.iter().zip(remaining) {
+            assert_eq!(remaining.is_some(), memory.memory_strength > 0.0);
+        }
+    };
+
+    let mut store = MemoryStore::default();
+    store.add_memories(memories.clone());
+    let pruned = store.maintain_collect(threshold);
+    check(pruned, memories.iter().map(|m| store.get_memory(&m.id).cloned()).collect());
+    assert_eq!(store.maintain(threshold), 0);
+
+    #[cfg(feature = "concurrent")]
+    {
+        let concurrent = ConcurrentMemoryStore::new(AgentProfile::default(), AgentState::default());
+        let sharded = ShardedMemoryStore::new(AgentProfile::default(), AgentState::default(), 3);
+        for memory in &memories {
+            concurrent.add_memory(memory.clone());
+            sharded.add_memory(memory.clone());
+        }
+        let pruned = concurrent.maintain_collect(threshold);
+        check(pruned, memories.iter().map(|m| concurrent.get_memory(&m.id)).collect());
+        let pruned = sharded.maintain_collect(threshold);
+        check(pruned, memories.iter().map(|m| sharded.get_memory(&m.id)).collect());
+    }
+}
+
+#[cfg(feature = "concurrent")]
+#[test]
+fn test_observer_counts_are_exact_across_threads() {
+    use memory_module::observer::StoreObserver;
+    use memory_module::uuid::Uuid;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct Counter {
+        adds: AtomicUsize,
+        retrievals: AtomicUsize,
+        queries: AtomicUsize,
+    }
+
+    impl StoreObserver for Counter {
+        fn on_add(&self, _memory: &Memory) {
+            self.adds.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_retrieve(&self, _id: &Uuid, _score: f32) {
+            self.retrievals.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_query(&self, _results: usize, _elapsed: std::time::Duration) {
+            self.queries.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    const THREADS: usize = 8;
+    const PER_THREAD: usize = 50;
+
+    let counter = Arc::new(Counter::default());
+    let store = ConcurrentMemoryStore::new(AgentProfile::default(), AgentState::default());
+    store.add_observer(counter.clone());
+
+    std::thread::scope(|scope| {
+        for t in 0..THREADS {
+            let store = &store;
+            scope.spawn(move || {
+                for i in 0..PER_THREAD {
+                    store.add_memory(Memory::new(vec![(t * PER_THREAD + i) as f32, 1.0], 0.0, 25.0, 1.0));
+                    store.find_relevant(&[1.0, 1.0], 1).unwrap();
+                }
+            });
+        }
+    });
+
+    let operations = THREADS * PER_THREAD;
+    assert_eq!(counter.adds.load(Ordering::Relaxed), operations);
+    assert_eq!(counter.retrievals.load(Ordering::Relaxed), operations);
+    assert_eq!(counter.queries.load(Ordering::Relaxed), operations);
+}
+
+#[cfg(feature = "concurrent")]
+#[test]
+fn test_concurrent_store_notifies_add_after_inserting() {
+    use memory_module::observer::StoreObserver;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, OnceLock, Weak};
+
+    #[derive(Default)]
+    struct ReadBack {
+        store: OnceLock<Weak<ConcurrentMemoryStore>>,
+        found: AtomicBool,
+    }
+
+    impl StoreObserver for ReadBack {
+        fn on_add(&self, memory: &Memory) {
+            let store = self.store.get().and_then(Weak::upgrade).expect("store");
+            self.found.store(store.get_memory(&memory.id).is_some(), Ordering::Relaxed);
+        }
+    }
+
+    let store = Arc::new(ConcurrentMemoryStore::new(AgentProfile::default(), AgentState::default()));
+    let observer = Arc::new(ReadBack::default());
+    observer.store.set(Arc::downgrade(&store)).unwrap();
+    store.add_observer(observer.clone());
+
+    store.add_memory(Memory::new(vec![1.0, 0.0], 0.0, 25.0, 1.0));
+    assert!(observer.found.load(Ordering::Relaxed));
+}
+
 #[cfg(feature = "faiss")]
 #[test]
 fn test_faiss_integration_basic() {
@@ -191,5 +502,127 @@ fn test_faiss_integration_basic() {
 
     let results = store.find_relevant(&[0.1, 0.2], 1).unwrap();
     assert_eq!(results.len(), 1);
-    assert!(results[0].1.id == id1 || results[0].1.id == id2);
+    assert!(results[0].memory.id == id1 || results[0].memory.id == id2);
+}
+
+#[cfg(feature = "faiss")]
+#[test]
+fn test_faiss_search_skips_removed_memories() {
+    use memory_module::faiss_index::FaissIndex;
+
+    let mut index = FaissIndex::new(2).unwrap();
+    let kept = uuid::Uuid::new_v4();
+    let removed = uuid::Uuid::new_v4();
+    index.add_vectors(&[(kept, &[0.9, 0.8][..]), (removed, &[0.1, 0.2][..])]).unwrap();
+    index.remove_vector(removed).unwrap();
+    // Removing an unknown id is a no-op.
+    index.remove_vector(removed).unwrap();
+
+    let results = index.search(&[0.1, 0.2], 2).unwrap();
+    assert_eq!(results.iter().map(|(_, id)| *id).collect::<Vec<_>>(), vec![kept]);
+
+    let mut store = MemoryStore::default();
+    let first = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    let second = store.add_memory(Memory::new(vec![0.9, 0.8], 0.0, 25.0, 1.0));
+    store.remove_memory(&first).unwrap();
+    let results = store.find_relevant(&[0.1, 0.2], 2).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].memory.id, second);
+}
+
+#[cfg(all(any(feature = "faiss", feature = "hnsw"), feature = "serde"))]
+#[test]
+fn test_search_index_is_rebuilt_after_load() {
+    use memory_module::storage::FileBackend;
+
+    let mut store = MemoryStore::default();
+    let near = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    store.add_memory(Memory::new(vec![0.9, -0.8], 0.0, 25.0, 1.0));
+    let path = std::env::temp_dir().join(format!("mm_test_{}.json", uuid::Uuid::new_v4()));
+    let backend = FileBackend::new(&path);
+    store.save(&backend).unwrap();
+
+    let mut loaded = MemoryStore::load(&backend).unwrap();
+    assert!(loaded.has_vector_index());
+    let indexed = loaded.find_relevant(&[0.1, 0.2], 1).unwrap();
+    loaded.set_use_index(false);
+    let brute = loaded.find_relevant(&[0.1, 0.2], 1).unwrap();
+    assert_eq!(indexed[0].memory.id, near);
+    assert_eq!(brute[0].memory.id, near);
+    loaded.rebuild_index().unwrap();
+
+    // Deserializing directly rebuilds the index too.
+    let mut decoded: MemoryStore = serde_json::from_str(&serde_json::to_string(&store).unwrap()).unwrap();
+    assert!(decoded.has_vector_index());
+    assert_eq!(decoded.find_relevant(&[0.1, 0.2], 1).unwrap()[0].memory.id, near);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_jsonl_round_trip() {
+    let mut store = MemoryStore::default();
+    let memories: Vec<Memory> = (0..3)
+        .map(|i| Memory::new(vec![i as f32, 0.5], 0.1 * i as f32, 25.0, 1.0))
+        .collect();
+    for memory in &memories {
+        store.add_memory(memory.clone());
+    }
+
+    let mut out = Vec::new();
+    store.export_jsonl(&mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(text.lines().count(), 3);
+
+    let mut imported = MemoryStore::default();
+    assert_eq!(imported.import_jsonl(format!("{}\n", text).as_bytes()).unwrap(), 3);
+    for memory in &memories {
+        assert_eq!(imported.get_memory(&memory.id), Some(memory));
+    }
+
+    let corrupt = format!("{}\nnot json\n", text.lines().next().unwrap());
+    let err = imported.import_jsonl(corrupt.as_bytes()).unwrap_err();
+    assert!(matches!(err, MemoryError::Serialization(ref msg) if msg.starts_with("line 2")));
+}
+
+#[cfg(all(feature = "hnsw", feature = "test-util"))]
+#[test]
+fn test_hnsw_index_overlaps_exact_scan() {
+    use memory_module::test_util::random_memory;
+    use std::collections::HashSet;
+
+    // Identical memories apart from their vectors, so ranking follows L2.
+    let formed = Utc::now();
+    let mut indexed = MemoryStore::default();
+    indexed.set_metric(SimilarityMetric::NegativeL2);
+    for seed in 0..1000 {
+        let mut memory = Memory::new(random_memory(16, seed).semantic_vector, 0.0, 25.0, 1.0);
+        memory.timestamp = formed;
+        indexed.add_memory(memory);
+    }
+    let mut exact = indexed.clone_filtered(|_| true);
+    exact.set_use_index(false);
+
+    let k = 10;
+    let mut hits = 0;
+    for seed in 0..10 {
+        let query = random_memory(16, 10_000 + seed).semantic_vector;
+        let expected: HashSet<_> = exact.find_relevant_ids(&query, k).unwrap().into_iter().map(|(_, id)| id).collect();
+        let found = indexed.find_relevant_ids(&query, k).unwrap();
+        hits += found.iter().filter(|(_, id)| expected.contains(id)).count();
+    }
+    assert!(hits as f32 / (10 * k) as f32 >= 0.8, "only {} of {} exact results found", hits, 10 * k);
+}
+
+#[test]
+fn test_stored_data_clones_and_compares() {
+    let memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    let mut data = StoredData::default();
+    data.memories.insert(memory.id, memory);
+
+    let copy = data.clone();
+    assert_eq!(copy, data);
+    data.agent_state.fatigue = 0.5;
+    assert_ne!(copy, data);
 }
@@ -0,0 +1,29 @@
+use memory_module::prelude::*;
+
+#[test]
+fn test_memory_backend_roundtrip() {
+    let backend = MemoryBackend::new();
+    assert!(backend.data().is_none());
+    assert_eq!(MemoryStore::load(&backend).unwrap().summary().memory_count, 0);
+
+    let state = AgentState::new(30.0, 0.2, 0.1, 0.3, 0.0);
+    let mut store = MemoryStore::new(AgentProfile::default(), state.clone());
+    let ids: Vec<_> = (0..3)
+        .map(|i| store.add_memory(Memory::new(vec![i as f32, 1.0], 0.0, 25.0, 1.0)))
+        .collect();
+    store.save(&backend.clone()).unwrap();
+
+    let data = backend.data().unwrap();
+    assert_eq!(data.memories.len(), 3);
+    for id in &ids {
+        assert_eq!(data.memories.get(id), store.get_memory(id));
+    }
+    assert_eq!(&data.agent_profile, store.agent_profile());
+    assert_eq!(data.agent_state, state);
+
+    let loaded = MemoryStore::load(&backend).unwrap();
+    assert!(ids.iter().all(|id| loaded.get_memory(id).is_some()));
+
+    backend.clear();
+    assert!(backend.data().is_none());
+}
@@ -0,0 +1,144 @@
+#![cfg(all(feature = "serde", feature = "sqlite"))]
+
+use memory_module::prelude::*;
+use std::fs;
+
+fn temp_db() -> (std::path::PathBuf, String) {
+    let path = std::env::temp_dir().join(format!("mm_test_{}.db", uuid::Uuid::new_v4()));
+    let url = format!("sqlite://{}?mode=rwc", path.display());
+    (path, url)
+}
+
+#[test]
+fn test_sqlite_backend_stores_agents_separately() {
+    let (path, url) = temp_db();
+    let alice = SqliteBackend::new(&url).with_agent_id("alice");
+    let bob = SqliteBackend::new(&url).with_agent_id("bob");
+
+    let mut alice_store = MemoryStore::default();
+    let alice_memory = alice_store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    alice_store.save(&alice).expect("save alice");
+    let mut bob_store = MemoryStore::default();
+    let bob_memory = bob_store.add_memory(Memory::new(vec![0.3, 0.4], 0.0, 25.0, 1.0));
+    bob_store.save(&bob).expect("save bob");
+
+    let loaded = MemoryStore::load(&alice).expect("load alice");
+    assert!(loaded.get_memory(&alice_memory).is_some());
+    assert!(loaded.get_memory(&bob_memory).is_none());
+    assert_eq!(alice.list_agents().unwrap(), vec!["alice", "bob"]);
+
+    alice.delete_agent("bob").expect("delete bob");
+    assert_eq!(alice.list_agents().unwrap(), vec!["alice"]);
+    assert!(alice.delete_agent("bob").unwrap_err().is_not_found());
+    assert!(MemoryStore::load(&bob).expect("load bob").get_memory(&bob_memory).is_none());
+
+    fs::remove_file(&path).expect("cleanup");
+}
+
+#[test]
+fn test_sqlite_backend_defaults_to_default_agent() {
+    let (path, url) = temp_db();
+    let backend = SqliteBackend::new(&url);
+    assert_eq!(backend.agent_id(), SqliteBackend::DEFAULT_AGENT_ID);
+
+    let mut store = MemoryStore::default();
+    let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+    store.save(&backend).expect("save");
+
+    let explicit = SqliteBackend::new(&url).with_agent_id("default");
+    assert!(MemoryStore::load(&explicit).expect("load").get_memory(&id).is_some());
+
+    fs::remove_file(&path).expect("cleanup");
+}
+
+#[test]
+fn test_sqlite_backend_reads_legacy_single_agent_table() {
+    let (path, url) = temp_db();
+    let memory = Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0);
+    let id = memory.id;
+    let legacy = serde_json::json!({
+        "memories": { id.to_string(): memory },
+        "agent_profile": AgentProfile::default(),
+        "agent_state": AgentState::default(),
+    });
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().connect(&url).await.unwrap();
+        sqlx::query("CREATE TABLE memory_store (id INTEGER PRIMARY KEY, data TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO memory_store (id, data) VALUES (1, ?1)")
+            .bind(legacy.to_string())
+            .execute(&pool)
+            .await
+            .unwrap();
+    });
+
+    let loaded = MemoryStore::load(&SqliteBackend::new(&url)).expect("load legacy");
+    assert!(loaded.get_memory(&id).is_some());
+    let other = SqliteBackend::new(&url).with_agent_id("other");
+    assert!(MemoryStore::load(&other).expect("load other").get_memory(&id).is_none());
+
+    fs::remove_file(&path).expect("cleanup");
+}
+
+#[test]
+fn test_sqlite_backend_reuses_pool_inside_a_runtime() {
+    let (path, url) = temp_db();
+    let mut store = MemoryStore::default();
+    let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        let backend = SqliteBackend::new(&url);
+        for _ in 0..3 {
+            store.save(&backend).expect("save");
+            assert!(MemoryStore::load(&backend).expect("load").get_memory(&id).is_some());
+        }
+        assert_eq!(backend.list_agents().unwrap(), vec![SqliteBackend::DEFAULT_AGENT_ID]);
+    });
+
+    fs::remove_file(&path).expect("cleanup");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sqlite_backend_async_round_trip() {
+    let (path, url) = temp_db();
+    let backend = SqliteBackend::new(&url).with_agent_id("async");
+    let mut store = MemoryStore::default();
+    let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.3, 25.0, 1.0));
+
+    store.save_async(&backend).await.expect("save");
+    let loaded = MemoryStore::load_async(&backend).await.expect("load");
+    assert_eq!(loaded.get_memory(&id).unwrap().emotion, 0.3);
+
+    // Data written asynchronously is visible to the blocking API.
+    let blocking = SqliteBackend::new(&url).with_agent_id("async");
+    assert!(MemoryStore::load(&blocking).expect("load").get_memory(&id).is_some());
+
+    drop(backend);
+    drop(blocking);
+    fs::remove_file(&path).expect("cleanup");
+}
+
+#[test]
+fn test_sqlite_backend_async_pool_outlives_caller_runtimes() {
+    let (path, url) = temp_db();
+    let backend = SqliteBackend::new(&url);
+    let mut store = MemoryStore::default();
+    let id = store.add_memory(Memory::new(vec![0.1, 0.2], 0.0, 25.0, 1.0));
+
+    // The pool is created by the first call; it must keep working after the
+    // runtime that made that call is gone.
+    for _ in 0..2 {
+        let caller = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        caller.block_on(async {
+            store.save_async(&backend).await.expect("save");
+            assert!(MemoryStore::load_async(&backend).await.expect("load").get_memory(&id).is_some());
+        });
+    }
+    assert!(MemoryStore::load(&backend).expect("load").get_memory(&id).is_some());
+
+    drop(backend);
+    fs::remove_file(&path).expect("cleanup");
+}